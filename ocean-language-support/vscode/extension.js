@@ -0,0 +1,15 @@
+const vscode = require('vscode');
+
+// The debug adapter is built into the ocean executable so we only have to tell vscode how to start it
+function activate(context) {
+  context.subscriptions.push(vscode.debug.registerDebugAdapterDescriptorFactory('hydro', {
+    createDebugAdapterDescriptor() {
+      const executable = vscode.workspace.getConfiguration('hydro').get('debugger.path', 'ocean');
+      return new vscode.DebugAdapterExecutable(executable, ['hydro', 'debug', '--dap']);
+    }
+  }));
+}
+
+function deactivate() {}
+
+module.exports = { activate, deactivate };
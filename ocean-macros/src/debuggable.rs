@@ -7,9 +7,9 @@ pub fn debuggable_macro(ast: &syn::DeriveInput) -> TokenStream {
     impl Debuggable for #name {
       fn debug(&self, compilation_unit: &CompilationUnit, context: &mut ExecutionContext, debug_context: &mut DebugContext) -> Result<bool, Exception> {
         let metric_name = stringify!(#name).to_lowercase();
-        debug_context.metric_tracker.start(context.get_call_stack(), metric_name.clone());
+        debug_context.start_metric(context, &metric_name);
         let result = self.execute(compilation_unit, context);
-        debug_context.metric_tracker.stop(context.get_call_stack(), metric_name);
+        debug_context.stop_metric(context, &metric_name);
         return result;
       }
     }
@@ -1,5 +1,10 @@
-//TODO pub mod analyzer;
+pub mod analyzer;
+pub mod breakpoint;
+pub mod callgraph;
+pub mod controlflowgraph;
+pub mod coverage;
 mod compilationunit;
+pub mod debugadapter;
 pub mod debugcontext;
 mod debuggable;
 pub mod exception;
@@ -7,10 +12,20 @@ mod executable;
 mod executioncontext;
 pub mod frontend;
 pub mod function;
+pub mod inspector;
 mod instruction;
 mod intrinsic;
 pub mod layouttemplate;
+pub mod linter;
 pub mod module;
+pub mod optimizer;
+pub mod profiler;
+pub mod recording;
+pub mod reload;
+pub mod sampler;
+pub mod sourcelocation;
+mod testing;
+pub mod trace;
 pub mod value;
 mod visualizer;
 
@@ -1 +1,90 @@
-pub struct AnalysisContext {}
+use crate::hydro::analyzer::analysisvalue::AnalysisValue;
+use crate::hydro::compilationunit::CompilationUnit;
+use crate::hydro::function::Function;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnalysisState {
+  pub stack: Vec<AnalysisValue>,
+  pub variables: HashMap<String, AnalysisValue>,
+}
+
+impl AnalysisState {
+  // stacks are lined up from the top since that is the part the next instructions will read
+  fn combine(&self, other: &Self, combine_value: fn(&AnalysisValue, &AnalysisValue) -> AnalysisValue) -> Self {
+    let length = self.stack.len().min(other.stack.len());
+    let stack = self.stack[self.stack.len() - length..]
+      .iter()
+      .zip(other.stack[other.stack.len() - length..].iter())
+      .map(|(left, right)| combine_value(left, right))
+      .collect::<Vec<AnalysisValue>>();
+
+    let mut variables = self.variables.clone();
+    for (name, value) in &other.variables {
+      let combined = match self.variables.get(name) {
+        Some(existing) => combine_value(existing, value),
+        None => value.clone(),
+      };
+      variables.insert(name.clone(), combined);
+    }
+
+    Self { stack, variables }
+  }
+
+  pub fn join(&self, other: &Self) -> Self {
+    self.combine(other, AnalysisValue::join)
+  }
+
+  pub fn widen(&self, other: &Self) -> Self {
+    self.combine(other, AnalysisValue::widen)
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AnalysisWarningKind {
+  DivisorMayBeZero,
+  BranchAlwaysTaken,
+  IndexMayBeOutOfBounds,
+}
+
+#[derive(Debug, Clone)]
+pub struct AnalysisWarning {
+  pub kind: AnalysisWarningKind,
+  pub program_counter: usize,
+  pub message: String,
+}
+
+pub struct AnalysisContext<'a> {
+  pub compilation_unit: &'a CompilationUnit,
+  pub module_name: String,
+  pub function: &'a Function,
+  pub program_counter: usize,
+  pub state: AnalysisState,
+  // set by a branch when only one of its targets can be taken
+  pub branch_outcome: Option<bool>,
+  pub report: bool,
+  pub warnings: Vec<AnalysisWarning>,
+}
+
+impl<'a> AnalysisContext<'a> {
+  pub fn pop(&mut self) -> AnalysisValue {
+    self.state.stack.pop().unwrap_or(AnalysisValue::Unknown)
+  }
+
+  pub fn push(&mut self, value: AnalysisValue) {
+    self.state.stack.push(value);
+  }
+
+  // pads the bottom of the stack with unknown values when we lost track of how deep it is
+  pub fn ensure_depth(&mut self, depth: usize) {
+    while self.state.stack.len() < depth {
+      self.state.stack.insert(0, AnalysisValue::Unknown);
+    }
+  }
+
+  pub fn warn(&mut self, kind: AnalysisWarningKind, message: String) {
+    if self.report {
+      self.warnings.push(AnalysisWarning { kind, program_counter: self.program_counter, message });
+    }
+  }
+}
@@ -0,0 +1,553 @@
+use crate::hydro::analyzer::possiblevalue::PossibleValue;
+use crate::hydro::value::{Type, Value};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BooleanSet {
+  pub can_be_true: bool,
+  pub can_be_false: bool,
+}
+
+impl BooleanSet {
+  pub fn single(value: bool) -> Self {
+    Self { can_be_true: value, can_be_false: !value }
+  }
+
+  pub fn full() -> Self {
+    Self { can_be_true: true, can_be_false: true }
+  }
+
+  pub fn from_outcomes(outcomes: &[bool]) -> Self {
+    Self { can_be_true: outcomes.contains(&true), can_be_false: outcomes.contains(&false) }
+  }
+
+  pub fn outcomes(&self) -> Vec<bool> {
+    let mut outcomes = Vec::new();
+    if self.can_be_false {
+      outcomes.push(false);
+    }
+    if self.can_be_true {
+      outcomes.push(true);
+    }
+    outcomes
+  }
+
+  pub fn is_empty(&self) -> bool {
+    !self.can_be_true && !self.can_be_false
+  }
+}
+
+// An interval of floats plus whether the value may be NaN. NaN doesn't fit in an interval since it isn't ordered
+// against anything. An empty interval is always stored as (inf, -inf) so equal sets compare equal
+#[derive(Debug, Clone, PartialEq)]
+pub struct FloatRange {
+  pub value_type: Type,
+  pub min: f64,
+  pub max: f64,
+  pub nan: bool,
+}
+
+impl FloatRange {
+  pub fn new(value_type: Type, min: f64, max: f64, nan: bool) -> Self {
+    if min > max || min.is_nan() || max.is_nan() {
+      Self { value_type, min: f64::INFINITY, max: f64::NEG_INFINITY, nan }
+    } else {
+      Self { value_type, min, max, nan }
+    }
+  }
+
+  pub fn single(value_type: Type, value: f64) -> Self {
+    if value.is_nan() {
+      Self::new(value_type, f64::INFINITY, f64::NEG_INFINITY, true)
+    } else {
+      Self::new(value_type, value, value, false)
+    }
+  }
+
+  pub fn full(value_type: Type) -> Self {
+    Self::new(value_type, f64::NEG_INFINITY, f64::INFINITY, true)
+  }
+
+  pub fn has_interval(&self) -> bool {
+    self.min <= self.max
+  }
+
+  pub fn is_empty(&self) -> bool {
+    !self.has_interval() && !self.nan
+  }
+
+  pub fn contains_zero(&self) -> bool {
+    self.has_interval() && self.min <= 0.0 && self.max >= 0.0
+  }
+
+  pub fn single_value(&self) -> Option<f64> {
+    if self.has_interval() && self.min == self.max && !self.nan {
+      Some(self.min)
+    } else {
+      None
+    }
+  }
+
+  pub fn join(&self, other: &Self) -> Self {
+    Self::new(self.value_type.clone(), self.min.min(other.min), self.max.max(other.max), self.nan || other.nan)
+  }
+
+  pub fn meet(&self, other: &Self) -> Self {
+    Self::new(self.value_type.clone(), self.min.max(other.min), self.max.min(other.max), self.nan && other.nan)
+  }
+
+  pub fn widen(&self, other: &Self) -> Self {
+    if !self.has_interval() {
+      return self.join(other);
+    }
+    let min = if other.has_interval() && other.min < self.min { f64::NEG_INFINITY } else { self.min };
+    let max = if other.has_interval() && other.max > self.max { f64::INFINITY } else { self.max };
+    Self::new(self.value_type.clone(), min, max, self.nan || other.nan)
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArraySummary {
+  pub length: PossibleValue,
+  // every element the array could hold joined together
+  pub element: Box<AnalysisValue>,
+}
+
+// Members that are missing are unknown, so an empty member map knows nothing about the layout besides its name
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayoutSummary {
+  pub module_name: String,
+  pub layout_name: String,
+  pub members: BTreeMap<String, AnalysisValue>,
+}
+
+impl LayoutSummary {
+  pub fn member(&self, member: &str) -> AnalysisValue {
+    self.members.get(member).cloned().unwrap_or(AnalysisValue::Unknown)
+  }
+
+  pub fn set_member(&mut self, member: String, value: AnalysisValue) {
+    if value == AnalysisValue::Unknown {
+      self.members.remove(member.as_str());
+    } else {
+      self.members.insert(member, value);
+    }
+  }
+
+  fn same_layout(&self, other: &Self) -> bool {
+    self.module_name == other.module_name && self.layout_name == other.layout_name
+  }
+}
+
+// The abstract values the analyzer tracks. Bottom is a value that can't exist, like the result of code that always
+// throws, and Unknown could be anything
+#[derive(Debug, Clone, PartialEq)]
+pub enum AnalysisValue {
+  Bottom,
+  Unknown,
+  // integers
+  Range(PossibleValue),
+  Boolean(BooleanSet),
+  Float(FloatRange),
+  Array(ArraySummary),
+  Layout(LayoutSummary),
+  // values like function pointers and references that we only track when they are exactly known
+  Constant(Value),
+}
+
+impl AnalysisValue {
+  pub fn from_value(value: &Value) -> Self {
+    match value {
+      Value::Boolean(x) => AnalysisValue::Boolean(BooleanSet::single(*x)),
+      Value::Unsigned8(_)
+      | Value::Unsigned16(_)
+      | Value::Unsigned32(_)
+      | Value::Unsigned64(_)
+      | Value::Unsigned128(_)
+      | Value::Signed8(_)
+      | Value::Signed16(_)
+      | Value::Signed32(_)
+      | Value::Signed64(_)
+      | Value::Signed128(_) => AnalysisValue::Range(PossibleValue::single(value.clone())),
+      Value::Float32(x) => AnalysisValue::Float(FloatRange::single(Type::Float32, *x as f64)),
+      Value::Float64(x) => AnalysisValue::Float(FloatRange::single(Type::Float64, *x)),
+      Value::Array(array) => AnalysisValue::Array(ArraySummary {
+        length: PossibleValue::single(*array.length.clone()),
+        element: Box::new(array.values.iter().fold(AnalysisValue::Bottom, |element, value| AnalysisValue::join(&element, &AnalysisValue::from_value(value)))),
+      }),
+      Value::Layout(layout) => {
+        let mut summary = LayoutSummary { module_name: layout.module_name.clone(), layout_name: layout.layout_name.clone(), members: BTreeMap::new() };
+        for (member, value) in &layout.values {
+          summary.set_member(member.clone(), AnalysisValue::from_value(value));
+        }
+        AnalysisValue::Layout(summary)
+      }
+      Value::FunctionPointer(_) | Value::Reference(_) => AnalysisValue::Constant(value.clone()),
+    }
+  }
+
+  // every value the type allows
+  pub fn from_type(value_type: &Type) -> Self {
+    match value_type {
+      Type::Boolean => AnalysisValue::Boolean(BooleanSet::full()),
+      Type::Unsigned8
+      | Type::Unsigned16
+      | Type::Unsigned32
+      | Type::Unsigned64
+      | Type::Unsigned128
+      | Type::Signed8
+      | Type::Signed16
+      | Type::Signed32
+      | Type::Signed64
+      | Type::Signed128 => AnalysisValue::Range(PossibleValue::full(value_type.clone())),
+      Type::Float32 | Type::Float64 => AnalysisValue::Float(FloatRange::full(value_type.clone())),
+      Type::Array(length, subtype) => AnalysisValue::Array(ArraySummary {
+        length: match length {
+          Some(length) => PossibleValue::single(Value::Unsigned64(*length)),
+          None => PossibleValue::full(Type::Unsigned64),
+        },
+        element: Box::new(AnalysisValue::from_type(subtype)),
+      }),
+      Type::Layout(module_name, layout_name, _) => AnalysisValue::Layout(LayoutSummary { module_name: module_name.clone(), layout_name: layout_name.clone(), members: BTreeMap::new() }),
+      _ => AnalysisValue::Unknown,
+    }
+  }
+
+  // the value a freshly allocated variable of the type holds when we can work it out without resolving layouts
+  pub fn from_default(value_type: &Type) -> Self {
+    match value_type {
+      Type::Array(Some(length), subtype) => AnalysisValue::Array(ArraySummary {
+        length: PossibleValue::single(Value::Unsigned64(*length)),
+        element: Box::new(if *length == 0 { AnalysisValue::Bottom } else { AnalysisValue::from_default(subtype) }),
+      }),
+      Type::Array(None, subtype) => AnalysisValue::Array(ArraySummary { length: PossibleValue::single(Value::Unsigned64(0)), element: Box::new(AnalysisValue::Bottom) }),
+      Type::Layout(_, _, _) | Type::Any | Type::Void | Type::FunctionPointer(_, _) | Type::Reference(_) => AnalysisValue::from_type(value_type),
+      _ => AnalysisValue::from_value(&value_type.default()),
+    }
+  }
+
+  pub fn value_type(&self) -> Option<Type> {
+    match self {
+      AnalysisValue::Range(range) => range.value_type(),
+      AnalysisValue::Boolean(_) => Some(Type::Boolean),
+      AnalysisValue::Float(range) => Some(range.value_type.clone()),
+      _ => None,
+    }
+  }
+
+  // the concrete value when only one is possible
+  pub fn single_value(&self) -> Option<Value> {
+    match self {
+      AnalysisValue::Range(range) => range.single_value(),
+      AnalysisValue::Boolean(set) => match set.outcomes().as_slice() {
+        [only] => Some(Value::Boolean(*only)),
+        _ => None,
+      },
+      AnalysisValue::Float(range) => match (range.single_value(), &range.value_type) {
+        (Some(value), Type::Float32) => Some(Value::Float32(value as f32)),
+        (Some(value), _) => Some(Value::Float64(value)),
+        (None, _) => None,
+      },
+      AnalysisValue::Constant(value) => Some(value.clone()),
+      _ => None,
+    }
+  }
+
+  // empty sets become Bottom so there is only one way to write a value that can't exist
+  fn canonical(self) -> Self {
+    let is_empty = match &self {
+      AnalysisValue::Range(range) => range.is_empty(),
+      AnalysisValue::Boolean(set) => set.is_empty(),
+      AnalysisValue::Float(range) => range.is_empty(),
+      AnalysisValue::Array(array) => array.length.is_empty(),
+      _ => false,
+    };
+    if is_empty {
+      AnalysisValue::Bottom
+    } else {
+      self
+    }
+  }
+
+  // the smallest value holding everything either side could be
+  pub fn join(left: &Self, right: &Self) -> Self {
+    match (left, right) {
+      (AnalysisValue::Bottom, other) | (other, AnalysisValue::Bottom) => other.clone(),
+      (AnalysisValue::Unknown, _) | (_, AnalysisValue::Unknown) => AnalysisValue::Unknown,
+      (AnalysisValue::Range(left), AnalysisValue::Range(right)) if left.value_type() == right.value_type() => AnalysisValue::Range(PossibleValue::union(left.clone(), right.clone())),
+      (AnalysisValue::Boolean(left), AnalysisValue::Boolean(right)) => {
+        AnalysisValue::Boolean(BooleanSet { can_be_true: left.can_be_true || right.can_be_true, can_be_false: left.can_be_false || right.can_be_false })
+      }
+      (AnalysisValue::Float(left), AnalysisValue::Float(right)) if left.value_type == right.value_type => AnalysisValue::Float(left.join(right)),
+      (AnalysisValue::Array(left), AnalysisValue::Array(right)) => AnalysisValue::Array(ArraySummary {
+        length: PossibleValue::union(left.length.clone(), right.length.clone()),
+        element: Box::new(AnalysisValue::join(&left.element, &right.element)),
+      }),
+      (AnalysisValue::Layout(left), AnalysisValue::Layout(right)) if left.same_layout(right) => {
+        let mut summary = LayoutSummary { module_name: left.module_name.clone(), layout_name: left.layout_name.clone(), members: BTreeMap::new() };
+        for (member, value) in &left.members {
+          if let Some(other) = right.members.get(member) {
+            summary.set_member(member.clone(), AnalysisValue::join(value, other));
+          }
+        }
+        AnalysisValue::Layout(summary)
+      }
+      (AnalysisValue::Constant(left), AnalysisValue::Constant(right)) if left == right => AnalysisValue::Constant(left.clone()),
+      _ => AnalysisValue::Unknown,
+    }
+  }
+
+  // the largest value holding only what both sides could be
+  pub fn meet(left: &Self, right: &Self) -> Self {
+    let result = match (left, right) {
+      (AnalysisValue::Bottom, _) | (_, AnalysisValue::Bottom) => AnalysisValue::Bottom,
+      (AnalysisValue::Unknown, other) | (other, AnalysisValue::Unknown) => other.clone(),
+      (AnalysisValue::Range(left), AnalysisValue::Range(right)) if left.value_type() == right.value_type() => AnalysisValue::Range(PossibleValue::intersect(left.clone(), right.clone())),
+      (AnalysisValue::Boolean(left), AnalysisValue::Boolean(right)) => {
+        AnalysisValue::Boolean(BooleanSet { can_be_true: left.can_be_true && right.can_be_true, can_be_false: left.can_be_false && right.can_be_false })
+      }
+      (AnalysisValue::Float(left), AnalysisValue::Float(right)) if left.value_type == right.value_type => AnalysisValue::Float(left.meet(right)),
+      (AnalysisValue::Array(left), AnalysisValue::Array(right)) => AnalysisValue::Array(ArraySummary {
+        length: PossibleValue::intersect(left.length.clone(), right.length.clone()),
+        element: Box::new(AnalysisValue::meet(&left.element, &right.element)),
+      }),
+      (AnalysisValue::Layout(left), AnalysisValue::Layout(right)) if left.same_layout(right) => {
+        let mut summary = left.clone();
+        for (member, value) in &right.members {
+          summary.set_member(member.clone(), AnalysisValue::meet(&left.member(member), value));
+        }
+        AnalysisValue::Layout(summary)
+      }
+      (AnalysisValue::Constant(left), AnalysisValue::Constant(right)) if left == right => AnalysisValue::Constant(left.clone()),
+      _ => AnalysisValue::Bottom,
+    };
+    result.canonical()
+  }
+
+  // like join but bounds that keep moving jump to the edge of their type so loops reach a fixpoint
+  pub fn widen(old: &Self, new: &Self) -> Self {
+    match (old, new) {
+      (AnalysisValue::Range(old), AnalysisValue::Range(new)) if old.value_type() == new.value_type() => AnalysisValue::Range(PossibleValue::widen(old, new)),
+      (AnalysisValue::Float(old), AnalysisValue::Float(new)) if old.value_type == new.value_type => AnalysisValue::Float(old.widen(new)),
+      (AnalysisValue::Array(old), AnalysisValue::Array(new)) => AnalysisValue::Array(ArraySummary {
+        length: PossibleValue::widen(&old.length, &new.length),
+        element: Box::new(AnalysisValue::widen(&old.element, &new.element)),
+      }),
+      (AnalysisValue::Layout(old), AnalysisValue::Layout(new)) if old.same_layout(new) => {
+        let mut summary = LayoutSummary { module_name: old.module_name.clone(), layout_name: old.layout_name.clone(), members: BTreeMap::new() };
+        for (member, value) in &old.members {
+          if let Some(other) = new.members.get(member) {
+            summary.set_member(member.clone(), AnalysisValue::widen(value, other));
+          }
+        }
+        AnalysisValue::Layout(summary)
+      }
+      _ => AnalysisValue::join(old, new),
+    }
+  }
+
+  pub fn is_subset(&self, other: &Self) -> bool {
+    AnalysisValue::join(self, other) == *other
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::hydro::analyzer::analysisvalue::{AnalysisValue, ArraySummary, BooleanSet, FloatRange, LayoutSummary};
+  use crate::hydro::analyzer::possiblevalue::PossibleValue;
+  use crate::hydro::value::{Type, Value};
+  use std::collections::BTreeMap;
+
+  const CASES: usize = 500;
+
+  // xorshift so the property tests are repeatable without pulling in a crate for it
+  struct Generator {
+    state: u64,
+  }
+
+  impl Generator {
+    fn new(seed: u64) -> Self {
+      Self { state: seed }
+    }
+
+    fn next(&mut self) -> u64 {
+      self.state ^= self.state << 13;
+      self.state ^= self.state >> 7;
+      self.state ^= self.state << 17;
+      self.state
+    }
+
+    fn below(&mut self, bound: u64) -> u64 {
+      self.next() % bound
+    }
+
+    fn chance(&mut self) -> bool {
+      self.below(2) == 0
+    }
+
+    // small bounds so generated ranges overlap and touch often
+    fn integer_range(&mut self) -> PossibleValue {
+      let ranges = (0..1 + self.below(3))
+        .map(|_| {
+          let min = self.below(20) as i32 - 10;
+          let max = min + self.below(8) as i32;
+          (true, Value::Signed32(min), Value::Signed32(max), true)
+        })
+        .collect();
+      PossibleValue::from_ranges(ranges)
+    }
+
+    fn float_range(&mut self) -> FloatRange {
+      let bounds = [f64::NEG_INFINITY, -2.5, -1.0, 0.0, 0.5, 3.0, f64::INFINITY];
+      let a = bounds[self.below(bounds.len() as u64) as usize];
+      let b = bounds[self.below(bounds.len() as u64) as usize];
+      let range = FloatRange::new(Type::Float64, a.min(b), a.max(b), self.chance());
+      if self.below(5) == 0 {
+        FloatRange::new(Type::Float64, f64::INFINITY, f64::NEG_INFINITY, true)
+      } else {
+        range
+      }
+    }
+
+    fn value(&mut self, depth: usize) -> AnalysisValue {
+      let choices = if depth == 0 { 6 } else { 8 };
+      match self.below(choices) {
+        0 => AnalysisValue::Bottom,
+        1 => AnalysisValue::Unknown,
+        2 => AnalysisValue::Range(self.integer_range()),
+        3 => match self.below(3) {
+          0 => AnalysisValue::Boolean(BooleanSet::single(true)),
+          1 => AnalysisValue::Boolean(BooleanSet::single(false)),
+          _ => AnalysisValue::Boolean(BooleanSet::full()),
+        },
+        4 => AnalysisValue::Float(self.float_range()),
+        5 => AnalysisValue::Constant(Value::Unsigned8(self.below(2) as u8)),
+        6 => {
+          let min = self.below(4);
+          let length = PossibleValue::range_inc_inc(Value::Unsigned64(min), Value::Unsigned64(min + self.below(4)));
+          AnalysisValue::Array(ArraySummary { length, element: Box::new(self.value(depth - 1)) })
+        }
+        _ => {
+          let mut members = BTreeMap::new();
+          for member in ["x", "y", "z"] {
+            if self.chance() {
+              let value = self.value(depth - 1);
+              if value != AnalysisValue::Unknown {
+                members.insert(member.to_string(), value);
+              }
+            }
+          }
+          AnalysisValue::Layout(LayoutSummary { module_name: "main".to_string(), layout_name: "Point".to_string(), members })
+        }
+      }
+    }
+  }
+
+  fn check_triples(seed: u64, property: fn(&AnalysisValue, &AnalysisValue, &AnalysisValue)) {
+    let mut generator = Generator::new(seed);
+    for _ in 0..CASES {
+      let a = generator.value(2);
+      let b = generator.value(2);
+      let c = generator.value(2);
+      property(&a, &b, &c);
+    }
+  }
+
+  #[test]
+  fn join_is_commutative_associative_and_idempotent() {
+    check_triples(0x9E3779B97F4A7C15, |a, b, c| {
+      assert_eq!(AnalysisValue::join(a, b), AnalysisValue::join(b, a), "{:?} join {:?}", a, b);
+      assert_eq!(AnalysisValue::join(&AnalysisValue::join(a, b), c), AnalysisValue::join(a, &AnalysisValue::join(b, c)), "{:?} join {:?} join {:?}", a, b, c);
+      assert_eq!(AnalysisValue::join(a, a), *a, "{:?} join itself", a);
+    });
+  }
+
+  #[test]
+  fn meet_is_commutative_associative_and_idempotent() {
+    check_triples(0xD1B54A32D192ED03, |a, b, c| {
+      assert_eq!(AnalysisValue::meet(a, b), AnalysisValue::meet(b, a), "{:?} meet {:?}", a, b);
+      assert_eq!(AnalysisValue::meet(&AnalysisValue::meet(a, b), c), AnalysisValue::meet(a, &AnalysisValue::meet(b, c)), "{:?} meet {:?} meet {:?}", a, b, c);
+      assert_eq!(AnalysisValue::meet(a, a), *a, "{:?} meet itself", a);
+    });
+  }
+
+  #[test]
+  fn join_and_meet_absorb() {
+    check_triples(0x2545F4914F6CDD1D, |a, b, _| {
+      assert_eq!(AnalysisValue::join(a, &AnalysisValue::meet(a, b)), *a, "{:?} join ({:?} meet {:?})", a, a, b);
+      assert_eq!(AnalysisValue::meet(a, &AnalysisValue::join(a, b)), *a, "{:?} meet ({:?} join {:?})", a, a, b);
+    });
+  }
+
+  #[test]
+  fn bottom_and_unknown_are_the_identities() {
+    check_triples(0x5851F42D4C957F2D, |a, _, _| {
+      assert_eq!(AnalysisValue::join(a, &AnalysisValue::Bottom), *a);
+      assert_eq!(AnalysisValue::meet(a, &AnalysisValue::Unknown), *a);
+      assert_eq!(AnalysisValue::join(a, &AnalysisValue::Unknown), AnalysisValue::Unknown);
+      assert_eq!(AnalysisValue::meet(a, &AnalysisValue::Bottom), AnalysisValue::Bottom);
+    });
+  }
+
+  #[test]
+  fn widen_is_an_upper_bound() {
+    check_triples(0x14057B7EF767814F, |a, b, _| {
+      let widened = AnalysisValue::widen(a, b);
+      assert!(a.is_subset(&widened), "{:?} is not inside {:?} widen {:?} = {:?}", a, a, b, widened);
+      assert!(b.is_subset(&widened), "{:?} is not inside {:?} widen {:?} = {:?}", b, a, b, widened);
+    });
+  }
+
+  #[test]
+  fn widen_chains_stabilize() {
+    let mut generator = Generator::new(0x94D049BB133111EB);
+    for _ in 0..CASES {
+      let mut value = generator.value(2);
+      let mut steps = 0;
+      loop {
+        let next = AnalysisValue::widen(&value, &AnalysisValue::join(&value, &generator.value(2)));
+        if next == value {
+          break;
+        }
+        value = next;
+        steps += 1;
+        assert!(steps < 20, "widening did not stabilize, reached {:?}", value);
+      }
+    }
+  }
+
+  #[test]
+  fn integer_sets_match_their_members() {
+    let mut generator = Generator::new(0xBF58476D1CE4E5B9);
+    for _ in 0..CASES {
+      let a = generator.integer_range();
+      let b = generator.integer_range();
+      let union = PossibleValue::union(a.clone(), b.clone());
+      let intersection = PossibleValue::intersect(a.clone(), b.clone());
+      let complement = PossibleValue::complement(a.clone());
+      for x in -12..20 {
+        let value = Value::Signed32(x);
+        let (in_a, in_b) = (a.contains(value.clone()), b.contains(value.clone()));
+        assert_eq!(union.contains(value.clone()), in_a || in_b, "{} in {:?} union {:?}", x, a, b);
+        assert_eq!(intersection.contains(value.clone()), in_a && in_b, "{} in {:?} intersect {:?}", x, a, b);
+        assert_eq!(complement.contains(value), !in_a, "{} in complement of {:?}", x, a);
+      }
+    }
+  }
+
+  #[test]
+  fn intersect_keeps_the_overlap() {
+    let a = PossibleValue::range_inc_inc(Value::Signed32(0), Value::Signed32(10));
+    let b = PossibleValue::range_inc_inc(Value::Signed32(5), Value::Signed32(20));
+    assert_eq!(PossibleValue::intersect(a, b), PossibleValue::range_inc_inc(Value::Signed32(5), Value::Signed32(10)));
+  }
+
+  #[test]
+  fn nan_is_kept_apart_from_the_interval() {
+    let nan = FloatRange::single(Type::Float64, f64::NAN);
+    let number = FloatRange::single(Type::Float64, 1.0);
+    assert!(!nan.is_empty() && !nan.has_interval());
+    assert_eq!(nan.join(&number), FloatRange::new(Type::Float64, 1.0, 1.0, true));
+    assert_eq!(AnalysisValue::meet(&AnalysisValue::Float(nan), &AnalysisValue::Float(number)), AnalysisValue::Bottom);
+  }
+}
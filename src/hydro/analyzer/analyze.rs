@@ -1,5 +1,746 @@
-use crate::hydro::analyzer::analysiscontext::AnalysisContext;
+use crate::hydro::analyzer::analysiscontext::{AnalysisContext, AnalysisWarningKind};
+use crate::hydro::analyzer::analysisvalue::{AnalysisValue, ArraySummary, BooleanSet, FloatRange};
+use crate::hydro::analyzer::boundscheck::{classify_index, BoundsClassification};
+use crate::hydro::analyzer::possiblevalue::PossibleValue;
+use crate::hydro::function::Target;
+use crate::hydro::instruction::*;
+use crate::hydro::optimizer::constantfolding::ConstantFolding;
+use crate::hydro::value::{Reference, Type, Value};
 
 pub trait Analyzable {
   fn analyze(&self, context: &mut AnalysisContext);
 }
+
+impl Analyzable for Instruction {
+  fn analyze(&self, context: &mut AnalysisContext) {
+    match self {
+      Instruction::PushValue(x) => x.analyze(context),
+      Instruction::PopValue(x) => x.analyze(context),
+      Instruction::Duplicate(x) => x.analyze(context),
+      Instruction::Swap(x) => x.analyze(context),
+      Instruction::Rotate(x) => x.analyze(context),
+      Instruction::Add(x) => x.analyze(context),
+      Instruction::Subtract(x) => x.analyze(context),
+      Instruction::Multiply(x) => x.analyze(context),
+      Instruction::Divide(x) => x.analyze(context),
+      Instruction::Modulo(x) => x.analyze(context),
+      Instruction::LeftShift(x) => x.analyze(context),
+      Instruction::RightShift(x) => x.analyze(context),
+      Instruction::BitwiseAnd(x) => x.analyze(context),
+      Instruction::BitwiseOr(x) => x.analyze(context),
+      Instruction::BitwiseXor(x) => x.analyze(context),
+      Instruction::BitwiseNot(x) => x.analyze(context),
+      Instruction::And(x) => x.analyze(context),
+      Instruction::Or(x) => x.analyze(context),
+      Instruction::Xor(x) => x.analyze(context),
+      Instruction::Not(x) => x.analyze(context),
+      Instruction::Equal(x) => x.analyze(context),
+      Instruction::NotEqual(x) => x.analyze(context),
+      Instruction::LessThan(x) => x.analyze(context),
+      Instruction::GreaterThan(x) => x.analyze(context),
+      Instruction::LessThanEqual(x) => x.analyze(context),
+      Instruction::GreaterThanEqual(x) => x.analyze(context),
+      Instruction::Jump(x) => x.analyze(context),
+      Instruction::Branch(x) => x.analyze(context),
+      Instruction::Call(x) => x.analyze(context),
+      Instruction::Return(x) => x.analyze(context),
+      Instruction::Cast(x) => x.analyze(context),
+      Instruction::Load(x) => x.analyze(context),
+      Instruction::Store(x) => x.analyze(context),
+      Instruction::GetArrayIndex(x) => x.analyze(context),
+      Instruction::SetArrayIndex(x) => x.analyze(context),
+      Instruction::GetLayoutIndex(x) => x.analyze(context),
+      Instruction::SetLayoutIndex(x) => x.analyze(context),
+      Instruction::Allocate(x) => x.analyze(context),
+      Instruction::AllocateArray(x) => x.analyze(context),
+    }
+  }
+}
+
+
+// runs the real instruction when every operand is exactly known and otherwise gives every value of the result type
+fn analyze_binary(context: &mut AnalysisContext, instruction: Instruction, result_type: fn(&Type, &Type) -> Option<Type>) {
+  let b = context.pop();
+  let a = context.pop();
+  let result = match (a.single_value(), b.single_value()) {
+    (Some(a_value), Some(b_value)) if !matches!(a, AnalysisValue::Constant(_)) && !matches!(b, AnalysisValue::Constant(_)) => {
+      match ConstantFolding::fold(&instruction, vec![a_value, b_value]) {
+        Some(value) => AnalysisValue::from_value(&value),
+        None => AnalysisValue::Unknown,
+      }
+    }
+    _ => match (a.value_type(), b.value_type()) {
+      (Some(a_type), Some(b_type)) => result_type(&a_type, &b_type).map(|value_type| AnalysisValue::from_type(&value_type)).unwrap_or(AnalysisValue::Unknown),
+      _ => AnalysisValue::Unknown,
+    },
+  };
+  context.push(result);
+}
+
+// add, subtract, and multiply are monotonic in each operand so the result bounds come from the corners
+fn analyze_arithmetic(context: &mut AnalysisContext, instruction: Instruction, integer_operation: fn(i128, i128) -> Option<i128>, float_operation: fn(f64, f64) -> f64) {
+  let b = context.state.stack.last().cloned().unwrap_or(AnalysisValue::Unknown);
+  let a = context.state.stack.iter().rev().nth(1).cloned().unwrap_or(AnalysisValue::Unknown);
+  let result = match (&a, &b) {
+    (AnalysisValue::Range(a), AnalysisValue::Range(b)) if a.single_value().is_none() || b.single_value().is_none() => {
+      match (a.value_type(), a.integer_bounds(), b.integer_bounds()) {
+        (Some(value_type), Some((a_min, a_max)), Some((b_min, b_max))) if a.value_type() == b.value_type() => {
+          let corners = [integer_operation(a_min, b_min), integer_operation(a_min, b_max), integer_operation(a_max, b_min), integer_operation(a_max, b_max)];
+          Some(match corners.iter().cloned().collect::<Option<Vec<i128>>>() {
+            Some(corners) => PossibleValue::from_integer_bounds(value_type, corners.iter().min().cloned(), corners.iter().max().cloned()),
+            None => PossibleValue::full(value_type),
+          })
+          .map(AnalysisValue::Range)
+        }
+        _ => None,
+      }
+    }
+    (AnalysisValue::Float(a), AnalysisValue::Float(b)) if a.value_type == b.value_type && (a.single_value().is_none() || b.single_value().is_none()) => {
+      Some(AnalysisValue::Float(float_corners(a, b, float_operation)))
+    }
+    _ => None,
+  };
+
+  match result {
+    Some(result) => {
+      context.pop();
+      context.pop();
+      context.push(result);
+    }
+    None => analyze_binary(context, instruction, same_numeric_type),
+  }
+}
+
+// float operations are monotonic between the special cases that give NaN, which the corners will show
+fn float_corners(a: &FloatRange, b: &FloatRange, operation: fn(f64, f64) -> f64) -> FloatRange {
+  if !a.has_interval() || !b.has_interval() {
+    return FloatRange::new(a.value_type.clone(), f64::INFINITY, f64::NEG_INFINITY, true);
+  }
+  let corners = [operation(a.min, b.min), operation(a.min, b.max), operation(a.max, b.min), operation(a.max, b.max)];
+  let nan = a.nan || b.nan || corners.iter().any(|corner| corner.is_nan());
+  let numbers = corners.iter().filter(|corner| !corner.is_nan()).cloned().collect::<Vec<f64>>();
+  if nan && numbers.len() < corners.len() {
+    // something like inf - inf happened somewhere in the range so anything in between could too
+    return FloatRange::full(a.value_type.clone());
+  }
+  FloatRange::new(a.value_type.clone(), numbers.iter().cloned().fold(f64::INFINITY, f64::min), numbers.iter().cloned().fold(f64::NEG_INFINITY, f64::max), nan)
+}
+
+fn analyze_division(context: &mut AnalysisContext, instruction: Instruction) {
+  let divisor = context.state.stack.last().cloned().unwrap_or(AnalysisValue::Unknown);
+  let dividend = context.state.stack.iter().rev().nth(1).cloned().unwrap_or(AnalysisValue::Unknown);
+  match &divisor {
+    // integer division by zero stops the vm but float division by zero just gives infinity or NaN
+    AnalysisValue::Range(divisor) => {
+      if let Some((min, max)) = divisor.integer_bounds() {
+        if min == 0 && max == 0 {
+          context.warn(AnalysisWarningKind::DivisorMayBeZero, "Divisor is always zero".to_string());
+        } else if min <= 0 && max >= 0 {
+          context.warn(AnalysisWarningKind::DivisorMayBeZero, "Divisor may be zero".to_string());
+        }
+      }
+    }
+    AnalysisValue::Float(divisor_range) => {
+      if let (AnalysisValue::Float(dividend_range), Instruction::Divide(_)) = (&dividend, &instruction) {
+        if !divisor_range.contains_zero() && dividend_range.value_type == divisor_range.value_type && divisor.single_value().is_none() {
+          let result = float_corners(dividend_range, divisor_range, |a, b| a / b);
+          context.pop();
+          context.pop();
+          context.push(AnalysisValue::Float(result));
+          return;
+        }
+      }
+    }
+    _ => {}
+  }
+  analyze_binary(context, instruction, same_numeric_type);
+}
+
+#[derive(Clone, Copy)]
+enum Comparison {
+  Equal,
+  NotEqual,
+  LessThan,
+  GreaterThan,
+  LessThanEqual,
+  GreaterThanEqual,
+}
+
+impl Comparison {
+  // (can be true, can be false) for any a in a_bounds and b in b_bounds
+  fn outcomes<T: PartialOrd>(&self, (a_min, a_max): (T, T), (b_min, b_max): (T, T)) -> (bool, bool) {
+    let overlap = a_min <= b_max && b_min <= a_max;
+    let always_equal = a_min == a_max && b_min == b_max && a_min == b_min;
+    match self {
+      Comparison::Equal => (overlap, !always_equal),
+      Comparison::NotEqual => (!always_equal, overlap),
+      Comparison::LessThan => (a_min < b_max, a_max >= b_min),
+      Comparison::GreaterThan => (a_max > b_min, a_min <= b_max),
+      Comparison::LessThanEqual => (a_min <= b_max, a_max > b_min),
+      Comparison::GreaterThanEqual => (a_max >= b_min, a_min < b_max),
+    }
+  }
+
+  fn compare<T: PartialOrd>(&self, a: T, b: T) -> bool {
+    match self {
+      Comparison::Equal => a == b,
+      Comparison::NotEqual => a != b,
+      Comparison::LessThan => a < b,
+      Comparison::GreaterThan => a > b,
+      Comparison::LessThanEqual => a <= b,
+      Comparison::GreaterThanEqual => a >= b,
+    }
+  }
+}
+
+fn analyze_comparison(context: &mut AnalysisContext, comparison: Comparison) {
+  let b = context.pop();
+  let a = context.pop();
+  let outcomes = match (&a, &b) {
+    (AnalysisValue::Range(a), AnalysisValue::Range(b)) if a.value_type() == b.value_type() => match (a.integer_bounds(), b.integer_bounds()) {
+      (Some(a_bounds), Some(b_bounds)) => comparison.outcomes(a_bounds, b_bounds),
+      _ => (true, true),
+    },
+    (AnalysisValue::Boolean(a), AnalysisValue::Boolean(b)) => {
+      let outcomes = a.outcomes().iter().flat_map(|a| b.outcomes().into_iter().map(move |b| comparison.compare(*a, b))).collect::<Vec<bool>>();
+      (outcomes.contains(&true), outcomes.contains(&false))
+    }
+    // every comparison with NaN is false except for not equal
+    (AnalysisValue::Float(a), AnalysisValue::Float(b)) if a.value_type == b.value_type => {
+      let (mut can_be_true, mut can_be_false) = if a.has_interval() && b.has_interval() { comparison.outcomes((a.min, a.max), (b.min, b.max)) } else { (false, false) };
+      if a.nan || b.nan {
+        match comparison {
+          Comparison::NotEqual => can_be_true = true,
+          _ => can_be_false = true,
+        }
+      }
+      (can_be_true, can_be_false)
+    }
+    _ => (true, true),
+  };
+  context.push(AnalysisValue::Boolean(BooleanSet { can_be_true: outcomes.0, can_be_false: outcomes.1 }));
+}
+
+fn boolean_values(value: &AnalysisValue) -> Vec<bool> {
+  match value {
+    AnalysisValue::Boolean(set) => set.outcomes(),
+    _ => vec![false, true],
+  }
+}
+
+fn analyze_logical(context: &mut AnalysisContext, operation: fn(bool, bool) -> bool) {
+  let b = boolean_values(&context.pop());
+  let a = boolean_values(&context.pop());
+  let outcomes = a.iter().flat_map(|a| b.iter().map(move |b| operation(*a, *b))).collect::<Vec<bool>>();
+  context.push(AnalysisValue::Boolean(BooleanSet::from_outcomes(&outcomes)));
+}
+
+fn same_numeric_type(a: &Type, b: &Type) -> Option<Type> {
+  if a == b && *a != Type::Boolean {
+    Some(a.clone())
+  } else {
+    None
+  }
+}
+
+fn same_integer_type(a: &Type, b: &Type) -> Option<Type> {
+  match a {
+    Type::Float32 | Type::Float64 => None,
+    _ => same_numeric_type(a, b),
+  }
+}
+
+fn left_type(a: &Type, _b: &Type) -> Option<Type> {
+  Some(a.clone())
+}
+
+fn check_index(context: &mut AnalysisContext, array: &AnalysisValue, index: &AnalysisValue) {
+  let (AnalysisValue::Array(array_summary), AnalysisValue::Range(index_range)) = (array, index) else {
+    return;
+  };
+  // with nothing known about the length every index would be flagged so we stay quiet
+  if array_summary.length.is_full() {
+    return;
+  }
+  let (Some((length_min, length_max)), Some((index_min, index_max))) = (array_summary.length.integer_bounds(), index_range.integer_bounds()) else {
+    return;
+  };
+  match classify_index(array, index) {
+    BoundsClassification::InBounds => {}
+    BoundsClassification::OutOfBounds => {
+      context.warn(AnalysisWarningKind::IndexMayBeOutOfBounds, format!("Index is always out of bounds (index {}..={} but length {}..={})", index_min, index_max, length_min, length_max))
+    }
+    BoundsClassification::Unknown => {
+      context.warn(AnalysisWarningKind::IndexMayBeOutOfBounds, format!("Index may be out of bounds (index {}..={} but length {}..={})", index_min, index_max, length_min, length_max))
+    }
+  }
+}
+
+// The variable a reference is rooted at and the element or member steps from there to what it refers to.
+// Indexes aren't tracked since arrays only keep a summary of their elements
+enum ReferenceStep {
+  Element,
+  Member(String),
+}
+
+fn reference_path(reference: &AnalysisValue) -> Option<(String, Vec<ReferenceStep>)> {
+  match reference {
+    AnalysisValue::Constant(Value::Reference(reference)) => reference_path_internal(reference),
+    _ => None,
+  }
+}
+
+fn reference_path_internal(reference: &Reference) -> Option<(String, Vec<ReferenceStep>)> {
+  let (base, step) = match reference {
+    Reference::Variable(variable) => return Some((variable.name.clone(), Vec::new())),
+    Reference::ArrayIndex(index_reference) => (index_reference.reference.as_ref(), ReferenceStep::Element),
+    Reference::LayoutIndex(layout_reference) => (layout_reference.reference.as_ref(), ReferenceStep::Member(layout_reference.index.clone())),
+  };
+  match base {
+    Value::Reference(base) => {
+      let (name, mut steps) = reference_path_internal(base)?;
+      steps.push(step);
+      Some((name, steps))
+    }
+    _ => None,
+  }
+}
+
+fn read_path(value: &AnalysisValue, steps: &[ReferenceStep]) -> AnalysisValue {
+  match (steps.first(), value) {
+    (None, _) => value.clone(),
+    (Some(ReferenceStep::Element), AnalysisValue::Array(array)) => read_path(&array.element, &steps[1..]),
+    (Some(ReferenceStep::Member(member)), AnalysisValue::Layout(layout)) => read_path(&layout.member(member), &steps[1..]),
+    _ => AnalysisValue::Unknown,
+  }
+}
+
+// Writing through a reference replaces the variable or layout member it names. An array element only gets joined
+// into the element summary since the other elements keep their values
+fn write_path(value: &AnalysisValue, steps: &[ReferenceStep], written: AnalysisValue) -> AnalysisValue {
+  match (steps.first(), value) {
+    (None, _) => written,
+    (Some(ReferenceStep::Element), AnalysisValue::Array(array)) => {
+      let element = write_path(&array.element, &steps[1..], written);
+      AnalysisValue::Array(ArraySummary { length: array.length.clone(), element: Box::new(AnalysisValue::join(&array.element, &element)) })
+    }
+    (Some(ReferenceStep::Member(member)), AnalysisValue::Layout(layout)) => {
+      let mut layout = layout.clone();
+      let member_value = write_path(&layout.member(member), &steps[1..], written);
+      layout.set_member(member.clone(), member_value);
+      AnalysisValue::Layout(layout)
+    }
+    _ => AnalysisValue::Unknown,
+  }
+}
+
+impl Analyzable for Push {
+  fn analyze(&self, context: &mut AnalysisContext) {
+    context.push(AnalysisValue::from_value(&self.value));
+  }
+}
+
+impl Analyzable for Pop {
+  fn analyze(&self, context: &mut AnalysisContext) {
+    context.pop();
+  }
+}
+
+impl Analyzable for Duplicate {
+  fn analyze(&self, context: &mut AnalysisContext) {
+    context.ensure_depth(1 + self.offset);
+    let value = context.pop();
+    let position = context.state.stack.len() - self.offset;
+    context.state.stack.insert(position, value.clone());
+    context.push(value);
+  }
+}
+
+impl Analyzable for Swap {
+  fn analyze(&self, context: &mut AnalysisContext) {
+    context.ensure_depth(2);
+    let a = context.pop();
+    let b = context.pop();
+    context.push(a);
+    context.push(b);
+  }
+}
+
+impl Analyzable for Rotate {
+  fn analyze(&self, context: &mut AnalysisContext) {
+    let size = self.size.unsigned_abs() as usize;
+    context.ensure_depth(size);
+    if size == 0 {
+      return;
+    }
+    if self.size > 0 {
+      let position = context.state.stack.len() - size;
+      let value = context.state.stack.remove(position);
+      context.push(value);
+    } else {
+      let value = context.pop();
+      let position = context.state.stack.len() - (size - 1);
+      context.state.stack.insert(position, value);
+    }
+  }
+}
+
+impl Analyzable for Add {
+  fn analyze(&self, context: &mut AnalysisContext) {
+    analyze_arithmetic(context, Instruction::Add(self.clone()), i128::checked_add, |a, b| a + b);
+  }
+}
+
+impl Analyzable for Subtract {
+  fn analyze(&self, context: &mut AnalysisContext) {
+    analyze_arithmetic(context, Instruction::Subtract(self.clone()), i128::checked_sub, |a, b| a - b);
+  }
+}
+
+impl Analyzable for Multiply {
+  fn analyze(&self, context: &mut AnalysisContext) {
+    analyze_arithmetic(context, Instruction::Multiply(self.clone()), i128::checked_mul, |a, b| a * b);
+  }
+}
+
+impl Analyzable for Divide {
+  fn analyze(&self, context: &mut AnalysisContext) {
+    analyze_division(context, Instruction::Divide(self.clone()));
+  }
+}
+
+impl Analyzable for Modulo {
+  fn analyze(&self, context: &mut AnalysisContext) {
+    analyze_division(context, Instruction::Modulo(self.clone()));
+  }
+}
+
+impl Analyzable for LeftShift {
+  fn analyze(&self, context: &mut AnalysisContext) {
+    analyze_binary(context, Instruction::LeftShift(self.clone()), left_type);
+  }
+}
+
+impl Analyzable for RightShift {
+  fn analyze(&self, context: &mut AnalysisContext) {
+    analyze_binary(context, Instruction::RightShift(self.clone()), left_type);
+  }
+}
+
+impl Analyzable for BitwiseAnd {
+  fn analyze(&self, context: &mut AnalysisContext) {
+    analyze_binary(context, Instruction::BitwiseAnd(self.clone()), same_integer_type);
+  }
+}
+
+impl Analyzable for BitwiseOr {
+  fn analyze(&self, context: &mut AnalysisContext) {
+    analyze_binary(context, Instruction::BitwiseOr(self.clone()), same_integer_type);
+  }
+}
+
+impl Analyzable for BitwiseXor {
+  fn analyze(&self, context: &mut AnalysisContext) {
+    analyze_binary(context, Instruction::BitwiseXor(self.clone()), same_integer_type);
+  }
+}
+
+impl Analyzable for BitwiseNot {
+  fn analyze(&self, context: &mut AnalysisContext) {
+    let result = match context.pop() {
+      AnalysisValue::Range(range) => match range.single_value() {
+        Some(value) => ConstantFolding::fold(&Instruction::BitwiseNot(self.clone()), vec![value]).map(|value| AnalysisValue::from_value(&value)).unwrap_or(AnalysisValue::Unknown),
+        None => range.value_type().map(|value_type| AnalysisValue::from_type(&value_type)).unwrap_or(AnalysisValue::Unknown),
+      },
+      _ => AnalysisValue::Unknown,
+    };
+    context.push(result);
+  }
+}
+
+impl Analyzable for And {
+  fn analyze(&self, context: &mut AnalysisContext) {
+    analyze_logical(context, |a, b| a && b);
+  }
+}
+
+impl Analyzable for Or {
+  fn analyze(&self, context: &mut AnalysisContext) {
+    analyze_logical(context, |a, b| a || b);
+  }
+}
+
+impl Analyzable for Xor {
+  fn analyze(&self, context: &mut AnalysisContext) {
+    analyze_logical(context, |a, b| a ^ b);
+  }
+}
+
+impl Analyzable for Not {
+  fn analyze(&self, context: &mut AnalysisContext) {
+    let values = boolean_values(&context.pop()).iter().map(|value| !value).collect::<Vec<bool>>();
+    context.push(AnalysisValue::Boolean(BooleanSet::from_outcomes(&values)));
+  }
+}
+
+impl Analyzable for Equal {
+  fn analyze(&self, context: &mut AnalysisContext) {
+    analyze_comparison(context, Comparison::Equal);
+  }
+}
+
+impl Analyzable for NotEqual {
+  fn analyze(&self, context: &mut AnalysisContext) {
+    analyze_comparison(context, Comparison::NotEqual);
+  }
+}
+
+impl Analyzable for LessThan {
+  fn analyze(&self, context: &mut AnalysisContext) {
+    analyze_comparison(context, Comparison::LessThan);
+  }
+}
+
+impl Analyzable for GreaterThan {
+  fn analyze(&self, context: &mut AnalysisContext) {
+    analyze_comparison(context, Comparison::GreaterThan);
+  }
+}
+
+impl Analyzable for LessThanEqual {
+  fn analyze(&self, context: &mut AnalysisContext) {
+    analyze_comparison(context, Comparison::LessThanEqual);
+  }
+}
+
+impl Analyzable for GreaterThanEqual {
+  fn analyze(&self, context: &mut AnalysisContext) {
+    analyze_comparison(context, Comparison::GreaterThanEqual);
+  }
+}
+
+impl Analyzable for Jump {
+  fn analyze(&self, _context: &mut AnalysisContext) {}
+}
+
+impl Analyzable for Branch {
+  fn analyze(&self, context: &mut AnalysisContext) {
+    let values = boolean_values(&context.pop());
+    context.branch_outcome = match values.as_slice() {
+      [only] => Some(*only),
+      _ => None,
+    };
+    if let Some(outcome) = context.branch_outcome {
+      let target = if outcome { &self.true_target } else { &self.false_target };
+      let target_name = match target {
+        Target::Label(label) => format!("'{}'", label),
+        Target::Index(index) => format!("pc {}", index),
+      };
+      context.warn(AnalysisWarningKind::BranchAlwaysTaken, format!("Branch always goes to {} since the condition is always {}", target_name, outcome));
+    }
+  }
+}
+
+impl Analyzable for Call {
+  fn analyze(&self, context: &mut AnalysisContext) {
+    let pointer = match context.pop() {
+      AnalysisValue::Constant(Value::FunctionPointer(pointer)) => pointer,
+      _ => {
+        context.state.stack.clear();
+        return;
+      }
+    };
+    let module_name = pointer.module.clone().unwrap_or(context.module_name.clone());
+    let Some(module) = context.compilation_unit.get_module(module_name.as_str()) else {
+      context.state.stack.clear();
+      return;
+    };
+
+    match module.functions.get(pointer.function.as_str()) {
+      Some(function) => {
+        for _ in &function.parameters {
+          context.pop();
+        }
+        match &function.return_type {
+          Some(Type::Void) => {}
+          Some(return_type) => context.push(AnalysisValue::from_type(return_type)),
+          // without a declared return type we can't tell if anything comes back
+          None => context.state.stack.clear(),
+        }
+      }
+      // intrinsics can push any number of values
+      None => context.state.stack.clear(),
+    }
+  }
+}
+
+impl Analyzable for Return {
+  fn analyze(&self, _context: &mut AnalysisContext) {}
+}
+
+impl Analyzable for Cast {
+  fn analyze(&self, context: &mut AnalysisContext) {
+    let value = context.pop();
+    if let Some(single) = value.single_value().filter(|_| !matches!(value, AnalysisValue::Constant(_))) {
+      let folded = ConstantFolding::fold(&Instruction::Cast(self.clone()), vec![single]);
+      context.push(folded.map(|folded| AnalysisValue::from_value(&folded)).unwrap_or(AnalysisValue::Unknown));
+      return;
+    }
+
+    let result = match (&value, &self.to_type) {
+      (_, to_type) if value.value_type().as_ref() == Some(to_type) => value.clone(),
+      // integer casts keep the value whenever it fits in the new type
+      (AnalysisValue::Range(range), Type::Float32 | Type::Float64) => match range.integer_bounds() {
+        Some((min, max)) => AnalysisValue::Float(FloatRange::new(self.to_type.clone(), min as f64, max as f64, false)),
+        None => AnalysisValue::from_type(&self.to_type),
+      },
+      (AnalysisValue::Range(range), to_type) if AnalysisValue::from_type(to_type).value_type().is_some() && *to_type != Type::Boolean => match range.integer_bounds() {
+        Some((min, max)) => AnalysisValue::Range(PossibleValue::from_integer_bounds(to_type.clone(), Some(min), Some(max))),
+        None => AnalysisValue::from_type(to_type),
+      },
+      (_, Type::Array(None, subtype)) if **subtype == Type::Unsigned8 => AnalysisValue::from_type(&self.to_type),
+      _ => AnalysisValue::Unknown,
+    };
+    context.push(result);
+  }
+}
+
+impl Analyzable for Load {
+  fn analyze(&self, context: &mut AnalysisContext) {
+    let reference = context.pop();
+    let result = match reference_path(&reference) {
+      Some((name, steps)) => read_path(&context.state.variables.get(name.as_str()).cloned().unwrap_or(AnalysisValue::Unknown), &steps),
+      None => AnalysisValue::Unknown,
+    };
+    context.push(result);
+  }
+}
+
+impl Analyzable for Store {
+  fn analyze(&self, context: &mut AnalysisContext) {
+    let value = context.pop();
+    let reference = context.pop();
+    if let Some((name, steps)) = reference_path(&reference) {
+      let existing = context.state.variables.get(name.as_str()).cloned().unwrap_or(AnalysisValue::Unknown);
+      context.state.variables.insert(name, write_path(&existing, &steps, value));
+    }
+    context.push(reference);
+  }
+}
+
+impl Analyzable for GetArrayIndex {
+  fn analyze(&self, context: &mut AnalysisContext) {
+    let index = context.pop();
+    let array = context.pop();
+    check_index(context, &array, &index);
+    let element = match &array {
+      AnalysisValue::Array(summary) => *summary.element.clone(),
+      _ => AnalysisValue::Unknown,
+    };
+    context.push(array);
+    context.push(element);
+  }
+}
+
+impl Analyzable for SetArrayIndex {
+  fn analyze(&self, context: &mut AnalysisContext) {
+    let value = context.pop();
+    let index = context.pop();
+    let array = context.pop();
+    check_index(context, &array, &index);
+    context.push(write_path(&array, &[ReferenceStep::Element], value));
+  }
+}
+
+impl Analyzable for GetLayoutIndex {
+  fn analyze(&self, context: &mut AnalysisContext) {
+    let layout = context.pop();
+    let member = match &layout {
+      AnalysisValue::Array(array) if self.member == "length" => AnalysisValue::Range(array.length.clone()),
+      AnalysisValue::Layout(layout) => layout.member(self.member.as_str()),
+      _ => AnalysisValue::Unknown,
+    };
+    context.push(layout);
+    context.push(member);
+  }
+}
+
+impl Analyzable for SetLayoutIndex {
+  fn analyze(&self, context: &mut AnalysisContext) {
+    let value = context.pop();
+    let layout = context.pop();
+    context.push(write_path(&layout, &[ReferenceStep::Member(self.member.clone())], value));
+  }
+}
+
+impl Analyzable for Allocate {
+  fn analyze(&self, context: &mut AnalysisContext) {
+    let reference = context.pop();
+    if let Some((name, steps)) = reference_path(&reference) {
+      if steps.is_empty() {
+        context.state.variables.insert(name, AnalysisValue::from_default(&self.allocated_type));
+      }
+    }
+    context.push(reference);
+  }
+}
+
+impl Analyzable for AllocateArray {
+  fn analyze(&self, context: &mut AnalysisContext) {
+    let length = match self.array_size {
+      Some(size) => PossibleValue::single(Value::Unsigned64(size)),
+      None => match context.pop() {
+        AnalysisValue::Range(size) => match size.integer_bounds() {
+          Some((min, max)) => PossibleValue::from_integer_bounds(Type::Unsigned64, Some(min.max(0)), Some(max.max(0))),
+          _ => PossibleValue::full(Type::Unsigned64),
+        },
+        _ => PossibleValue::full(Type::Unsigned64),
+      },
+    };
+    let element = if length == PossibleValue::single(Value::Unsigned64(0)) { AnalysisValue::Bottom } else { AnalysisValue::from_default(&self.array_sub_type) };
+    context.push(AnalysisValue::Array(ArraySummary { length, element: Box::new(element) }));
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::hydro::analyzer::analysiscontext::AnalysisWarningKind;
+  use crate::hydro::analyzer::functionanalysis::analyze_function;
+  use crate::hydro::compilationunit::CompilationUnit;
+  use crate::hydro::testing::function_with_body;
+
+  // the (program counter, message) of every finding of one kind
+  fn findings(header: &str, lines: &[&str], kind: AnalysisWarningKind) -> Vec<(usize, String)> {
+    let function = function_with_body(header, lines);
+    let analysis = analyze_function(&CompilationUnit::new(), "main", &function);
+    analysis.warnings.into_iter().filter(|warning| warning.kind == kind).map(|warning| (warning.program_counter, warning.message)).collect()
+  }
+
+  #[test]
+  fn finds_divisors_that_may_be_zero() {
+    let kind = AnalysisWarningKind::DivisorMayBeZero;
+    assert_eq!(findings("f s32 returns s32", &["push s32 10", "swap", "divide", "return"], kind), vec![(2, "Divisor may be zero".to_string())]);
+    assert_eq!(findings("f s32 returns s32", &["push s32 0", "modulo", "return"], kind), vec![(1, "Divisor is always zero".to_string())]);
+    assert!(findings("f s32 returns s32", &["push s32 3", "divide", "return"], kind).is_empty());
+    // floats divide by zero without throwing
+    assert!(findings("f f64 returns f64", &["push f64 0", "divide", "return"], kind).is_empty());
+  }
+
+  #[test]
+  fn finds_branches_that_always_go_one_way() {
+    let kind = AnalysisWarningKind::BranchAlwaysTaken;
+    let always = findings("f returns s32", &["push s32 3", "push s32 5", "lessthan", "branch yes no", "label yes", "push s32 1", "return", "label no", "push s32 2", "return"], kind);
+    assert_eq!(always, vec![(3, "Branch always goes to 'yes' since the condition is always true".to_string())]);
+    assert!(findings("f s32 returns s32", &["push s32 5", "lessthan", "branch yes no", "label yes", "push s32 1", "return", "label no", "push s32 2", "return"], kind).is_empty());
+  }
+
+  #[test]
+  fn finds_indexes_that_may_be_out_of_bounds() {
+    let kind = AnalysisWarningKind::IndexMayBeOutOfBounds;
+    assert_eq!(findings("f returns s32", &["alloc array 4 s32", "push u64 7", "getindex", "return"], kind), vec![(2, "Index is always out of bounds (index 7..=7 but length 4..=4)".to_string())]);
+    let maybe = findings("f u64 returns s32", &["alloc array 4 s32", "swap", "getindex", "return"], kind);
+    assert_eq!(maybe.len(), 1);
+    assert!(maybe[0].1.starts_with("Index may be out of bounds (index 0..="), "{}", maybe[0].1);
+    assert!(findings("f returns s32", &["alloc array 4 s32", "push u64 3", "getindex", "return"], kind).is_empty());
+  }
+}
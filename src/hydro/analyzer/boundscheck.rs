@@ -0,0 +1,206 @@
+use crate::hydro::analyzer::analysisvalue::AnalysisValue;
+use crate::hydro::analyzer::functionanalysis::analyze_function;
+use crate::hydro::compilationunit::CompilationUnit;
+use crate::hydro::instruction::Instruction;
+use crate::hydro::sourcelocation::SourceLocation;
+use crate::util::errors::{Error, Severity};
+use std::fs;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BoundsClassification {
+  InBounds,
+  OutOfBounds,
+  Unknown,
+}
+
+// compares every index the site could see against every length the array could have
+pub fn classify_index(array: &AnalysisValue, index: &AnalysisValue) -> BoundsClassification {
+  let (AnalysisValue::Array(array), AnalysisValue::Range(index)) = (array, index) else {
+    return BoundsClassification::Unknown;
+  };
+  let (Some((length_min, length_max)), Some((index_min, index_max))) = (array.length.integer_bounds(), index.integer_bounds()) else {
+    return BoundsClassification::Unknown;
+  };
+
+  if index_min >= 0 && index_max < length_min {
+    BoundsClassification::InBounds
+  } else if index_min >= length_max || index_max < 0 {
+    BoundsClassification::OutOfBounds
+  } else {
+    BoundsClassification::Unknown
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct BoundsSite {
+  pub module: String,
+  pub function: String,
+  pub program_counter: usize,
+  pub location: Option<SourceLocation>,
+  pub classification: BoundsClassification,
+}
+
+impl BoundsSite {
+  pub fn to_string(&self) -> String {
+    match &self.location {
+      Some(location) => format!("{}.{} pc {} ({})", self.module, self.function, self.program_counter, location.to_string()),
+      None => format!("{}.{} pc {}", self.module, self.function, self.program_counter),
+    }
+  }
+}
+
+pub struct BoundsReport {
+  pub sites: Vec<BoundsSite>,
+}
+
+impl BoundsReport {
+  pub fn count(&self, classification: BoundsClassification) -> usize {
+    self.sites.iter().filter(|site| site.classification == classification).count()
+  }
+
+  pub fn has_errors(&self) -> bool {
+    self.count(BoundsClassification::OutOfBounds) > 0
+  }
+
+  pub fn print(&self) {
+    for site in self.sites.iter().filter(|site| site.classification == BoundsClassification::OutOfBounds) {
+      let message = format!("Array index is always out of bounds in '{}.{}'", site.module, site.function);
+      let error = Error::new(Severity::Error, site.location.as_ref().map(|location| location.span).unwrap_or((0, 0)), message);
+      match &site.location {
+        Some(location) => match fs::read_to_string(location.file.as_str()) {
+          Ok(contents) => error.display_message(contents.as_bytes(), &location.file, 1),
+          Err(_) => error.display_message_without_file(&location.file),
+        },
+        None => error.display_message_without_file(&format!("{}.{}", site.module, site.function)),
+      }
+    }
+
+    println!("Bounds Check Report");
+    println!("  Proven In Bounds: {}", self.count(BoundsClassification::InBounds));
+    println!("  Proven Out Of Bounds: {}", self.count(BoundsClassification::OutOfBounds));
+    let unknown = self.sites.iter().filter(|site| site.classification == BoundsClassification::Unknown).collect::<Vec<&BoundsSite>>();
+    println!("  Unknown: {}", unknown.len());
+    for site in unknown {
+      println!("    {}", site.to_string());
+    }
+  }
+}
+
+// Proves array accesses are in bounds with the range analysis and turns off the runtime check at those sites.
+// Sites the analysis never reaches keep their check and are left out of the report
+pub struct BoundsCheckElimination {}
+
+impl BoundsCheckElimination {
+  pub fn run(&self, compilation_unit: &mut CompilationUnit) -> BoundsReport {
+    let mut sites = Vec::new();
+    for module_name in compilation_unit.get_module_names() {
+      let module = compilation_unit.get_module(module_name.as_str()).unwrap();
+      let mut function_names = module.functions.keys().cloned().collect::<Vec<String>>();
+      function_names.sort();
+
+      for function_name in function_names {
+        let function = &module.functions[&function_name];
+        let analysis = analyze_function(compilation_unit, module_name.as_str(), function);
+        for (program_counter, instruction) in function.body.iter().enumerate() {
+          // how far below the top of the stack the index and array are
+          let depth = match instruction {
+            Instruction::GetArrayIndex(_) => 0,
+            Instruction::SetArrayIndex(_) => 1,
+            _ => continue,
+          };
+          let Some(state) = analysis.in_states.get(&program_counter) else {
+            continue;
+          };
+          let value_at = |offset: usize| state.stack.iter().rev().nth(offset).cloned().unwrap_or(AnalysisValue::Unknown);
+          sites.push(BoundsSite {
+            module: module_name.clone(),
+            function: function_name.clone(),
+            program_counter,
+            location: function.get_source_location(program_counter).cloned(),
+            classification: classify_index(&value_at(depth + 1), &value_at(depth)),
+          });
+        }
+      }
+    }
+
+    for site in sites.iter().filter(|site| site.classification == BoundsClassification::InBounds) {
+      let function = compilation_unit.get_module_mut(site.module.as_str()).unwrap().functions.get_mut(site.function.as_str()).unwrap();
+      match &mut function.body[site.program_counter] {
+        Instruction::GetArrayIndex(get_array_index) => get_array_index.bounds_checked = false,
+        Instruction::SetArrayIndex(set_array_index) => set_array_index.bounds_checked = false,
+        _ => {}
+      }
+    }
+
+    BoundsReport { sites }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::hydro::analyzer::possiblevalue::PossibleValue;
+  use crate::hydro::testing::compile;
+  use crate::hydro::value::{Type, Value};
+  use crate::hydro::Hydro;
+
+  fn array(length: Option<u64>) -> AnalysisValue {
+    AnalysisValue::from_type(&Type::Array(length, Box::new(Type::Signed32)))
+  }
+
+  fn index(value: Value) -> AnalysisValue {
+    AnalysisValue::from_value(&value)
+  }
+
+  // whether each array access in the function still checks its bounds
+  fn checked_sites(compilation_unit: &CompilationUnit, function_name: &str) -> Vec<(usize, bool)> {
+    let function = &compilation_unit.get_module("main").unwrap().functions[function_name];
+    function
+      .body
+      .iter()
+      .enumerate()
+      .filter_map(|(program_counter, instruction)| match instruction {
+        Instruction::GetArrayIndex(get_array_index) => Some((program_counter, get_array_index.bounds_checked)),
+        Instruction::SetArrayIndex(set_array_index) => Some((program_counter, set_array_index.bounds_checked)),
+        _ => None,
+      })
+      .collect()
+  }
+
+  #[test]
+  fn indices_are_classified_against_every_possible_length() {
+    assert_eq!(classify_index(&array(Some(4)), &index(Value::Unsigned64(3))), BoundsClassification::InBounds);
+    assert_eq!(classify_index(&array(Some(4)), &AnalysisValue::Range(PossibleValue::range_inc_inc(Value::Unsigned64(0), Value::Unsigned64(3)))), BoundsClassification::InBounds);
+    assert_eq!(classify_index(&array(Some(4)), &index(Value::Unsigned64(4))), BoundsClassification::OutOfBounds);
+    assert_eq!(classify_index(&array(Some(4)), &index(Value::Signed64(-1))), BoundsClassification::OutOfBounds);
+    assert_eq!(classify_index(&array(Some(4)), &AnalysisValue::Range(PossibleValue::range_inc_inc(Value::Unsigned64(2), Value::Unsigned64(5)))), BoundsClassification::Unknown);
+    assert_eq!(classify_index(&array(Some(4)), &AnalysisValue::from_type(&Type::Unsigned64)), BoundsClassification::Unknown);
+    assert_eq!(classify_index(&array(None), &index(Value::Unsigned64(0))), BoundsClassification::Unknown);
+    assert_eq!(classify_index(&AnalysisValue::Unknown, &index(Value::Unsigned64(0))), BoundsClassification::Unknown);
+  }
+
+  #[test]
+  fn only_proven_sites_lose_their_check() {
+    let mut compilation_unit = compile(
+      "module main\n\nfunction lookup u64 returns s32 body\n    alloc array 4 s32\n    swap\n    getindex\n    return\n\nmain body\n    alloc array 4 s32\n    push u64 2\n    push s32 9\n    setindex\n    push u64 3\n    getindex\n    return\n",
+    );
+    let report = BoundsCheckElimination {}.run(&mut compilation_unit);
+
+    assert_eq!(checked_sites(&compilation_unit, "main"), vec![(3, false), (5, false)]);
+    assert_eq!(checked_sites(&compilation_unit, "lookup"), vec![(2, true)]);
+    assert_eq!(report.count(BoundsClassification::InBounds), 2);
+    assert_eq!(report.count(BoundsClassification::OutOfBounds), 0);
+    assert_eq!(report.count(BoundsClassification::Unknown), 1);
+    assert!(!report.has_errors());
+  }
+
+  #[test]
+  fn proven_out_of_bounds_accesses_are_rejected() {
+    let mut compilation_unit = compile("module main\n\nmain body\n    alloc array 4 s32\n    push u64 7\n    getindex\n    return\n");
+    let report = BoundsCheckElimination {}.run(&mut compilation_unit);
+    assert_eq!(report.count(BoundsClassification::OutOfBounds), 1);
+    assert_eq!(checked_sites(&compilation_unit, "main"), vec![(2, true)]);
+    assert!(!Hydro::check_bounds(&mut compile("module main\n\nmain body\n    alloc array 4 s32\n    push u64 7\n    getindex\n    return\n")));
+    assert!(Hydro::check_bounds(&mut compile("module main\n\nmain body\n    alloc array 4 s32\n    push u64 3\n    getindex\n    return\n")));
+  }
+}
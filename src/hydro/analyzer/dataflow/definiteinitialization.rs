@@ -0,0 +1,119 @@
+use crate::hydro::analyzer::dataflow::variableaccess::VariableAccess;
+use crate::hydro::analyzer::dataflow::{DataflowAnalysis, DataflowResult, Direction, Lattice};
+use crate::hydro::function::Function;
+use std::collections::{BTreeSet, HashMap};
+
+// The variables stored on every path to here. None means no path has reached here yet
+#[derive(Debug, Clone, PartialEq)]
+pub struct InitializedVariables(pub Option<BTreeSet<String>>);
+
+impl InitializedVariables {
+  pub fn contains(&self, variable: &String) -> bool {
+    match &self.0 {
+      Some(variables) => variables.contains(variable),
+      None => true,
+    }
+  }
+}
+
+impl Lattice for InitializedVariables {
+  fn bottom() -> Self {
+    InitializedVariables(None)
+  }
+
+  fn join(&self, other: &Self) -> Self {
+    match (&self.0, &other.0) {
+      (None, _) => other.clone(),
+      (_, None) => self.clone(),
+      (Some(left), Some(right)) => InitializedVariables(Some(left.intersection(right).cloned().collect())),
+    }
+  }
+}
+
+pub struct DefiniteInitialization<'a> {
+  pub accesses: &'a HashMap<usize, VariableAccess>,
+}
+
+impl<'a> DataflowAnalysis for DefiniteInitialization<'a> {
+  type Fact = InitializedVariables;
+
+  fn direction(&self) -> Direction {
+    Direction::Forward
+  }
+
+  fn boundary(&self) -> Self::Fact {
+    InitializedVariables(Some(BTreeSet::new()))
+  }
+
+  fn transfer(&self, _function: &Function, program_counter: usize, fact: &Self::Fact) -> Self::Fact {
+    match (&fact.0, self.accesses.get(&program_counter)) {
+      (Some(initialized), Some(access)) if !access.definitions.is_empty() => {
+        let mut initialized = initialized.clone();
+        initialized.extend(access.definitions.iter().cloned());
+        InitializedVariables(Some(initialized))
+      }
+      _ => fact.clone(),
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct UninitializedUse {
+  pub program_counter: usize,
+  pub variable: String,
+  // no store on any path reaches the use
+  pub never_initialized: bool,
+}
+
+// every variable read that some path reaches without storing to the variable first
+pub fn find_uninitialized_uses(
+  accesses: &HashMap<usize, VariableAccess>,
+  initialized: &DataflowResult<InitializedVariables>,
+  reaching: &DataflowResult<BTreeSet<(String, usize)>>,
+) -> Vec<UninitializedUse> {
+  let mut program_counters = accesses.keys().cloned().collect::<Vec<usize>>();
+  program_counters.sort();
+
+  let mut uses = Vec::new();
+  for program_counter in program_counters {
+    let before = initialized.before(program_counter);
+    for variable in &accesses[&program_counter].uses {
+      if !before.contains(variable) {
+        let never_initialized = !reaching.before(program_counter).iter().any(|(name, _)| name == variable);
+        uses.push(UninitializedUse { program_counter, variable: variable.clone(), never_initialized });
+      }
+    }
+  }
+  uses
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::hydro::analyzer::dataflow::reachingdefinitions::ReachingDefinitions;
+  use crate::hydro::analyzer::dataflow::solve;
+  use crate::hydro::controlflowgraph::ControlFlowGraph;
+  use crate::hydro::testing::{function_with_body, variable_accesses};
+
+  #[test]
+  fn finds_variables_stored_on_only_some_paths() {
+    #[rustfmt::skip]
+    let function = function_with_body("f bool returns s32", &[
+      "push vref x", "alloc s32", "pop", "push vref y", "load", "pop", "branch yes no",
+      "label yes", "push vref z", "push s32 1", "store", "pop", "jump join",
+      "label no", "jump join",
+      "label join", "push vref z", "load", "return",
+    ]);
+    let accesses = variable_accesses(&function);
+    let graph = ControlFlowGraph::build(&function);
+    let initialized = solve(&DefiniteInitialization { accesses: &accesses }, &function, &graph);
+    assert_eq!(initialized.before(1), InitializedVariables(Some(BTreeSet::new())));
+    assert_eq!(initialized.after(9), InitializedVariables(Some(BTreeSet::from(["x".to_string(), "z".to_string()]))));
+    // only the path through 'yes' stores z
+    assert_eq!(initialized.before(14), InitializedVariables(Some(BTreeSet::from(["x".to_string()]))));
+
+    let reaching = solve(&ReachingDefinitions { accesses: &accesses }, &function, &graph);
+    let uses = find_uninitialized_uses(&accesses, &initialized, &reaching).into_iter().map(|uninitialized| (uninitialized.program_counter, uninitialized.variable, uninitialized.never_initialized)).collect::<Vec<(usize, String, bool)>>();
+    assert_eq!(uses, vec![(4, "y".to_string(), true), (14, "z".to_string(), false)]);
+  }
+}
@@ -0,0 +1,98 @@
+use crate::hydro::analyzer::dataflow::variableaccess::VariableAccess;
+use crate::hydro::analyzer::dataflow::{DataflowAnalysis, Direction};
+use crate::hydro::function::Function;
+use std::collections::{BTreeSet, HashMap};
+
+// A variable is live when some path from here loads it before it is overwritten
+pub struct Liveness<'a> {
+  pub accesses: &'a HashMap<usize, VariableAccess>,
+  // what an unknown reference could be reading
+  pub all_variables: BTreeSet<String>,
+}
+
+impl<'a> Liveness<'a> {
+  pub fn new(accesses: &'a HashMap<usize, VariableAccess>) -> Self {
+    let all_variables = accesses.values().flat_map(|access| access.uses.iter().chain(access.definitions.iter()).cloned()).collect();
+    Self { accesses, all_variables }
+  }
+}
+
+impl<'a> DataflowAnalysis for Liveness<'a> {
+  type Fact = BTreeSet<String>;
+
+  fn direction(&self) -> Direction {
+    Direction::Backward
+  }
+
+  fn boundary(&self) -> Self::Fact {
+    BTreeSet::new()
+  }
+
+  fn transfer(&self, _function: &Function, program_counter: usize, fact: &Self::Fact) -> Self::Fact {
+    let Some(access) = self.accesses.get(&program_counter) else {
+      return fact.clone();
+    };
+    let mut live = fact.clone();
+    for definition in &access.definitions {
+      live.remove(definition);
+    }
+    live.extend(access.uses.iter().cloned());
+    if access.unknown_use {
+      live.extend(self.all_variables.iter().cloned());
+    }
+    live
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::hydro::analyzer::dataflow::solve;
+  use crate::hydro::controlflowgraph::ControlFlowGraph;
+  use crate::hydro::testing::{function_with_body, variable_accesses};
+
+  fn live(variables: &[&str]) -> BTreeSet<String> {
+    variables.iter().map(|variable| variable.to_string()).collect()
+  }
+
+  #[test]
+  fn variables_are_live_until_overwritten() {
+    #[rustfmt::skip]
+    let function = function_with_body("f bool returns s32", &[
+      "push vref x", "alloc s32", "pop", "push vref y", "load", "pop", "branch yes no",
+      "label yes", "push vref z", "push s32 1", "store", "pop", "jump join",
+      "label no", "jump join",
+      "label join", "push vref z", "load", "return",
+    ]);
+    let accesses = variable_accesses(&function);
+    let result = solve(&Liveness::new(&accesses), &function, &ControlFlowGraph::build(&function));
+    assert_eq!(result.before(14), live(&["z"]));
+    assert_eq!(result.after(9), live(&["z"]));
+    // the store overwrites z before the path through 'yes' reads it
+    assert_eq!(result.before(9), live(&[]));
+    assert_eq!(result.before(7), live(&[]));
+    assert_eq!(result.before(6), live(&["z"]));
+    assert_eq!(result.before(4), live(&["y", "z"]));
+    // x is never read
+    assert_eq!(result.after(1), live(&["y", "z"]));
+  }
+
+  #[test]
+  fn variables_stay_live_around_a_loop() {
+    #[rustfmt::skip]
+    let function = function_with_body("f returns s32", &[
+      "push vref i", "alloc s32", "pop",
+      "label loop", "push vref i", "load", "push s32 10", "lessthan", "branch step done",
+      "label step", "push vref i", "push vref i", "load", "push s32 1", "add", "store", "pop", "jump loop",
+      "label done", "push s32 0", "return",
+    ]);
+    let accesses = variable_accesses(&function);
+    let result = solve(&Liveness::new(&accesses), &function, &ControlFlowGraph::build(&function));
+    // the back edge keeps i live after the store
+    assert_eq!(result.after(13), live(&["i"]));
+    assert_eq!(result.before(13), live(&[]));
+    assert_eq!(result.before(10), live(&["i"]));
+    assert_eq!(result.before(16), live(&[]));
+    assert_eq!(result.before(1), live(&[]));
+  }
+}
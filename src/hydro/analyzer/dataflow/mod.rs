@@ -0,0 +1,190 @@
+use crate::hydro::controlflowgraph::ControlFlowGraph;
+use crate::hydro::function::Function;
+use std::collections::{BTreeSet, HashMap};
+
+pub mod definiteinitialization;
+pub mod liveness;
+pub mod reachingdefinitions;
+pub mod stackdepth;
+pub mod variableaccess;
+
+pub trait Lattice: Clone + PartialEq {
+  // the fact for code nothing has reached yet. Joining with it changes nothing
+  fn bottom() -> Self;
+  fn join(&self, other: &Self) -> Self;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+  Forward,
+  Backward,
+}
+
+pub trait DataflowAnalysis {
+  type Fact: Lattice;
+
+  fn direction(&self) -> Direction;
+
+  // the fact at the entry of the function for forward analyses and at every exit for backward ones
+  fn boundary(&self) -> Self::Fact;
+
+  // the fact after the instruction runs for forward analyses and before it runs for backward ones
+  fn transfer(&self, function: &Function, program_counter: usize, fact: &Self::Fact) -> Self::Fact;
+}
+
+pub struct DataflowResult<F: Lattice> {
+  // facts in program order no matter the direction, so before is always the fact as the instruction starts
+  pub before: HashMap<usize, F>,
+  pub after: HashMap<usize, F>,
+}
+
+impl<F: Lattice> DataflowResult<F> {
+  pub fn before(&self, program_counter: usize) -> F {
+    self.before.get(&program_counter).cloned().unwrap_or(F::bottom())
+  }
+
+  pub fn after(&self, program_counter: usize) -> F {
+    self.after.get(&program_counter).cloned().unwrap_or(F::bottom())
+  }
+}
+
+// Iterates block facts to a fixpoint and then replays each block once to get the fact at every instruction
+pub fn solve<A: DataflowAnalysis>(analysis: &A, function: &Function, graph: &ControlFlowGraph) -> DataflowResult<A::Fact> {
+  let direction = analysis.direction();
+  let block_count = graph.blocks.len();
+  // for forward analyses 'input' is the block entry and for backward ones it is the block exit
+  let mut input = vec![A::Fact::bottom(); block_count];
+  let mut output = vec![A::Fact::bottom(); block_count];
+
+  let mut work_list = (0..block_count).collect::<BTreeSet<usize>>();
+  while let Some(id) = match direction {
+    Direction::Forward => work_list.pop_first(),
+    Direction::Backward => work_list.pop_last(),
+  } {
+    let block = &graph.blocks[id];
+    let (sources, is_boundary) = match direction {
+      Direction::Forward => (&block.predecessors, id == 0),
+      Direction::Backward => (&block.successors, block.successors.is_empty()),
+    };
+    let mut fact = if is_boundary { analysis.boundary() } else { A::Fact::bottom() };
+    for source in sources {
+      fact = fact.join(&output[*source]);
+    }
+    input[id] = fact.clone();
+
+    let new_output = transfer_block(analysis, function, block.start, block.end, fact, &mut |_, _, _| {});
+    if new_output != output[id] {
+      output[id] = new_output;
+      let dependents = match direction {
+        Direction::Forward => &block.successors,
+        Direction::Backward => &block.predecessors,
+      };
+      work_list.extend(dependents.iter().cloned());
+    }
+  }
+
+  let mut before = HashMap::new();
+  let mut after = HashMap::new();
+  for block in &graph.blocks {
+    transfer_block(analysis, function, block.start, block.end, input[block.id].clone(), &mut |program_counter, in_fact, out_fact| match direction {
+      Direction::Forward => {
+        before.insert(program_counter, in_fact);
+        after.insert(program_counter, out_fact);
+      }
+      Direction::Backward => {
+        after.insert(program_counter, in_fact);
+        before.insert(program_counter, out_fact);
+      }
+    });
+  }
+
+  DataflowResult { before, after }
+}
+
+fn transfer_block<A: DataflowAnalysis>(
+  analysis: &A,
+  function: &Function,
+  start: usize,
+  end: usize,
+  fact: A::Fact,
+  record: &mut dyn FnMut(usize, A::Fact, A::Fact),
+) -> A::Fact {
+  let program_counters = match analysis.direction() {
+    Direction::Forward => (start..end).collect::<Vec<usize>>(),
+    Direction::Backward => (start..end).rev().collect::<Vec<usize>>(),
+  };
+  let mut fact = fact;
+  for program_counter in program_counters {
+    let next = analysis.transfer(function, program_counter, &fact);
+    record(program_counter, fact, next.clone());
+    fact = next;
+  }
+  fact
+}
+
+// A set that only grows when joined, for may analyses
+impl<T: Clone + Ord> Lattice for BTreeSet<T> {
+  fn bottom() -> Self {
+    BTreeSet::new()
+  }
+
+  fn join(&self, other: &Self) -> Self {
+    self.union(other).cloned().collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::hydro::testing::function_with_body;
+
+  // the instructions that may have run before here, or that may still run for the backward direction
+  struct Visited {
+    direction: Direction,
+  }
+
+  impl DataflowAnalysis for Visited {
+    type Fact = BTreeSet<usize>;
+
+    fn direction(&self) -> Direction {
+      self.direction
+    }
+
+    fn boundary(&self) -> Self::Fact {
+      BTreeSet::new()
+    }
+
+    fn transfer(&self, _function: &Function, program_counter: usize, fact: &Self::Fact) -> Self::Fact {
+      let mut visited = fact.clone();
+      visited.insert(program_counter);
+      visited
+    }
+  }
+
+  fn set(program_counters: &[usize]) -> BTreeSet<usize> {
+    program_counters.iter().cloned().collect()
+  }
+
+  #[test]
+  fn forward_facts_flow_around_loops() {
+    let function = function_with_body("f bool returns s32", &["branch loop done", "label loop", "push bool true", "branch loop done", "label done", "push s32 1", "return"]);
+    let result = solve(&Visited { direction: Direction::Forward }, &function, &ControlFlowGraph::build(&function));
+    assert_eq!(result.before(0), set(&[]));
+    // the loop head is reached from the entry and from its own back edge
+    assert_eq!(result.before(1), set(&[0, 1, 2]));
+    assert_eq!(result.after(1), set(&[0, 1, 2]));
+    assert_eq!(result.before(3), set(&[0, 1, 2]));
+    assert_eq!(result.after(4), set(&[0, 1, 2, 3, 4]));
+  }
+
+  #[test]
+  fn backward_facts_are_reported_in_program_order() {
+    let function = function_with_body("f bool returns s32", &["branch yes no", "label yes", "push s32 1", "return", "label no", "push s32 2", "return"]);
+    let result = solve(&Visited { direction: Direction::Backward }, &function, &ControlFlowGraph::build(&function));
+    assert_eq!(result.after(2), set(&[]));
+    assert_eq!(result.before(2), set(&[2]));
+    assert_eq!(result.before(1), set(&[1, 2]));
+    assert_eq!(result.after(0), set(&[1, 2, 3, 4]));
+    assert_eq!(result.before(0), set(&[0, 1, 2, 3, 4]));
+  }
+}
@@ -0,0 +1,68 @@
+use crate::hydro::analyzer::dataflow::variableaccess::VariableAccess;
+use crate::hydro::analyzer::dataflow::{DataflowAnalysis, Direction};
+use crate::hydro::function::Function;
+use std::collections::{BTreeSet, HashMap};
+
+// Which stores and allocs (variable, program counter) may have written the value a variable holds here
+pub struct ReachingDefinitions<'a> {
+  pub accesses: &'a HashMap<usize, VariableAccess>,
+}
+
+impl<'a> DataflowAnalysis for ReachingDefinitions<'a> {
+  type Fact = BTreeSet<(String, usize)>;
+
+  fn direction(&self) -> Direction {
+    Direction::Forward
+  }
+
+  fn boundary(&self) -> Self::Fact {
+    BTreeSet::new()
+  }
+
+  fn transfer(&self, _function: &Function, program_counter: usize, fact: &Self::Fact) -> Self::Fact {
+    let Some(access) = self.accesses.get(&program_counter) else {
+      return fact.clone();
+    };
+    if access.definitions.is_empty() {
+      return fact.clone();
+    }
+    let mut reaching = fact.iter().filter(|(variable, _)| !access.definitions.contains(variable)).cloned().collect::<BTreeSet<(String, usize)>>();
+    for definition in &access.definitions {
+      reaching.insert((definition.clone(), program_counter));
+    }
+    reaching
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::hydro::analyzer::dataflow::solve;
+  use crate::hydro::controlflowgraph::ControlFlowGraph;
+  use crate::hydro::testing::{function_with_body, variable_accesses};
+
+  fn definitions(definitions: &[(&str, usize)]) -> BTreeSet<(String, usize)> {
+    definitions.iter().map(|(variable, program_counter)| (variable.to_string(), *program_counter)).collect()
+  }
+
+  #[test]
+  fn stores_reach_around_a_loop() {
+    #[rustfmt::skip]
+    let function = function_with_body("f returns s32", &[
+      "push vref i", "alloc s32", "pop",
+      "label loop", "push vref i", "load", "push s32 10", "lessthan", "branch step done",
+      "label step", "push vref i", "push vref i", "load", "push s32 1", "add", "store", "pop", "jump loop",
+      "label done", "push vref i", "load", "return",
+    ]);
+    let accesses = variable_accesses(&function);
+    assert_eq!(accesses[&1].definitions, vec!["i".to_string()]);
+    assert_eq!(accesses[&13].definitions, vec!["i".to_string()]);
+    assert_eq!(accesses[&10].uses, vec!["i".to_string()]);
+
+    let result = solve(&ReachingDefinitions { accesses: &accesses }, &function, &ControlFlowGraph::build(&function));
+    assert_eq!(result.before(4), definitions(&[("i", 1), ("i", 13)]));
+    // the store replaces every definition that reached it
+    assert_eq!(result.after(13), definitions(&[("i", 13)]));
+    assert_eq!(result.before(17), definitions(&[("i", 1), ("i", 13)]));
+  }
+}
@@ -0,0 +1,55 @@
+use crate::hydro::analyzer::dataflow::{DataflowAnalysis, Direction, Lattice};
+use crate::hydro::function::Function;
+
+// The fewest values the stack can hold here on any path. None means no path has reached here yet
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MinimumStackDepth(pub Option<usize>);
+
+impl MinimumStackDepth {
+  pub fn at_least(&self, depth: usize) -> bool {
+    matches!(self.0, Some(minimum) if minimum >= depth)
+  }
+}
+
+impl Lattice for MinimumStackDepth {
+  fn bottom() -> Self {
+    MinimumStackDepth(None)
+  }
+
+  fn join(&self, other: &Self) -> Self {
+    match (self.0, other.0) {
+      (None, _) => *other,
+      (_, None) => *self,
+      (Some(left), Some(right)) => MinimumStackDepth(Some(left.min(right))),
+    }
+  }
+}
+
+// Functions start with their arguments on the stack. An instruction that would underflow throws, so whatever comes after
+// it only runs with the stack it would have left
+pub struct StackDepth {
+  pub parameters: usize,
+}
+
+impl DataflowAnalysis for StackDepth {
+  type Fact = MinimumStackDepth;
+
+  fn direction(&self) -> Direction {
+    Direction::Forward
+  }
+
+  fn boundary(&self) -> Self::Fact {
+    MinimumStackDepth(Some(self.parameters))
+  }
+
+  fn transfer(&self, function: &Function, program_counter: usize, fact: &Self::Fact) -> Self::Fact {
+    let Some(depth) = fact.0 else {
+      return *fact;
+    };
+    match function.body[program_counter].stack_effect() {
+      Some((pops, pushes)) => MinimumStackDepth(Some(depth.saturating_sub(pops) + pushes)),
+      // a call takes as many arguments as its callee has so nothing is known about the stack it leaves
+      None => MinimumStackDepth(Some(0)),
+    }
+  }
+}
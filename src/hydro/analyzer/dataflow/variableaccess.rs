@@ -0,0 +1,63 @@
+use crate::hydro::analyzer::analysisvalue::AnalysisValue;
+use crate::hydro::analyzer::functionanalysis::FunctionAnalysis;
+use crate::hydro::function::Function;
+use crate::hydro::instruction::Instruction;
+use crate::hydro::value::{Reference, Value};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VariableAccess {
+  // variables whose current value is read, including writes to one of their elements or members
+  pub uses: Vec<String>,
+  // variables that are completely overwritten
+  pub definitions: Vec<String>,
+  // the instruction goes through a reference we couldn't follow so it may read any variable
+  pub unknown_use: bool,
+}
+
+// Which variables each instruction reads and writes. Variables are only reached through references on the stack
+// so this uses the range analysis to find out which reference each load, store, and alloc gets
+pub fn find_variable_accesses(function: &Function, analysis: &FunctionAnalysis) -> HashMap<usize, VariableAccess> {
+  let mut accesses = HashMap::new();
+  for (program_counter, instruction) in function.body.iter().enumerate() {
+    let Some(state) = analysis.in_states.get(&program_counter) else {
+      continue;
+    };
+    let value_at = |offset: usize| state.stack.iter().rev().nth(offset).cloned().unwrap_or(AnalysisValue::Unknown);
+    let (reference, defines) = match instruction {
+      Instruction::Load(_) => (value_at(0), false),
+      Instruction::Store(_) | Instruction::Allocate(_) => (value_at(if matches!(instruction, Instruction::Store(_)) { 1 } else { 0 }), true),
+      _ => continue,
+    };
+
+    let mut access = VariableAccess::default();
+    match reference {
+      AnalysisValue::Constant(Value::Reference(Reference::Variable(variable))) => {
+        if defines {
+          access.definitions.push(variable.name);
+        } else {
+          access.uses.push(variable.name);
+        }
+      }
+      AnalysisValue::Constant(Value::Reference(reference)) => match base_variable(&reference) {
+        Some(name) => access.uses.push(name),
+        None => access.unknown_use = true,
+      },
+      _ => access.unknown_use = true,
+    }
+    accesses.insert(program_counter, access);
+  }
+  accesses
+}
+
+fn base_variable(reference: &Reference) -> Option<String> {
+  let base = match reference {
+    Reference::Variable(variable) => return Some(variable.name.clone()),
+    Reference::ArrayIndex(index_reference) => index_reference.reference.as_ref(),
+    Reference::LayoutIndex(layout_reference) => layout_reference.reference.as_ref(),
+  };
+  match base {
+    Value::Reference(reference) => base_variable(reference),
+    _ => None,
+  }
+}
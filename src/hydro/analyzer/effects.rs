@@ -0,0 +1,219 @@
+use crate::hydro::analyzer::analysisvalue::AnalysisValue;
+use crate::hydro::analyzer::boundscheck::{classify_index, BoundsClassification};
+use crate::hydro::analyzer::functionanalysis::{analyze_function, find_loop_heads};
+use crate::hydro::callgraph::CallGraph;
+use crate::hydro::compilationunit::CompilationUnit;
+use crate::hydro::function::Function;
+use crate::hydro::instruction::Instruction;
+use crate::hydro::intrinsic::Intrinsic;
+use crate::hydro::value::{Type, Value};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Effects {
+  pub reads_input: bool,
+  pub writes_output: bool,
+  // loads or stores through a reference the function didn't make itself
+  pub foreign_references: bool,
+  pub may_throw: bool,
+  // loops, recursion, and calls we can't resolve mean we can't prove the function finishes
+  pub may_not_terminate: bool,
+}
+
+impl Effects {
+  pub fn unknown() -> Self {
+    Self { reads_input: true, writes_output: true, foreign_references: true, may_throw: true, may_not_terminate: true }
+  }
+
+  // pure functions do no I/O and only touch their own variables, though they can still throw or loop forever
+  pub fn is_pure(&self) -> bool {
+    !self.reads_input && !self.writes_output && !self.foreign_references
+  }
+
+  // a call with constant arguments can be replaced by its result when running it early can't be observed or hang the compiler
+  pub fn is_evaluable(&self) -> bool {
+    self.is_pure() && !self.may_not_terminate
+  }
+
+  pub fn merge(&self, other: &Self) -> Self {
+    Self {
+      reads_input: self.reads_input || other.reads_input,
+      writes_output: self.writes_output || other.writes_output,
+      foreign_references: self.foreign_references || other.foreign_references,
+      may_throw: self.may_throw || other.may_throw,
+      may_not_terminate: self.may_not_terminate || other.may_not_terminate,
+    }
+  }
+
+  pub fn labels(&self) -> Vec<&'static str> {
+    let mut labels = Vec::new();
+    if self.is_pure() {
+      labels.push("pure");
+    }
+    if self.reads_input {
+      labels.push("reads-input");
+    }
+    if self.writes_output {
+      labels.push("writes-output");
+    }
+    if self.foreign_references {
+      labels.push("foreign-references");
+    }
+    if self.may_throw {
+      labels.push("may-throw");
+    }
+    if self.may_not_terminate {
+      labels.push("nonterminating-unknown");
+    }
+    labels
+  }
+
+  // the effects of an intrinsic come from the vm code it maps to
+  pub fn of_intrinsic(intrinsic: &Intrinsic) -> Self {
+    match intrinsic.get_intrinsic_code("vm".to_string()).as_deref() {
+      Ok("readline") => Self { reads_input: true, may_throw: true, ..Self::default() },
+      Ok("print") | Ok("println") | Ok("flush") => Self { writes_output: true, may_throw: true, ..Self::default() },
+      _ => Self::unknown(),
+    }
+  }
+}
+
+// Classifies every function and intrinsic in the compilation unit. Each function's own instructions are checked against the
+// abstract interpreter's states and then the effects of everything it calls are folded in until nothing changes
+pub struct EffectAnalysis {
+  pub effects: HashMap<(String, String), Effects>,
+}
+
+impl EffectAnalysis {
+  pub fn run(compilation_unit: &CompilationUnit) -> Self {
+    let call_graph = CallGraph::build(compilation_unit);
+    let mut local_effects = HashMap::new();
+    let mut callees = HashMap::new();
+    for module_name in compilation_unit.get_module_names() {
+      let module = compilation_unit.get_module(module_name.as_str()).unwrap();
+      for function in module.functions.values() {
+        let key = (module_name.clone(), function.name.clone());
+        let (mut effects, called) = EffectAnalysis::local_effects(compilation_unit, module_name.as_str(), function);
+        if call_graph.is_recursive(&key) {
+          effects.may_not_terminate = true;
+        }
+        local_effects.insert(key.clone(), effects);
+        callees.insert(key, called);
+      }
+      for intrinsic in module.intrinsics.values() {
+        local_effects.insert((module_name.clone(), intrinsic.name.clone()), Effects::of_intrinsic(intrinsic));
+      }
+    }
+
+    let mut effects = local_effects.clone();
+    let mut changed = true;
+    while changed {
+      changed = false;
+      for (key, called) in &callees {
+        let merged = called.iter().fold(effects[key], |merged, callee| merged.merge(&effects.get(callee).cloned().unwrap_or(Effects::unknown())));
+        if merged != effects[key] {
+          effects.insert(key.clone(), merged);
+          changed = true;
+        }
+      }
+    }
+
+    Self { effects }
+  }
+
+  pub fn get(&self, module_name: &str, function_name: &str) -> Effects {
+    self.effects.get(&(module_name.to_string(), function_name.to_string())).cloned().unwrap_or(Effects::unknown())
+  }
+
+  // the effects of the function's own instructions and the functions it calls
+  fn local_effects(compilation_unit: &CompilationUnit, module_name: &str, function: &Function) -> (Effects, Vec<(String, String)>) {
+    let mut effects = Effects::default();
+    let mut called = Vec::new();
+    if !find_loop_heads(function).is_empty() {
+      effects.may_not_terminate = true;
+    }
+
+    let analysis = analyze_function(compilation_unit, module_name, function);
+    for (program_counter, state) in &analysis.in_states {
+      let value_at = |offset: usize| state.stack.iter().rev().nth(offset).cloned().unwrap_or(AnalysisValue::Unknown);
+      match &function.body[*program_counter] {
+        Instruction::Call(_) => match value_at(0) {
+          AnalysisValue::Constant(Value::FunctionPointer(pointer)) => called.push((pointer.module.clone().unwrap_or(module_name.to_string()), pointer.function.clone())),
+          _ => effects = effects.merge(&Effects::unknown()),
+        },
+        // the vm rejects divisors that are zero or can't be read as an unsigned integer
+        Instruction::Divide(_) | Instruction::Modulo(_) => match value_at(0) {
+          AnalysisValue::Range(divisor) if divisor.integer_bounds().map(|(min, _)| min >= 1).unwrap_or(false) => {}
+          _ => effects.may_throw = true,
+        },
+        Instruction::GetArrayIndex(_) => {
+          if classify_index(&value_at(1), &value_at(0)) != BoundsClassification::InBounds {
+            effects.may_throw = true;
+          }
+        }
+        Instruction::SetArrayIndex(_) => {
+          if classify_index(&value_at(2), &value_at(1)) != BoundsClassification::InBounds {
+            effects.may_throw = true;
+          }
+        }
+        Instruction::Cast(cast) => {
+          if value_at(0).value_type().as_ref() != Some(&cast.to_type) {
+            effects.may_throw = true;
+          }
+        }
+        Instruction::Load(_) => EffectAnalysis::reference_effects(&value_at(0), &mut effects),
+        Instruction::Store(_) => EffectAnalysis::reference_effects(&value_at(1), &mut effects),
+        _ => {}
+      }
+    }
+
+    // a reference parameter is another function's variable even if we never see it get used
+    if function.parameters.iter().any(|parameter| matches!(parameter, Type::Reference(_))) {
+      effects.foreign_references = true;
+    }
+
+    called.sort();
+    called.dedup();
+    (effects, called)
+  }
+
+  fn reference_effects(reference: &AnalysisValue, effects: &mut Effects) {
+    if !matches!(reference, AnalysisValue::Constant(Value::Reference(_))) {
+      effects.foreign_references = true;
+      effects.may_throw = true;
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::hydro::testing::compile;
+
+  const SOURCE: &str = "module io\n\nintrinsic println any body\n    target vm \"println\"\n\nmodule main\n\nfunction log s32 body\n    push funcp io println\n    call\n    return\n\nfunction report s32 body\n    push funcp main log\n    call\n    return\n\nfunction inverse s32 returns s32 body\n    push s32 100\n    swap\n    divide\n    return\n\nfunction forever s32 returns s32 body\n    push funcp main forever\n    call\n    return\n\nfunction double s32 returns s32 body\n    push s32 2\n    multiply\n    return\n\nmain body\n    push s32 1\n    return\n";
+
+  #[test]
+  fn io_reaches_every_caller() {
+    let analysis = EffectAnalysis::run(&compile(SOURCE));
+    assert_eq!(analysis.get("io", "println"), Effects { writes_output: true, may_throw: true, ..Effects::default() });
+    assert_eq!(analysis.get("main", "log").labels(), vec!["writes-output", "may-throw"]);
+    // report only calls log but still prints through it
+    assert_eq!(analysis.get("main", "report").labels(), vec!["writes-output", "may-throw"]);
+    assert!(!analysis.get("main", "report").is_evaluable());
+  }
+
+  #[test]
+  fn classifies_throwing_and_nonterminating_functions() {
+    let analysis = EffectAnalysis::run(&compile(SOURCE));
+    // dividing by an argument can throw but can't be observed
+    assert_eq!(analysis.get("main", "inverse").labels(), vec!["pure", "may-throw"]);
+    assert!(analysis.get("main", "inverse").is_evaluable());
+
+    assert_eq!(analysis.get("main", "forever").labels(), vec!["pure", "nonterminating-unknown"]);
+    assert!(!analysis.get("main", "forever").is_evaluable());
+
+    assert_eq!(analysis.get("main", "double"), Effects::default());
+    assert!(analysis.get("main", "double").is_evaluable());
+    assert_eq!(analysis.get("main", "missing"), Effects::unknown());
+  }
+}
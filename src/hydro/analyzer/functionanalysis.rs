@@ -0,0 +1,138 @@
+use crate::hydro::analyzer::analysiscontext::{AnalysisContext, AnalysisState, AnalysisWarning};
+use crate::hydro::analyzer::analysisvalue::AnalysisValue;
+use crate::hydro::analyzer::analyze::Analyzable;
+use crate::hydro::compilationunit::CompilationUnit;
+use crate::hydro::function::Function;
+use crate::hydro::instruction::Instruction;
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+// how many times a loop head is joined normally before we start widening it
+const WIDENING_DELAY: usize = 2;
+
+pub struct FunctionAnalysis {
+  // the abstract state before each reachable instruction executes
+  pub in_states: HashMap<usize, AnalysisState>,
+  pub warnings: Vec<AnalysisWarning>,
+}
+
+pub fn analyze_function(compilation_unit: &CompilationUnit, module_name: &str, function: &Function) -> FunctionAnalysis {
+  let mut in_states: HashMap<usize, AnalysisState> = HashMap::new();
+  if function.body.is_empty() {
+    return FunctionAnalysis { in_states, warnings: Vec::new() };
+  }
+
+  let loop_heads = find_loop_heads(function);
+  let entry = AnalysisState {
+    stack: function.parameters.iter().map(AnalysisValue::from_type).collect(),
+    variables: HashMap::new(),
+  };
+  in_states.insert(0, entry);
+
+  let mut visits: HashMap<usize, usize> = HashMap::new();
+  let mut worklist = BTreeSet::from([0]);
+  while let Some(program_counter) = worklist.pop_first() {
+    let (state, successors) = step(compilation_unit, module_name, function, program_counter, &in_states[&program_counter], false).0;
+    for successor in successors {
+      let merged = match in_states.get(&successor) {
+        None => state.clone(),
+        Some(existing) => {
+          let joined = existing.join(&state);
+          let count = visits.entry(successor).or_insert(0);
+          *count += 1;
+          if loop_heads.contains(&successor) && *count > WIDENING_DELAY {
+            existing.widen(&joined)
+          } else {
+            joined
+          }
+        }
+      };
+      if in_states.get(&successor) != Some(&merged) {
+        in_states.insert(successor, merged);
+        worklist.insert(successor);
+      }
+    }
+  }
+
+  // warnings come from one last pass over the stable states so an intermediate state can't produce a false positive
+  let mut warnings = Vec::new();
+  let mut program_counters = in_states.keys().cloned().collect::<Vec<usize>>();
+  program_counters.sort();
+  for program_counter in program_counters {
+    warnings.append(&mut step(compilation_unit, module_name, function, program_counter, &in_states[&program_counter], true).1);
+  }
+
+  FunctionAnalysis { in_states, warnings }
+}
+
+fn step(
+  compilation_unit: &CompilationUnit,
+  module_name: &str,
+  function: &Function,
+  program_counter: usize,
+  state: &AnalysisState,
+  report: bool,
+) -> ((AnalysisState, Vec<usize>), Vec<AnalysisWarning>) {
+  let mut context = AnalysisContext {
+    compilation_unit,
+    module_name: module_name.to_string(),
+    function,
+    program_counter,
+    state: state.clone(),
+    branch_outcome: None,
+    report,
+    warnings: Vec::new(),
+  };
+  let instruction = &function.body[program_counter];
+  instruction.analyze(&mut context);
+
+  let successors = match (instruction, context.branch_outcome) {
+    (Instruction::Branch(branch), Some(outcome)) => {
+      let target = if outcome { branch.true_target.clone() } else { branch.false_target.clone() };
+      function.get_target_pointer(target).ok().filter(|target| *target < function.body.len()).into_iter().collect()
+    }
+    _ => function.known_successors(program_counter),
+  };
+
+  ((context.state, successors), context.warnings)
+}
+
+// instructions that are the target of a backwards edge
+pub fn find_loop_heads(function: &Function) -> HashSet<usize> {
+  let mut loop_heads = HashSet::new();
+  for index in 0..function.body.len() {
+    for successor in function.known_successors(index) {
+      if successor <= index {
+        loop_heads.insert(successor);
+      }
+    }
+  }
+  loop_heads
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::hydro::testing::function_with_body;
+
+  #[test]
+  fn widening_ends_a_long_counting_loop() {
+    #[rustfmt::skip]
+    let function = function_with_body("f returns s32", &[
+      "push vref i", "alloc s32", "push s32 0", "store", "pop",
+      "label loop", "push vref i", "load", "push s32 1000000", "lessthan", "branch step done",
+      "label step", "push vref i", "push vref i", "load", "push s32 1", "add", "store", "pop", "jump loop",
+      "label done", "push vref i", "load", "return",
+    ]);
+    let analysis = analyze_function(&CompilationUnit::new(), "main", &function);
+    let i = |program_counter: usize| match &analysis.in_states[&program_counter].variables["i"] {
+      AnalysisValue::Range(range) => range.integer_bounds(),
+      other => panic!("i should be a range but was {:?}", other),
+    };
+    // iterating the loop would take a million rounds so the fixpoint only comes from widening. Every value the loop gives i
+    // has to be in the range at the loop head and after it
+    for program_counter in [5, 10, 18] {
+      let (min, max) = i(program_counter).unwrap();
+      assert!(min <= 0 && max >= 1000000, "i at pc {} was {}..={}", program_counter, min, max);
+    }
+  }
+}
@@ -1,4 +1,9 @@
-mod analysiscontext;
+pub mod analysiscontext;
+pub mod analysisvalue;
 mod analysisgraph;
 pub mod analyze;
+pub mod boundscheck;
+pub mod dataflow;
+pub mod effects;
+pub mod functionanalysis;
 pub mod possiblevalue;
@@ -1,211 +1,285 @@
 use crate::hydro::value::{Type, Value};
+use std::cmp::Ordering;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct PossibleValue {
   pub ranges: Vec<(bool, Value, Value, bool)>,
 }
 
 impl PossibleValue {
-  pub fn range_inc_exc(from: Value, to: Value) -> Self {
-    Self {
-      ranges: vec![(true, from, to, false)],
+  pub fn single(value: Value) -> Self {
+    Self::range_inc_inc(value.clone(), value)
+  }
+
+  pub fn full(value_type: Type) -> Self {
+    Self::range_inc_inc(Type::min(value_type.clone()), Type::max(value_type))
+  }
+
+  pub fn value_type(&self) -> Option<Type> {
+    self.ranges.first().map(|range| range.1.type_of())
+  }
+
+  // the smallest and largest value in any of the ranges
+  pub fn hull(&self) -> Option<(Value, Value)> {
+    let mut hull: Option<(Value, Value)> = None;
+    for (_, min, max, _) in &self.ranges {
+      hull = match hull {
+        None => Some((min.clone(), max.clone())),
+        Some((hull_min, hull_max)) => Some((if *min < hull_min { min.clone() } else { hull_min }, if *max > hull_max { max.clone() } else { hull_max })),
+      };
     }
+    hull
   }
 
-  pub fn range_exc_inc(from: Value, to: Value) -> Self {
-    Self {
-      ranges: vec![(false, from, to, true)],
+  pub fn single_value(&self) -> Option<Value> {
+    match self.ranges.as_slice() {
+      [(true, min, max, true)] if min == max => Some(min.clone()),
+      _ => None,
     }
   }
 
-  pub fn range_inc_inc(from: Value, to: Value) -> Self {
-    Self {
-      ranges: vec![(true, from, to, true)],
+  pub fn is_full(&self) -> bool {
+    match (self.value_type(), self.hull()) {
+      (Some(value_type), Some((min, max))) => min == Type::min(value_type.clone()) && max == Type::max(value_type),
+      _ => false,
     }
   }
 
-  pub fn range_exc_exc(from: Value, to: Value) -> Self {
-    Self {
-      ranges: vec![(false, from, to, false)],
+  // integer and boolean bounds as i128 so range arithmetic doesn't have to care about the exact type
+  pub fn integer_bounds(&self) -> Option<(i128, i128)> {
+    let (min, max) = self.hull()?;
+    Some((Self::to_i128(&min)?, Self::to_i128(&max)?))
+  }
+
+  // builds an inclusive range, falling back to every value of the type when the bounds don't fit since the vm wraps on overflow
+  pub fn from_integer_bounds(value_type: Type, min: Option<i128>, max: Option<i128>) -> Self {
+    match (min.and_then(|min| Self::from_i128(&value_type, min)), max.and_then(|max| Self::from_i128(&value_type, max))) {
+      (Some(min), Some(max)) => Self::range_inc_inc(min, max),
+      _ => Self::full(value_type),
     }
   }
 
-  pub fn intersect(left: Self, right: Self) -> Self {
-    let mut results = Vec::new();
-    for target in left.ranges {
-      let mut ints = Self::intersect_internal(target, right.ranges.clone());
-      results.append(&mut ints)
+  pub fn to_i128(value: &Value) -> Option<i128> {
+    match value {
+      Value::Boolean(x) => Some(*x as i128),
+      Value::Unsigned8(x) => Some(*x as i128),
+      Value::Unsigned16(x) => Some(*x as i128),
+      Value::Unsigned32(x) => Some(*x as i128),
+      Value::Unsigned64(x) => Some(*x as i128),
+      Value::Unsigned128(x) => i128::try_from(*x).ok(),
+      Value::Signed8(x) => Some(*x as i128),
+      Value::Signed16(x) => Some(*x as i128),
+      Value::Signed32(x) => Some(*x as i128),
+      Value::Signed64(x) => Some(*x as i128),
+      Value::Signed128(x) => Some(*x),
+      _ => None,
     }
-    Self {
-      ranges: Self::union_internal(results),
+  }
+
+  pub fn from_i128(value_type: &Type, value: i128) -> Option<Value> {
+    match value_type {
+      Type::Boolean => match value {
+        0 => Some(Value::Boolean(false)),
+        1 => Some(Value::Boolean(true)),
+        _ => None,
+      },
+      Type::Unsigned8 => u8::try_from(value).ok().map(Value::Unsigned8),
+      Type::Unsigned16 => u16::try_from(value).ok().map(Value::Unsigned16),
+      Type::Unsigned32 => u32::try_from(value).ok().map(Value::Unsigned32),
+      Type::Unsigned64 => u64::try_from(value).ok().map(Value::Unsigned64),
+      Type::Unsigned128 => u128::try_from(value).ok().map(Value::Unsigned128),
+      Type::Signed8 => i8::try_from(value).ok().map(Value::Signed8),
+      Type::Signed16 => i16::try_from(value).ok().map(Value::Signed16),
+      Type::Signed32 => i32::try_from(value).ok().map(Value::Signed32),
+      Type::Signed64 => i64::try_from(value).ok().map(Value::Signed64),
+      Type::Signed128 => Some(Value::Signed128(value)),
+      _ => None,
     }
   }
 
-  fn intersect_internal(
-    target: (bool, Value, Value, bool),
-    remaining: Vec<(bool, Value, Value, bool)>,
-  ) -> Vec<(bool, Value, Value, bool)> {
-    let mut overlapping = Vec::new();
-    let mut nonoverlapping = Vec::new();
-    for range in remaining {
-      if Self::overlaps(target.clone(), range.clone()) {
-        overlapping.push(range);
-      } else {
-        nonoverlapping.push(range.clone());
+  // any bound that is still moving jumps straight to the edge of the type so loops reach a fixpoint
+  pub fn widen(old: &Self, new: &Self) -> Self {
+    let (Some((old_min, old_max)), Some((new_min, new_max)), Some(value_type)) = (old.hull(), new.hull(), new.value_type()) else {
+      return new.clone();
+    };
+    Self::range_inc_inc(
+      if new_min < old_min { Type::min(value_type.clone()) } else { old_min },
+      if new_max > old_max { Type::max(value_type) } else { old_max },
+    )
+  }
+
+  pub fn range_inc_exc(from: Value, to: Value) -> Self {
+    Self::from_ranges(vec![(true, from, to, false)])
+  }
+
+  pub fn range_exc_inc(from: Value, to: Value) -> Self {
+    Self::from_ranges(vec![(false, from, to, true)])
+  }
+
+  pub fn range_inc_inc(from: Value, to: Value) -> Self {
+    Self::from_ranges(vec![(true, from, to, true)])
+  }
+
+  pub fn range_exc_exc(from: Value, to: Value) -> Self {
+    Self::from_ranges(vec![(false, from, to, false)])
+  }
+
+  pub fn empty() -> Self {
+    Self { ranges: Vec::new() }
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.ranges.is_empty()
+  }
+
+  pub fn from_ranges(ranges: Vec<(bool, Value, Value, bool)>) -> Self {
+    Self { ranges: Self::normalize(ranges) }
+  }
+
+  // Puts the ranges in a canonical form so equal sets compare equal. Integer ranges are made inclusive on both ends,
+  // empty ranges are dropped, and anything overlapping or touching is merged. The result is sorted
+  fn normalize(ranges: Vec<(bool, Value, Value, bool)>) -> Vec<(bool, Value, Value, bool)> {
+    let mut ranges = ranges.into_iter().filter_map(Self::canonical_range).collect::<Vec<(bool, Value, Value, bool)>>();
+    ranges.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal).then(b.0.cmp(&a.0)));
+
+    let mut results: Vec<(bool, Value, Value, bool)> = Vec::new();
+    for range in ranges {
+      match results.last_mut() {
+        Some(last) if Self::touches(last, &range) => *last = Self::union_range(last.clone(), range),
+        _ => results.push(range),
       }
     }
+    results
+  }
 
-    let mut results = Vec::new();
-    for range in &overlapping {
-      results.push(Self::intersect_range(target.clone(), range.clone()));
+  fn canonical_range(range: (bool, Value, Value, bool)) -> Option<(bool, Value, Value, bool)> {
+    let (min_included, min, max, max_included) = range;
+    match (Self::to_i128(&min), Self::to_i128(&max)) {
+      (Some(min_integer), Some(max_integer)) => {
+        let value_type = min.type_of();
+        let min_integer = if min_included { min_integer } else { min_integer.checked_add(1)? };
+        let max_integer = if max_included { max_integer } else { max_integer.checked_sub(1)? };
+        if min_integer > max_integer {
+          return None;
+        }
+        Some((true, Self::from_i128(&value_type, min_integer)?, Self::from_i128(&value_type, max_integer)?, true))
+      }
+      _ => {
+        if min > max || (min == max && !(min_included && max_included)) || min.partial_cmp(&max).is_none() {
+          None
+        } else {
+          Some((min_included, min, max, max_included))
+        }
+      }
     }
-
-    Self::union_internal(results)
   }
 
-  pub fn intersect_range(
-    a: (bool, Value, Value, bool),
-    b: (bool, Value, Value, bool),
-  ) -> (bool, Value, Value, bool) {
-    // assume these overlap
-    let (mut a_min_included, mut a_min, mut a_max, mut a_max_included) = a;
-    let (b_min_included, b_min, b_max, b_max_included) = b;
-    if a_min < b_min {
-      a_min_included = b_min_included;
-      a_min = b_min;
-    } else if b_min == a_min {
-      a_min_included &= b_min_included;
+  // whether the right range starts before or right after the left range ends. Assumes left starts first
+  fn touches(left: &(bool, Value, Value, bool), right: &(bool, Value, Value, bool)) -> bool {
+    if right.1 < left.2 {
+      return true;
     }
+    if right.1 == left.2 {
+      return left.3 || right.0;
+    }
+    // integers have no values between n and n + 1
+    match (Self::to_i128(&left.2), Self::to_i128(&right.1)) {
+      (Some(left_max), Some(right_min)) => left_max.checked_add(1) == Some(right_min),
+      _ => false,
+    }
+  }
 
-    if b_max < a_max {
-      a_max_included = b_max_included;
-      a_min = b_max
-    } else if b_max == a_min {
-      a_max_included &= b_max_included;
+  pub fn intersect(left: Self, right: Self) -> Self {
+    let mut results = Vec::new();
+    for left_range in &left.ranges {
+      for right_range in &right.ranges {
+        if let Some(range) = Self::intersect_range(left_range.clone(), right_range.clone()) {
+          results.push(range);
+        }
+      }
     }
-    (a_min_included, a_min, a_max, a_max_included)
+    Self::from_ranges(results)
   }
 
-  fn inside(range: (bool, Value, Value, bool), value: Value) -> bool {
-    let (min_included, min, max, max_included) = range;
-    let left = if min_included {
-      min <= value
+  // the overlap of two ranges or None when they don't overlap
+  pub fn intersect_range(a: (bool, Value, Value, bool), b: (bool, Value, Value, bool)) -> Option<(bool, Value, Value, bool)> {
+    let (a_min_included, a_min, a_max, a_max_included) = a;
+    let (b_min_included, b_min, b_max, b_max_included) = b;
+
+    let (min_included, min) = if a_min > b_min {
+      (a_min_included, a_min)
+    } else if b_min > a_min {
+      (b_min_included, b_min)
     } else {
-      min < value
+      (a_min_included && b_min_included, a_min)
     };
-    let right = if max_included {
-      max >= value
+
+    let (max_included, max) = if a_max < b_max {
+      (a_max_included, a_max)
+    } else if b_max < a_max {
+      (b_max_included, b_max)
     } else {
-      max > value
+      (a_max_included && b_max_included, a_max)
     };
-    left && right
-  }
 
-  fn overlaps(left: (bool, Value, Value, bool), right: (bool, Value, Value, bool)) -> bool {
-    let (min_a_included, min_a, max_a, max_a_included) = left;
-    let (min_b_included, min_b, max_b, max_b_included) = right;
+    Self::canonical_range((min_included, min, max, max_included))
+  }
 
-    ((max_b > min_a && max_b < max_a) || (min_b > min_a && min_b < max_a))
-      || (min_a == max_b && min_a_included && max_b_included)
-      || (max_a == min_b && max_a_included && min_b_included)
+  fn inside(range: (bool, Value, Value, bool), value: Value) -> bool {
+    let (min_included, min, max, max_included) = range;
+    let left = if min_included { min <= value } else { min < value };
+    let right = if max_included { max >= value } else { max > value };
+    left && right
   }
 
   pub fn union(left: Self, right: Self) -> Self {
     let mut results = left.ranges;
     let mut right_range = right.ranges;
     results.append(&mut right_range);
-    Self {
-      ranges: Self::union_internal(results),
-    }
+    Self::from_ranges(results)
   }
 
-  fn union_internal(value: Vec<(bool, Value, Value, bool)>) -> Vec<(bool, Value, Value, bool)> {
-    let mut source = value;
-    while source.len() != 0 {
-      let target = source.first().unwrap();
-      let remaining = source.clone();
-      let mut overlapping = Vec::new();
-      let mut nonoverlapping = Vec::new();
-      for range in remaining {
-        if Self::overlaps(target.clone(), range.clone()) {
-          overlapping.push(range);
-        } else {
-          nonoverlapping.push(range.clone());
-        }
-      }
-      if overlapping.len() == 0 {
-        return nonoverlapping;
-      }
-      let mut a = target.clone();
-      for range in overlapping {
-        a = Self::union_range(a, range);
-      }
-      nonoverlapping.push(a);
-      source = nonoverlapping;
-    }
-    Vec::new()
-  }
-
-  fn union_range(
-    a: (bool, Value, Value, bool),
-    b: (bool, Value, Value, bool),
-  ) -> (bool, Value, Value, bool) {
+  fn union_range(a: (bool, Value, Value, bool), b: (bool, Value, Value, bool)) -> (bool, Value, Value, bool) {
     let (mut min_included, mut min, mut max, mut max_included) = a;
     let (b_min_included, b_min, b_max, b_max_included) = b;
     if b_min < min {
       min_included = b_min_included;
       min = b_min;
     } else if b_min == min {
-      min_included &= b_min_included;
+      min_included |= b_min_included;
     }
 
     if b_max > max {
       max_included = b_max_included;
       max = b_max
     } else if b_max == max {
-      max_included &= b_max_included;
+      max_included |= b_max_included;
     }
     (min_included, min, max, max_included)
   }
 
+  // everything of the same type that isn't in the value. An empty value has no type so its complement is empty too
   pub fn complement(value: Self) -> Self {
-    Self {
-      ranges: Self::complement_internal(value.ranges),
-    }
-  }
-
-  fn complement_internal(
-    ranges: Vec<(bool, Value, Value, bool)>,
-  ) -> Vec<(bool, Value, Value, bool)> {
+    let Some(value_type) = value.value_type() else {
+      return Self::empty();
+    };
+    let ranges = value.ranges;
     let mut result = Vec::new();
-    let mut sorted_ranges = ranges.clone();
-    sorted_ranges.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
-
-    for i in 0..(sorted_ranges.len() + 1) {
-      if i == 0 {
-        let range = &sorted_ranges[i];
-        let left = Type::min(range.1.type_of());
-        result.push((true, left, range.1.clone(), !range.0));
-      } else if i < sorted_ranges.len() {
-        let range = &sorted_ranges[i];
-        let previous = &sorted_ranges[i - 1];
-        result.push((!previous.3, previous.2.clone(), range.1.clone(), !range.0));
-      } else {
-        let prev = &sorted_ranges[i - 1];
-        let right = Type::max(prev.2.type_of());
-        result.push((!prev.3, prev.2.clone(), right, true));
-      }
+    result.push((true, Type::min(value_type.clone()), ranges[0].1.clone(), !ranges[0].0));
+    for window in ranges.windows(2) {
+      result.push((!window[0].3, window[0].2.clone(), window[1].1.clone(), !window[1].0));
     }
+    let last = &ranges[ranges.len() - 1];
+    result.push((!last.3, last.2.clone(), Type::max(value_type), true));
 
-    Self::union_internal(result)
+    Self::from_ranges(result)
   }
 
   pub fn contains(&self, value: Value) -> bool {
-    for range in &self.ranges {
-      if Self::inside(range.clone(), value.clone()) {
-        return true;
-      }
-    }
-    false
+    self.ranges.iter().any(|range| Self::inside(range.clone(), value.clone()))
+  }
+
+  pub fn is_subset(&self, other: &Self) -> bool {
+    Self::union(self.clone(), other.clone()) == *other
   }
 }
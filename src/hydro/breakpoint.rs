@@ -0,0 +1,478 @@
+use crate::hydro::analyzer::possiblevalue::PossibleValue;
+use crate::hydro::executioncontext::ExecutionContext;
+use crate::hydro::value::{ArrayIndexRef, LayoutIndexRef, Reference, Value, VariableRef};
+use std::cmp::Ordering;
+
+// Debugger breakpoints and watchpoints. Both read values out of the stopped program with a ValuePath which is a
+// variable or stack slot followed by any number of '.member' and '[index]' accessors. 'top' and 'stack[0]' are the top
+// of the operand stack
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PathRoot {
+  Variable(String),
+  // counted from the top of the stack
+  Stack(usize),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Accessor {
+  Member(String),
+  Index(usize),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValuePath {
+  pub root: PathRoot,
+  pub accessors: Vec<Accessor>,
+}
+
+impl ValuePath {
+  pub fn parse(text: &str) -> Result<Self, String> {
+    let text = text.trim();
+    let name_end = text.find(['.', '[']).unwrap_or(text.len());
+    let name = &text[..name_end];
+    if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-') || name.starts_with(|c: char| c.is_ascii_digit()) {
+      return Err(format!("Expected a variable name, 'top', or 'stack[<index>]' but got '{}'", text));
+    }
+
+    let mut accessors = Vec::new();
+    let mut rest = &text[name_end..];
+    while !rest.is_empty() {
+      if let Some(member) = rest.strip_prefix('.') {
+        let member_end = member.find(['.', '[']).unwrap_or(member.len());
+        if member_end == 0 {
+          return Err(format!("Expected a member name after '.' in '{}'", text));
+        }
+        accessors.push(Accessor::Member(member[..member_end].to_string()));
+        rest = &member[member_end..];
+      } else if let Some(index) = rest.strip_prefix('[') {
+        let Some(index_end) = index.find(']') else {
+          return Err(format!("Expected a ']' in '{}'", text));
+        };
+        match index[..index_end].trim().parse::<usize>() {
+          Ok(value) => accessors.push(Accessor::Index(value)),
+          Err(_) => return Err(format!("Expected an index but got '{}' in '{}'", &index[..index_end], text)),
+        }
+        rest = &index[index_end + 1..];
+      } else {
+        return Err(format!("Unexpected '{}' in '{}'", rest, text));
+      }
+    }
+
+    let root = match (name, accessors.first()) {
+      ("top", _) => PathRoot::Stack(0),
+      ("stack", Some(Accessor::Index(index))) => {
+        let index = *index;
+        accessors.remove(0);
+        PathRoot::Stack(index)
+      }
+      _ => PathRoot::Variable(name.to_string()),
+    };
+    Ok(Self { root, accessors })
+  }
+
+  pub fn resolve(&self, context: &ExecutionContext) -> Result<Value, String> {
+    let mut value = match &self.root {
+      PathRoot::Variable(name) => context.variables.get(name).cloned().ok_or(format!("Variable '{}' is not defined in the current context", name))?,
+      PathRoot::Stack(index) => context
+        .stack
+        .iter()
+        .rev()
+        .nth(*index)
+        .cloned()
+        .ok_or(format!("Stack index {} is out of bounds for a stack of size {}", index, context.stack.len()))?,
+    };
+
+    for accessor in &self.accessors {
+      value = ValuePath::dereference(value, context)?;
+      value = match (accessor, value) {
+        (Accessor::Member(member), Value::Layout(layout)) => layout.values.get(member).cloned().ok_or(format!("Layout '{}.{}' has no member '{}'", layout.module_name, layout.layout_name, member))?,
+        (Accessor::Index(index), Value::Array(array)) => array.values.get(*index).cloned().ok_or(format!("Index {} is out of bounds for an array of length {}", index, array.values.len()))?,
+        (Accessor::Member(member), other) => return Err(format!("Cannot get member '{}' of {}", member, other.to_string())),
+        (Accessor::Index(index), other) => return Err(format!("Cannot index {} with {}", other.to_string(), index)),
+      };
+    }
+    ValuePath::dereference(value, context)
+  }
+
+  // the reference ExecutionContext::modify takes. Stack slots can't be referenced
+  pub fn to_reference(&self) -> Option<Reference> {
+    let PathRoot::Variable(name) = &self.root else {
+      return None;
+    };
+    let mut reference = Reference::Variable(VariableRef::new(name.clone()));
+    for accessor in &self.accessors {
+      let inner = Box::new(Value::Reference(reference));
+      reference = match accessor {
+        Accessor::Member(member) => Reference::LayoutIndex(LayoutIndexRef::new(inner, member.clone())),
+        Accessor::Index(index) => Reference::ArrayIndex(ArrayIndexRef::new(inner, Box::new(Value::Unsigned64(*index as u64)))),
+      };
+    }
+    Some(reference)
+  }
+
+  fn dereference(value: Value, context: &ExecutionContext) -> Result<Value, String> {
+    match value {
+      Value::Reference(_) => context.resolve(value).map_err(|exception| exception.message),
+      _ => Ok(value),
+    }
+  }
+
+  pub fn to_string(&self) -> String {
+    let mut result = match &self.root {
+      PathRoot::Variable(name) => name.clone(),
+      PathRoot::Stack(index) => format!("stack[{}]", index),
+    };
+    for accessor in &self.accessors {
+      match accessor {
+        Accessor::Member(member) => result += format!(".{}", member).as_str(),
+        Accessor::Index(index) => result += format!("[{}]", index).as_str(),
+      }
+    }
+    result
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operand {
+  Path(ValuePath),
+  Literal(Value),
+}
+
+impl Operand {
+  fn parse(text: &str) -> Result<Self, String> {
+    let text = text.trim();
+    if text.len() >= 2 && ((text.starts_with('"') && text.ends_with('"')) || (text.starts_with('\'') && text.ends_with('\''))) {
+      return Ok(Operand::Literal(Value::string(text[1..text.len() - 1].to_string())));
+    }
+    match text {
+      "true" => return Ok(Operand::Literal(Value::Boolean(true))),
+      "false" => return Ok(Operand::Literal(Value::Boolean(false))),
+      _ => {}
+    }
+    if let Ok(integer) = text.parse::<i128>() {
+      return Ok(Operand::Literal(Value::Signed128(integer)));
+    }
+    if let Ok(float) = text.parse::<f64>() {
+      return Ok(Operand::Literal(Value::Float64(float)));
+    }
+    Ok(Operand::Path(ValuePath::parse(text)?))
+  }
+
+  fn resolve(&self, context: &ExecutionContext) -> Result<Value, String> {
+    match self {
+      Operand::Path(path) => path.resolve(context),
+      Operand::Literal(value) => Ok(value.clone()),
+    }
+  }
+
+  fn to_string(&self) -> String {
+    match self {
+      Operand::Path(path) => path.to_string(),
+      Operand::Literal(value @ Value::Array(_)) => format!("\"{}\"", value.to_string()),
+      Operand::Literal(value) => value.to_string(),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Comparison {
+  Equal,
+  NotEqual,
+  LessThan,
+  LessThanEqual,
+  GreaterThan,
+  GreaterThanEqual,
+}
+
+impl Comparison {
+  // longer operators first so '<=' isn't read as '<'
+  const OPERATORS: [(&'static str, Comparison); 6] = [
+    ("==", Comparison::Equal),
+    ("!=", Comparison::NotEqual),
+    ("<=", Comparison::LessThanEqual),
+    (">=", Comparison::GreaterThanEqual),
+    ("<", Comparison::LessThan),
+    (">", Comparison::GreaterThan),
+  ];
+
+  fn symbol(&self) -> &'static str {
+    Comparison::OPERATORS.iter().find(|(_, comparison)| comparison == self).unwrap().0
+  }
+
+  fn holds(&self, ordering: Option<Ordering>) -> bool {
+    match (self, ordering) {
+      (Comparison::NotEqual, None) => true,
+      (_, None) => false,
+      (Comparison::Equal, Some(ordering)) => ordering == Ordering::Equal,
+      (Comparison::NotEqual, Some(ordering)) => ordering != Ordering::Equal,
+      (Comparison::LessThan, Some(ordering)) => ordering == Ordering::Less,
+      (Comparison::LessThanEqual, Some(ordering)) => ordering != Ordering::Greater,
+      (Comparison::GreaterThan, Some(ordering)) => ordering == Ordering::Greater,
+      (Comparison::GreaterThanEqual, Some(ordering)) => ordering != Ordering::Less,
+    }
+  }
+}
+
+// Either '<operand> <comparison> <operand>' or a single operand that has to be a boolean
+#[derive(Debug, Clone, PartialEq)]
+pub struct Condition {
+  pub left: Operand,
+  pub comparison: Option<(Comparison, Operand)>,
+}
+
+impl Condition {
+  pub fn parse(text: &str) -> Result<Self, String> {
+    let mut in_string = None;
+    for (index, character) in text.char_indices() {
+      match (in_string, character) {
+        (None, '"' | '\'') => in_string = Some(character),
+        (Some(quote), _) if quote == character => in_string = None,
+        (None, _) => {
+          if let Some((symbol, comparison)) = Comparison::OPERATORS.iter().find(|(symbol, _)| text[index..].starts_with(symbol)) {
+            return Ok(Self {
+              left: Operand::parse(&text[..index])?,
+              comparison: Some((*comparison, Operand::parse(&text[index + symbol.len()..])?)),
+            });
+          }
+        }
+        _ => {}
+      }
+    }
+    Ok(Self { left: Operand::parse(text)?, comparison: None })
+  }
+
+  pub fn evaluate(&self, context: &ExecutionContext) -> Result<bool, String> {
+    let left = self.left.resolve(context)?;
+    match &self.comparison {
+      Some((comparison, right)) => Ok(comparison.holds(Condition::compare(&left, &right.resolve(context)?))),
+      None => match left {
+        Value::Boolean(value) => Ok(value),
+        other => Err(format!("Expected the condition '{}' to be a boolean but got {}", self.to_string(), other.to_string())),
+      },
+    }
+  }
+
+  // integers of different widths and signs compare by value and strings compare by their text
+  fn compare(left: &Value, right: &Value) -> Option<Ordering> {
+    if let (Some(left), Some(right)) = (PossibleValue::to_i128(left), PossibleValue::to_i128(right)) {
+      return Some(left.cmp(&right));
+    }
+    if let (Some(left), Some(right)) = (Condition::to_f64(left), Condition::to_f64(right)) {
+      return left.partial_cmp(&right);
+    }
+    match (left, right) {
+      (Value::Array(_), Value::Array(_)) => left.to_string().partial_cmp(&right.to_string()),
+      _ if left == right => Some(Ordering::Equal),
+      _ => None,
+    }
+  }
+
+  fn to_f64(value: &Value) -> Option<f64> {
+    match value {
+      Value::Float32(value) => Some(*value as f64),
+      Value::Float64(value) => Some(*value),
+      Value::Boolean(_) => None,
+      _ => PossibleValue::to_i128(value).map(|value| value as f64),
+    }
+  }
+
+  pub fn to_string(&self) -> String {
+    match &self.comparison {
+      Some((comparison, right)) => format!("{} {} {}", self.left.to_string(), comparison.symbol(), right.to_string()),
+      None => self.left.to_string(),
+    }
+  }
+}
+
+// Fills in every '{path}' in a log message with the value it points at
+pub fn interpolate(message: &str, context: &ExecutionContext) -> String {
+  let mut result = String::new();
+  let mut rest = message;
+  while let Some(start) = rest.find('{') {
+    let Some(length) = rest[start..].find('}') else {
+      break;
+    };
+    result += &rest[..start];
+    let path = &rest[start + 1..start + length];
+    result += match ValuePath::parse(path).and_then(|path| path.resolve(context)) {
+      Ok(value) => value.to_string(),
+      Err(message) => format!("<{}>", message),
+    }
+    .as_str();
+    rest = &rest[start + length + 1..];
+  }
+  result + rest
+}
+
+#[derive(Debug, Clone)]
+pub struct Breakpoint {
+  pub id: usize,
+  pub program_counter: usize,
+  pub condition: Option<Condition>,
+  // the first hits that are skipped before the breakpoint stops
+  pub ignore_count: usize,
+  pub hit_count: usize,
+  // logpoints print their message instead of stopping
+  pub log_message: Option<String>,
+  pub enabled: bool,
+}
+
+impl Breakpoint {
+  pub fn new(program_counter: usize) -> Self {
+    Self {
+      id: 0,
+      program_counter,
+      condition: None,
+      ignore_count: 0,
+      hit_count: 0,
+      log_message: None,
+      enabled: true,
+    }
+  }
+
+  pub fn describe(&self) -> String {
+    let mut result = format!("pc {}", self.program_counter);
+    if let Some(condition) = &self.condition {
+      result += format!(" if {}", condition.to_string()).as_str();
+    }
+    if self.ignore_count != 0 {
+      result += format!(" ignoring {}", self.ignore_count).as_str();
+    }
+    if let Some(message) = &self.log_message {
+      result += format!(" logging \"{}\"", message).as_str();
+    }
+    result += format!(" (hit {} times)", self.hit_count).as_str();
+    if !self.enabled {
+      result += " [disabled]";
+    }
+    result
+  }
+}
+
+// Stops once the value a path points to changes. Variables belong to a single call so the watchpoint is only checked
+// in the function call it was set in
+#[derive(Debug, Clone)]
+pub struct Watchpoint {
+  pub id: usize,
+  pub path: ValuePath,
+  pub module_name: String,
+  pub function_name: String,
+  pub depth: usize,
+  pub last_value: Option<Value>,
+  pub enabled: bool,
+}
+
+impl Watchpoint {
+  pub fn new(path: ValuePath, context: &ExecutionContext) -> Self {
+    Self {
+      id: 0,
+      last_value: path.resolve(context).ok(),
+      path,
+      module_name: context.current_module.clone(),
+      function_name: context.current_function.clone(),
+      depth: context.depth(),
+      enabled: true,
+    }
+  }
+
+  // returns a message describing the change if the watched value changed since it was last checked
+  pub fn check(&mut self, context: &ExecutionContext) -> Option<String> {
+    if !self.enabled || context.depth() != self.depth || context.current_module != self.module_name || context.current_function != self.function_name {
+      return None;
+    }
+    let value = self.path.resolve(context).ok();
+    if value == self.last_value {
+      return None;
+    }
+    let describe = |value: &Option<Value>| value.as_ref().map(|value| value.to_string()).unwrap_or("<undefined>".to_string());
+    let message = format!("Watchpoint {}: {} changed from {} to {}", self.id, self.path.to_string(), describe(&self.last_value), describe(&value));
+    self.last_value = value;
+    Some(message)
+  }
+
+  pub fn describe(&self) -> String {
+    let mut result = format!("watch {} in {}.{}", self.path.to_string(), self.module_name, self.function_name);
+    if let Some(value) = &self.last_value {
+      result += format!(" = {}", value.to_string()).as_str();
+    }
+    if !self.enabled {
+      result += " [disabled]";
+    }
+    result
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::collections::HashMap;
+
+  fn context() -> ExecutionContext {
+    let mut variables = HashMap::new();
+    variables.insert("count".to_string(), Value::Unsigned32(7));
+    variables.insert("name".to_string(), Value::string("hydro".to_string()));
+    variables.insert("numbers".to_string(), Value::Array(crate::hydro::value::Array::new(crate::hydro::value::Type::Signed8, Box::new(Value::Unsigned32(3)), vec![Value::Signed8(-1), Value::Signed8(0), Value::Signed8(1)])));
+    ExecutionContext {
+      parent_execution_context: None,
+      stack: vec![Value::Boolean(true), Value::Unsigned8(200)],
+      program_counter: 0,
+      variables,
+      return_value: None,
+      current_function: "main".to_string(),
+      current_module: "main".to_string(),
+    }
+  }
+
+  fn holds(condition: &str) -> bool {
+    Condition::parse(condition).unwrap().evaluate(&context()).unwrap()
+  }
+
+  #[test]
+  fn conditions_compare_across_integer_types() {
+    assert!(holds("count == 7"));
+    assert!(holds("count>=7"));
+    assert!(!holds("count < 7"));
+    assert!(holds("top > count"));
+    assert!(holds("numbers[0] < 0"));
+    assert!(holds("stack[1]"));
+    assert!(holds("name == \"hydro\""));
+    assert!(holds("name != 'h<=o'"));
+    assert!(holds("count < 7.5"));
+  }
+
+  #[test]
+  fn bad_conditions_are_errors() {
+    assert!(Condition::parse("count == [").is_err());
+    assert!(Condition::parse("count").unwrap().evaluate(&context()).is_err());
+    assert!(Condition::parse("missing == 1").unwrap().evaluate(&context()).is_err());
+    assert!(Condition::parse("numbers[3] == 1").unwrap().evaluate(&context()).is_err());
+  }
+
+  #[test]
+  fn log_messages_are_interpolated() {
+    assert_eq!(interpolate("count is {count} and top is {top}", &context()), "count is 7 and top is 200");
+    assert_eq!(interpolate("{missing}!", &context()), "<Variable 'missing' is not defined in the current context>!");
+  }
+
+  #[test]
+  fn paths_become_references_that_can_be_modified() {
+    let mut context = context();
+    let reference = ValuePath::parse("numbers[1]").unwrap().to_reference().unwrap();
+    context.modify(&reference, Value::Signed8(9)).unwrap();
+    assert_eq!(ValuePath::parse("numbers[1]").unwrap().resolve(&context), Ok(Value::Signed8(9)));
+    assert_eq!(ValuePath::parse("top").unwrap().to_reference(), None);
+  }
+
+  #[test]
+  fn watchpoints_report_changes_once() {
+    let mut context = context();
+    let mut watchpoint = Watchpoint::new(ValuePath::parse("numbers[2]").unwrap(), &context);
+    assert_eq!(watchpoint.check(&context), None);
+    if let Some(Value::Array(array)) = context.variables.get_mut("numbers") {
+      array.values[2] = Value::Signed8(5);
+    }
+    assert_eq!(watchpoint.check(&context), Some("Watchpoint 0: numbers[2] changed from 1 to 5".to_string()));
+    assert_eq!(watchpoint.check(&context), None);
+  }
+}
@@ -0,0 +1,120 @@
+use crate::hydro::compilationunit::CompilationUnit;
+use crate::hydro::function::Function;
+use crate::hydro::instruction::Instruction;
+use crate::hydro::value::{Reference, Value};
+use std::collections::{HashMap, HashSet};
+
+// Every function pointer pushed by a function is treated as a possible call since pointers can be stored and called later
+#[derive(Debug, Clone)]
+pub struct CallGraph {
+  pub edges: HashMap<(String, String), Vec<(String, String)>>,
+}
+
+impl CallGraph {
+  pub fn build(compilation_unit: &CompilationUnit) -> Self {
+    let mut edges = HashMap::new();
+    for module_name in compilation_unit.get_module_names() {
+      let module = compilation_unit.get_module(module_name.as_str()).unwrap();
+      for function in module.functions.values() {
+        edges.insert((module_name.clone(), function.name.clone()), CallGraph::find_function_pointers(function, module_name.as_str()));
+      }
+      for intrinsic in module.intrinsics.values() {
+        edges.insert((module_name.clone(), intrinsic.name.clone()), Vec::new());
+      }
+    }
+    Self { edges }
+  }
+
+  pub fn find_function_pointers(function: &Function, module_name: &str) -> Vec<(String, String)> {
+    let mut pointers = Vec::new();
+    for instruction in &function.body {
+      if let Instruction::PushValue(push) = instruction {
+        CallGraph::collect_function_pointers(&push.value, module_name, &mut pointers);
+      }
+    }
+    pointers.sort();
+    pointers.dedup();
+    pointers
+  }
+
+  pub fn collect_function_pointers(value: &Value, module_name: &str, pointers: &mut Vec<(String, String)>) {
+    match value {
+      Value::FunctionPointer(pointer) => {
+        let target_module = pointer.module.clone().unwrap_or(module_name.to_string());
+        pointers.push((target_module, pointer.function.clone()));
+      }
+      Value::Array(array) => {
+        for element in &array.values {
+          CallGraph::collect_function_pointers(element, module_name, pointers);
+        }
+      }
+      Value::Layout(layout) => {
+        for member in layout.values.values() {
+          CallGraph::collect_function_pointers(member, module_name, pointers);
+        }
+      }
+      Value::Reference(Reference::ArrayIndex(reference)) => {
+        CallGraph::collect_function_pointers(&reference.reference, module_name, pointers);
+        CallGraph::collect_function_pointers(&reference.index, module_name, pointers);
+      }
+      Value::Reference(Reference::LayoutIndex(reference)) => CallGraph::collect_function_pointers(&reference.reference, module_name, pointers),
+      _ => {}
+    }
+  }
+
+  pub fn callees(&self, caller: &(String, String)) -> Vec<(String, String)> {
+    self.edges.get(caller).cloned().unwrap_or_default()
+  }
+
+  pub fn reachable_from(&self, root: &(String, String)) -> HashSet<(String, String)> {
+    let mut reachable = HashSet::new();
+    let mut work_list = vec![root.clone()];
+    while let Some(current) = work_list.pop() {
+      if reachable.insert(current.clone()) {
+        work_list.extend(self.callees(&current));
+      }
+    }
+    reachable
+  }
+
+  // a function is recursive if it can reach itself through any chain of calls
+  pub fn is_recursive(&self, function: &(String, String)) -> bool {
+    let mut visited = HashSet::new();
+    let mut work_list = self.callees(function);
+    while let Some(current) = work_list.pop() {
+      if current == *function {
+        return true;
+      }
+      if visited.insert(current.clone()) {
+        work_list.extend(self.callees(&current));
+      }
+    }
+    false
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::hydro::testing::compile;
+
+  fn key(function: &str) -> (String, String) {
+    ("main".to_string(), function.to_string())
+  }
+
+  #[test]
+  fn finds_direct_and_mutual_recursion() {
+    let source = "module main\n\nfunction even s32 returns bool body\n    push funcp main odd\n    call\n    return\n\nfunction odd s32 returns bool body\n    push funcp this even\n    call\n    return\n\nfunction again returns s32 body\n    push funcp main again\n    call\n    return\n\nfunction caller returns s32 body\n    push funcp main again\n    call\n    return\n\nfunction leaf returns s32 body\n    push s32 1\n    return\n\nmain body\n    push funcp main caller\n    call\n    push s32 4\n    push funcp main even\n    call\n    return\n";
+    let call_graph = CallGraph::build(&compile(source));
+    assert!(call_graph.is_recursive(&key("even")) && call_graph.is_recursive(&key("odd")));
+    assert!(call_graph.is_recursive(&key("again")));
+    // calling into a recursive function doesn't make the caller recursive
+    assert!(!call_graph.is_recursive(&key("caller")) && !call_graph.is_recursive(&key("main")));
+    assert!(!call_graph.is_recursive(&key("leaf")));
+
+    assert_eq!(call_graph.callees(&key("odd")), vec![key("even")]);
+    let mut reachable = call_graph.reachable_from(&key("main")).into_iter().map(|(_, function)| function).collect::<Vec<String>>();
+    reachable.sort();
+    assert_eq!(reachable, vec!["again", "caller", "even", "main", "odd"]);
+  }
+}
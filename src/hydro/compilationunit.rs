@@ -1,7 +1,9 @@
-use crate::hydro::debugcontext::DebugContext;
+use crate::hydro::debugcontext::{DebugContext, StopReason};
 use crate::hydro::exception::Exception;
 use crate::hydro::executioncontext::ExecutionContext;
+use crate::hydro::function::Function;
 use crate::hydro::module::Module;
+use crate::hydro::sourcelocation::SourceLocation;
 use crate::hydro::value::{Type, Value};
 use std::collections::HashMap;
 
@@ -32,13 +34,83 @@ impl CompilationUnit {
     self.modules.get(module_name)
   }
 
+  pub fn get_module_mut(&mut self, module_name: &str) -> Option<&mut Module> {
+    self.modules.get_mut(module_name)
+  }
+
+  pub fn get_module_names(&self) -> Vec<String> {
+    let mut module_names = self.modules.keys().cloned().collect::<Vec<String>>();
+    module_names.sort();
+    module_names
+  }
+
+  pub fn instruction_count(&self) -> usize {
+    self.modules.values().flat_map(|module| module.functions.values()).map(|function| function.body.len()).sum()
+  }
+
   pub fn contains_module(&self, module_name: &str) -> bool {
     self.modules.contains_key(module_name)
   }
 
+  pub fn get_function(&self, module_name: &str, function_name: &str) -> Option<&Function> {
+    self.modules.get(module_name).and_then(|module| module.functions.get(function_name))
+  }
+
+  pub fn get_source_location(&self, module_name: &str, function_name: &str, program_counter: usize) -> Option<&SourceLocation> {
+    self.get_function(module_name, function_name).and_then(|function| function.get_source_location(program_counter))
+  }
+
+  // returns every (module, function, pc) that a file:line location resolves to
+  pub fn find_source_line(&self, file_name: &str, line: usize) -> Vec<(String, String, usize)> {
+    let mut results = Vec::new();
+    let mut closest_line = None;
+    for module in self.modules.values() {
+      for function in module.functions.values() {
+        match function.find_source_line(file_name, line) {
+          Some((found_line, program_counter)) => {
+            if closest_line.is_none() || found_line < closest_line.unwrap() {
+              closest_line = Some(found_line);
+              results.clear();
+            }
+            if closest_line == Some(found_line) {
+              results.push((module.name.clone(), function.name.clone(), program_counter));
+            }
+          }
+          None => {}
+        }
+      }
+    }
+    results
+  }
+
+  // Value::type_of can't see other modules so function pointers are looked up here
+  pub fn type_of(&self, value: &Value, current_module: &str) -> Type {
+    match value {
+      Value::FunctionPointer(pointer) => {
+        let module_name = match &pointer.module {
+          Some(module_name) => module_name.as_str(),
+          None => current_module,
+        };
+        match self.modules.get(module_name) {
+          Some(module) => match module.functions.get(pointer.function.as_str()) {
+            Some(function) => function.to_type(),
+            None => match module.intrinsics.get(pointer.function.as_str()) {
+              Some(intrinsic) => Type::FunctionPointer(intrinsic.parameters.clone(), Box::new(Type::Any)),
+              None => Type::FunctionPointer(Vec::new(), Box::new(Type::Any)),
+            },
+          },
+          None => Type::FunctionPointer(Vec::new(), Box::new(Type::Any)),
+        }
+      }
+      Value::Reference(_) => Type::Reference(Box::new(Type::Any)),
+      _ => value.type_of(),
+    }
+  }
+
   pub fn resolve_type(&self, type_to_resolve: Type, context: &ExecutionContext) -> Result<Type, Exception> {
     match type_to_resolve {
       Type::Any => Ok(type_to_resolve),
+      Type::Void => Ok(type_to_resolve),
       Type::Float32 => Ok(type_to_resolve),
       Type::Float64 => Ok(type_to_resolve),
       Type::Boolean => Ok(type_to_resolve),
@@ -81,6 +153,14 @@ impl CompilationUnit {
     }
   }
 
+  // functions that declare a non-void return type have to actually hit a return
+  fn check_missing_return(function: &Function, context: &ExecutionContext) -> Option<String> {
+    match (&function.return_type, &context.return_value) {
+      (Some(Type::Void), _) | (None, _) | (_, Some(_)) => None,
+      (Some(return_type), None) => Some(format!("Function '{}' ended without returning a value of type {:?}", function.name, return_type)),
+    }
+  }
+
   pub fn execute(&self, module_name: String, function_name: String, arguments: Vec<Value>, parent_context: Option<Box<ExecutionContext>>) -> Result<Option<Value>, Exception> {
     let mut context = ExecutionContext {
       parent_execution_context: parent_context,
@@ -98,10 +178,11 @@ impl CompilationUnit {
     };
 
     for (expected_type, got_value) in current_function.parameters.iter().zip(arguments) {
-      if Type::subset(&got_value.type_of(), expected_type) {
+      let got_type = self.type_of(&got_value, module_name.as_str());
+      if Type::subset(&got_type, expected_type) {
         context.stack.push(got_value);
       } else {
-        return Err(Exception::new(context, format!("Unexpected function parameter type found {:?} but expected {:?}", got_value.type_of(), expected_type).as_str()));
+        return Err(Exception::new(context, format!("Unexpected function parameter type found {:?} but expected {:?}", got_type, expected_type).as_str()));
       }
     }
 
@@ -117,6 +198,10 @@ impl CompilationUnit {
       }
     }
 
+    if let Some(message) = Self::check_missing_return(current_function, &context) {
+      return Err(Exception::new(context, message.as_str()));
+    }
+
     Ok(context.return_value.clone())
   }
 
@@ -132,21 +217,16 @@ impl CompilationUnit {
     };
 
     // every function that is called from the code will have a parent_context set. When the parent context is not there then we are in the main function before pc 0
-    match parent_context {
-      Some(_) => {}
-      None => {
-        match debug_context.console(self, &module_name, &mut Some(&mut context), None) {
-          Ok(_) => {}
-          Err(readline_error) => return Err(Exception::new(context, readline_error.to_string().as_str())),
-        }
-        // hacky way of letting the user step immediately when the program runs
-        if debug_context.step.clone().is_some() {
-          debug_context.step = Some(debug_context.step.clone().unwrap() + 1);
-        }
+    let mut entry_stop = parent_context.is_none();
+    if entry_stop {
+      match debug_context.pause(self, &module_name, &mut context, StopReason::Entry) {
+        Ok(_) => {}
+        Err(readline_error) => return Err(Exception::new(context, readline_error.to_string().as_str())),
       }
     }
+    let depth = context.depth();
 
-    debug_context.metric_tracker.start(context.get_call_stack(), "total".to_string());
+    debug_context.start_metric(&context, "total");
 
     let current_function = match self.modules.get(module_name.as_str()) {
       Some(module) => module.functions.get(&*function_name).unwrap(),
@@ -154,19 +234,27 @@ impl CompilationUnit {
     };
 
     for (expected_type, got_value) in current_function.parameters.iter().zip(arguments) {
-      if Type::subset(&got_value.type_of(), expected_type) {
+      let got_type = self.type_of(&got_value, module_name.as_str());
+      if Type::subset(&got_type, expected_type) {
         context.stack.push(got_value);
       } else {
-        debug_context.metric_tracker.stop(context.get_call_stack(), "total".to_string());
-        return Err(Exception::new(context, format!("Unexpected function parameter type found {:?} but expected {:?}", got_value.type_of(), expected_type).as_str()));
+        debug_context.stop_metric(&context, "total");
+        return Err(Exception::new(context, format!("Unexpected function parameter type found {:?} but expected {:?}", got_type, expected_type).as_str()));
       }
     }
 
+    if let Some(coverage) = debug_context.coverage.as_mut() {
+      coverage.entered(module_name.as_str(), current_function);
+    }
+
     while context.program_counter.clone() < current_function.body.len() {
-      // check for break points
-      let should_step_break = debug_context.update_step();
-      if should_step_break || debug_context.is_break_point(module_name.clone(), function_name.clone(), context.program_counter) {
-        match debug_context.console(self, &module_name, &mut Some(&mut context), None) {
+      debug_context.record(&context);
+
+      // check for break points. The first instruction of main was already stopped on by the entry stop
+      let stop_reason = if entry_stop { None } else { debug_context.should_stop(self, &context, depth) };
+      entry_stop = false;
+      if let Some(reason) = stop_reason {
+        match debug_context.pause(self, &module_name, &mut context, reason) {
           Ok(_) => {}
           Err(readline_error) => return Err(Exception::new(context, readline_error.to_string().as_str())),
         }
@@ -175,23 +263,35 @@ impl CompilationUnit {
       //check for profile points here
 
       let inst = current_function.body[context.program_counter.clone()].clone();
+      let program_counter = context.program_counter;
+      let traced = debug_context.trace_start(&context);
       let cont = inst.debug(self, &mut context, debug_context);
+      if let (Some((step, variables_before)), Ok(_)) = (&traced, &cont) {
+        debug_context.trace_end(*step, program_counter, &inst, variables_before, &context);
+      }
+      if let Some(coverage) = debug_context.coverage.as_mut() {
+        coverage.executed(module_name.as_str(), current_function, program_counter, cont.as_ref().ok().map(|_| context.program_counter));
+      }
       match cont {
         Ok(should_continue) if !should_continue => break,
         Err(exception) => {
-          exception.print_stacktrace();
-          match debug_context.console(self, &module_name, &mut Some(&mut context), None) {
+          exception.print_stacktrace(self);
+          match debug_context.pause(self, &module_name, &mut context, StopReason::Exception(exception.message.clone())) {
             Ok(_) => {}
             Err(readline_error) => return Err(Exception::new(context, readline_error.to_string().as_str())),
           }
-          debug_context.metric_tracker.stop(context.get_call_stack(), "total".to_string());
+          debug_context.stop_metric(&context, "total");
           return Err(exception);
         }
         _ => {}
       }
     }
 
-    debug_context.metric_tracker.stop(context.get_call_stack(), "total".to_string());
+    debug_context.stop_metric(&context, "total");
+    if let Some(message) = Self::check_missing_return(current_function, &context) {
+      return Err(Exception::new(context, message.as_str()));
+    }
+    debug_context.returned(&context, depth);
     Ok(context.return_value.clone())
   }
 }
@@ -0,0 +1,156 @@
+use crate::hydro::function::Function;
+use crate::hydro::instruction::Instruction;
+use std::collections::BTreeSet;
+
+#[derive(Debug, Clone)]
+pub struct BasicBlock {
+  pub id: usize,
+  // the instructions in [start, end)
+  pub start: usize,
+  pub end: usize,
+  pub labels: Vec<String>,
+  pub successors: Vec<usize>,
+  pub predecessors: Vec<usize>,
+}
+
+impl BasicBlock {
+  pub fn last_instruction(&self) -> usize {
+    self.end - 1
+  }
+
+  pub fn contains(&self, program_counter: usize) -> bool {
+    self.start <= program_counter && program_counter < self.end
+  }
+}
+
+// Basic blocks start at the entry, at labels, at jump targets, and after any Jump, Branch, or Return
+#[derive(Debug, Clone)]
+pub struct ControlFlowGraph {
+  pub blocks: Vec<BasicBlock>,
+  block_of_instruction: Vec<usize>,
+}
+
+impl ControlFlowGraph {
+  pub fn build(function: &Function) -> Self {
+    if function.body.is_empty() {
+      return Self { blocks: Vec::new(), block_of_instruction: Vec::new() };
+    }
+
+    let mut leaders = BTreeSet::from([0]);
+    leaders.extend(function.jump_labels.values().filter(|index| **index < function.body.len()).cloned());
+    for (index, instruction) in function.body.iter().enumerate() {
+      match instruction {
+        Instruction::Jump(_) | Instruction::Branch(_) | Instruction::Return(_) => {
+          if index + 1 < function.body.len() {
+            leaders.insert(index + 1);
+          }
+          leaders.extend(function.known_successors(index));
+        }
+        _ => {}
+      }
+    }
+
+    let leaders = leaders.into_iter().collect::<Vec<usize>>();
+    let mut blocks = Vec::new();
+    let mut block_of_instruction = vec![0; function.body.len()];
+    for (id, start) in leaders.iter().enumerate() {
+      let end = leaders.get(id + 1).cloned().unwrap_or(function.body.len());
+      for index in *start..end {
+        block_of_instruction[index] = id;
+      }
+      let mut labels = function.jump_labels.iter().filter(|(_, index)| **index == *start).map(|(label, _)| label.clone()).collect::<Vec<String>>();
+      labels.sort();
+      blocks.push(BasicBlock { id, start: *start, end, labels, successors: Vec::new(), predecessors: Vec::new() });
+    }
+
+    for id in 0..blocks.len() {
+      let mut successors = function.known_successors(blocks[id].last_instruction()).iter().map(|successor| block_of_instruction[*successor]).collect::<Vec<usize>>();
+      successors.dedup();
+      for successor in &successors {
+        blocks[*successor].predecessors.push(id);
+      }
+      blocks[id].successors = successors;
+    }
+
+    Self { blocks, block_of_instruction }
+  }
+
+  pub fn entry(&self) -> Option<&BasicBlock> {
+    self.blocks.first()
+  }
+
+  pub fn block_containing(&self, program_counter: usize) -> Option<&BasicBlock> {
+    self.block_of_instruction.get(program_counter).map(|id| &self.blocks[*id])
+  }
+
+  // the name a block is shown with, its first label if it has one
+  pub fn block_name(&self, id: usize) -> String {
+    match self.blocks[id].labels.first() {
+      Some(label) => label.clone(),
+      None => format!("block{}", id),
+    }
+  }
+
+  // blocks that are the target of an edge coming from the same or a later block
+  pub fn loop_heads(&self) -> Vec<usize> {
+    let mut heads = self.blocks.iter().flat_map(|block| block.successors.iter().filter(|successor| **successor <= block.id).cloned().collect::<Vec<usize>>()).collect::<Vec<usize>>();
+    heads.sort();
+    heads.dedup();
+    heads
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::hydro::function::Target;
+  use crate::hydro::instruction::Jump;
+  use crate::hydro::testing::function_with_body;
+
+  // (start, end, labels, successors) of every block
+  fn shape(graph: &ControlFlowGraph) -> Vec<(usize, usize, Vec<String>, Vec<usize>)> {
+    graph.blocks.iter().map(|block| (block.start, block.end, block.labels.clone(), block.successors.clone())).collect()
+  }
+
+  #[test]
+  fn splits_blocks_at_labels_and_after_control_flow() {
+    #[rustfmt::skip]
+    let function = function_with_body("f returns s32", &[
+      "push s32 0", "push bool true", "branch loop done",
+      "label loop", "push s32 1", "jump check",
+      "label check", "duplicate", "push bool false", "branch loop done",
+      "push s32 7",
+      "label done", "return",
+      "push s32 1", "return",
+    ]);
+    let graph = ControlFlowGraph::build(&function);
+    let labels = |label: &str| vec![label.to_string()];
+    assert_eq!(
+      shape(&graph),
+      vec![
+        (0, 3, vec![], vec![1, 4]),
+        (3, 5, labels("loop"), vec![2]),
+        (5, 8, labels("check"), vec![1, 4]),
+        // a branch ends its block even when the next instruction isn't a target
+        (8, 9, vec![], vec![4]),
+        (9, 10, labels("done"), vec![]),
+        // nothing reaches past the return but it is still its own block
+        (10, 12, vec![], vec![]),
+      ]
+    );
+    assert_eq!(graph.blocks[4].predecessors, vec![0, 2, 3]);
+    assert_eq!(graph.blocks[1].predecessors, vec![0, 2]);
+    assert_eq!(graph.loop_heads(), vec![1]);
+    assert_eq!((graph.block_name(1), graph.block_name(3)), ("loop".to_string(), "block3".to_string()));
+    assert_eq!(graph.block_containing(6).map(|block| block.id), Some(2));
+  }
+
+  #[test]
+  fn splits_blocks_at_index_targets() {
+    let mut function = function_with_body("f returns s32", &["push s32 1", "jump somewhere", "push s32 2", "push s32 3", "return"]);
+    function.body[1] = Instruction::Jump(Jump { target: Target::Index(3) });
+    let graph = ControlFlowGraph::build(&function);
+    assert_eq!(shape(&graph), vec![(0, 2, vec![], vec![2]), (2, 3, vec![], vec![2]), (3, 5, vec![], vec![])]);
+    assert!(ControlFlowGraph::build(&function_with_body("f", &[])).entry().is_none());
+  }
+}
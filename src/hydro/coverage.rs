@@ -0,0 +1,166 @@
+use crate::hydro::compilationunit::CompilationUnit;
+use crate::hydro::function::Function;
+use crate::hydro::instruction::Instruction;
+use crate::util::lcov::{ratio, LcovReport};
+use std::collections::{BTreeMap, HashMap};
+
+// Which instructions of every function ran and which way every branch went. Lines in the lcov report take the count of
+// their most run instruction and each branch instruction becomes a block with a true (0) and a false (1) branch
+
+#[derive(Debug, Clone, Default)]
+pub struct FunctionCoverage {
+  pub calls: u64,
+  // indexed by program counter
+  pub hits: Vec<u64>,
+  // program counter of a branch to how many times it went to the true and the false target
+  pub branches: BTreeMap<usize, [u64; 2]>,
+}
+
+impl FunctionCoverage {
+  fn new(function: &Function) -> Self {
+    Self { calls: 0, hits: vec![0; function.body.len()], branches: BTreeMap::new() }
+  }
+
+  pub fn instructions_hit(&self) -> usize {
+    self.hits.iter().filter(|hits| **hits > 0).count()
+  }
+
+  pub fn directions_hit(&self) -> usize {
+    self.branches.values().map(|directions| directions.iter().filter(|taken| **taken > 0).count()).sum()
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct Coverage {
+  functions: HashMap<String, HashMap<String, FunctionCoverage>>,
+}
+
+impl Coverage {
+  pub fn new() -> Self {
+    Self { functions: HashMap::new() }
+  }
+
+  pub fn entered(&mut self, module_name: &str, function: &Function) {
+    self.function_mut(module_name, function).calls += 1;
+  }
+
+  // called once the instruction at program_counter is done. next_program_counter is None when it threw
+  pub fn executed(&mut self, module_name: &str, function: &Function, program_counter: usize, next_program_counter: Option<usize>) {
+    let coverage = self.function_mut(module_name, function);
+    coverage.hits[program_counter] += 1;
+    if let (Some(Instruction::Branch(branch)), Some(next_program_counter)) = (function.body.get(program_counter), next_program_counter) {
+      let direction = if function.get_target_pointer(branch.true_target.clone()) == Ok(next_program_counter) { 0 } else { 1 };
+      coverage.branches.entry(program_counter).or_insert([0, 0])[direction] += 1;
+    }
+  }
+
+  fn function_mut(&mut self, module_name: &str, function: &Function) -> &mut FunctionCoverage {
+    self.functions.entry(module_name.to_string()).or_default().entry(function.name.clone()).or_insert_with(|| FunctionCoverage::new(function))
+  }
+
+  pub fn get(&self, module_name: &str, function_name: &str) -> Option<&FunctionCoverage> {
+    self.functions.get(module_name).and_then(|functions| functions.get(function_name))
+  }
+
+  // every function of the compilation unit that has source locations, including the ones that never ran
+  pub fn to_lcov(&self, compilation_unit: &CompilationUnit) -> LcovReport {
+    let mut report = LcovReport::new();
+    for (module_name, function_name, function) in all_functions(compilation_unit) {
+      let file = match function.location.as_ref().or(function.source_locations.values().next()) {
+        Some(location) => location.file.clone(),
+        None => continue,
+      };
+      let coverage = self.get(module_name.as_str(), function_name.as_str()).cloned().unwrap_or(FunctionCoverage::new(function));
+      let file_coverage = report.files.entry(file).or_default();
+      let first_line = function.location.as_ref().map(|location| location.line).or(function.source_locations.values().map(|location| location.line).min()).unwrap_or(1);
+      file_coverage.functions.insert(format!("{}.{}", module_name, function_name), (first_line, coverage.calls));
+
+      for (program_counter, instruction) in function.body.iter().enumerate() {
+        let line = match function.get_source_location(program_counter) {
+          Some(location) => location.line,
+          None => continue,
+        };
+        let hits = coverage.hits[program_counter];
+        let line_hits = file_coverage.lines.entry(line).or_insert(0);
+        *line_hits = (*line_hits).max(hits);
+        if let Instruction::Branch(_) = instruction {
+          let directions = coverage.branches.get(&program_counter).cloned().unwrap_or([0, 0]);
+          for (branch, taken) in directions.iter().enumerate() {
+            file_coverage.branches.insert((line, program_counter, branch), if hits == 0 { None } else { Some(*taken) });
+          }
+        }
+      }
+    }
+    report
+  }
+
+  // instruction and branch direction coverage for every module and function
+  pub fn summary(&self, compilation_unit: &CompilationUnit) -> Vec<String> {
+    let mut lines = vec![format!("{:<40} {:>8} {:>20} {:>20}", "function", "calls", "instructions", "branch directions")];
+    for module_name in compilation_unit.get_module_names() {
+      let mut function_lines = Vec::new();
+      let (mut instructions_hit, mut instructions, mut directions_hit, mut directions) = (0, 0, 0, 0);
+      for (function_name, function) in module_functions(compilation_unit, module_name.as_str()) {
+        let coverage = self.get(module_name.as_str(), function_name.as_str()).cloned().unwrap_or(FunctionCoverage::new(function));
+        let branch_directions = function.body.iter().filter(|instruction| matches!(instruction, Instruction::Branch(_))).count() * 2;
+        instructions_hit += coverage.instructions_hit();
+        instructions += function.body.len();
+        directions_hit += coverage.directions_hit();
+        directions += branch_directions;
+        function_lines.push(format!("  {:<38} {:>8} {:>20} {:>20}", function_name, coverage.calls, ratio(coverage.instructions_hit(), function.body.len()), ratio(coverage.directions_hit(), branch_directions)));
+      }
+      if !function_lines.is_empty() {
+        lines.push(format!("{:<40} {:>8} {:>20} {:>20}", module_name, "", ratio(instructions_hit, instructions), ratio(directions_hit, directions)));
+        lines.append(&mut function_lines);
+      }
+    }
+    lines
+  }
+}
+
+fn all_functions(compilation_unit: &CompilationUnit) -> Vec<(String, String, &Function)> {
+  let mut functions = Vec::new();
+  for module_name in compilation_unit.get_module_names() {
+    for (function_name, function) in module_functions(compilation_unit, module_name.as_str()) {
+      functions.push((module_name.clone(), function_name, function));
+    }
+  }
+  functions
+}
+
+// (function name, function) of a module sorted by name
+fn module_functions<'a>(compilation_unit: &'a CompilationUnit, module_name: &str) -> Vec<(String, &'a Function)> {
+  let mut functions = match compilation_unit.get_module(module_name) {
+    Some(module) => module.functions.iter().map(|(function_name, function)| (function_name.clone(), function)).collect::<Vec<(String, &Function)>>(),
+    None => Vec::new(),
+  };
+  functions.sort_by(|a, b| a.0.cmp(&b.0));
+  functions
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::hydro::testing::{compile, debug_main, headless_debug_context};
+  use crate::hydro::value::Value;
+
+  #[test]
+  fn records_instructions_and_branch_directions() {
+    let source = "module main\n\nfunction positive s32 returns bool body\n    push s32 0\n    greaterthan\n    branch yes no\n    label yes\n    push bool true\n    return\n    label no\n    push bool false\n    return\n\nfunction unused body\n    return\n\nmain body\n    push s32 5\n    push funcp main positive\n    call\n    return\n";
+    let compilation_unit = compile(source);
+    let mut debug_context = headless_debug_context();
+    debug_context.coverage = Some(Coverage::new());
+    let result = debug_main(&compilation_unit, &mut debug_context);
+    assert_eq!(result.unwrap(), Some(Value::Boolean(true)));
+
+    let coverage = debug_context.coverage.unwrap();
+    let positive = coverage.get("main", "positive").unwrap();
+    assert_eq!((positive.calls, positive.instructions_hit(), positive.directions_hit()), (1, 5, 1));
+
+    let report = coverage.to_lcov(&compilation_unit);
+    let file = &report.files["main.h2o"];
+    assert_eq!(file.functions["main.unused"], (14, 0));
+    assert_eq!(file.branches.values().cloned().collect::<Vec<Option<u64>>>(), vec![Some(1), Some(0)]);
+    assert_eq!((file.lines_hit(), file.lines.len()), (9, 12));
+  }
+}
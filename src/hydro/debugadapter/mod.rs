@@ -0,0 +1,665 @@
+use crate::hydro::breakpoint::{Breakpoint, Condition, ValuePath};
+use crate::hydro::compilationunit::CompilationUnit;
+use crate::hydro::debugcontext::{DebugContext, StepMode, StopReason};
+use crate::hydro::executioncontext::ExecutionContext;
+use crate::hydro::inspector::ValueFormatter;
+use crate::hydro::intrinsic::intrinsicmanager::capture_output;
+use crate::hydro::value::Value;
+use crate::hydro::Hydro;
+use rustyline::error::ReadlineError;
+use serde_json::{json, Value as JsonValue};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::io::{BufRead, Write};
+use std::path::Path;
+use std::rc::Rc;
+use std::sync::mpsc::{channel, Receiver, TryRecvError};
+use std::thread;
+
+// Speaks the Debug Adapter Protocol so editors like VS Code can drive the Hydro debugger.
+// Every message is a JSON object behind a 'Content-Length' header. Requests come in on a channel so we can check for
+// new ones between instructions while the program is running
+
+struct MessageWriter {
+  output: Box<dyn Write>,
+  seq: u64,
+}
+
+impl MessageWriter {
+  fn send(&mut self, mut message: JsonValue) {
+    self.seq += 1;
+    message["seq"] = json!(self.seq);
+    let body = message.to_string();
+    // there is nobody left to tell if the client went away
+    let _ = write!(self.output, "Content-Length: {}\r\n\r\n{}", body.len(), body);
+    let _ = self.output.flush();
+  }
+
+  fn event(&mut self, event: &str, body: JsonValue) {
+    self.send(json!({ "type": "event", "event": event, "body": body }));
+  }
+}
+
+enum Action {
+  None,
+  Launch(JsonValue),
+  ConfigurationDone,
+  Resume(Option<StepMode>),
+  Pause,
+  Abort,
+}
+
+pub struct DebugAdapter {
+  requests: Receiver<JsonValue>,
+  writer: Rc<RefCell<MessageWriter>>,
+  program: Option<String>,
+  stop_on_entry: bool,
+  // the frames of the stopped program with the innermost function first. Empty while the program is running
+  frames: Vec<ExecutionContext>,
+  // the ids of every breakpoint set for a source path so setBreakpoints can replace them
+  source_break_points: HashMap<String, Vec<usize>>,
+  exception_reported: bool,
+  aborted: bool,
+}
+
+impl DebugAdapter {
+  fn new(requests: Receiver<JsonValue>, output: Box<dyn Write>, program: Option<String>) -> Self {
+    Self {
+      requests,
+      writer: Rc::new(RefCell::new(MessageWriter { output, seq: 0 })),
+      program,
+      stop_on_entry: false,
+      frames: Vec::new(),
+      source_break_points: HashMap::new(),
+      exception_reported: false,
+      aborted: false,
+    }
+  }
+
+  // Takes over stdin and stdout. Anything else the process prints is sent to stderr so it can't corrupt the protocol
+  pub fn serve_stdio(program: String) -> io::Result<()> {
+    let output = DebugAdapter::take_stdout()?;
+    let (sender, receiver) = channel();
+    thread::spawn(move || {
+      let stdin = io::stdin();
+      let mut input = stdin.lock();
+      while let Some(message) = DebugAdapter::read_message(&mut input) {
+        if sender.send(message).is_err() {
+          break;
+        }
+      }
+    });
+    DebugAdapter::run(receiver, output, Some(program));
+    Ok(())
+  }
+
+  #[cfg(unix)]
+  fn take_stdout() -> io::Result<Box<dyn Write>> {
+    use std::fs::File;
+    use std::os::unix::io::FromRawFd;
+    io::stdout().flush()?;
+    unsafe {
+      let protocol = libc::dup(libc::STDOUT_FILENO);
+      if protocol < 0 || libc::dup2(libc::STDERR_FILENO, libc::STDOUT_FILENO) < 0 {
+        return Err(io::Error::last_os_error());
+      }
+      Ok(Box::new(File::from_raw_fd(protocol)))
+    }
+  }
+
+  #[cfg(not(unix))]
+  fn take_stdout() -> io::Result<Box<dyn Write>> {
+    Ok(Box::new(io::stdout()))
+  }
+
+  // None once the input is closed or isn't speaking the protocol anymore
+  pub fn read_message(input: &mut impl BufRead) -> Option<JsonValue> {
+    let mut content_length = None;
+    loop {
+      let mut line = String::new();
+      if input.read_line(&mut line).ok()? == 0 {
+        return None;
+      }
+      let line = line.trim_end();
+      if line.is_empty() {
+        if content_length.is_some() {
+          break;
+        }
+      } else if let Some(length) = line.strip_prefix("Content-Length:") {
+        content_length = length.trim().parse::<usize>().ok();
+      }
+    }
+
+    let mut body = vec![0; content_length?];
+    input.read_exact(&mut body).ok()?;
+    serde_json::from_slice(&body).ok()
+  }
+
+  pub fn run(requests: Receiver<JsonValue>, output: Box<dyn Write>, program: Option<String>) {
+    let mut adapter = DebugAdapter::new(requests, output, program);
+    let mut debug_context = DebugContext::new();
+    let mut compilation_unit: Option<CompilationUnit> = None;
+    let mut configured = false;
+
+    while compilation_unit.is_none() || !configured {
+      let Ok(request) = adapter.requests.recv() else {
+        return;
+      };
+      match adapter.handle(&request, &mut debug_context, compilation_unit.as_ref()) {
+        Action::Launch(arguments) => match adapter.launch(&arguments) {
+          Ok(launched) => {
+            compilation_unit = Some(launched);
+            adapter.respond(&request, json!({}));
+            adapter.writer.borrow_mut().event("initialized", json!({}));
+          }
+          Err(message) => adapter.fail(&request, message),
+        },
+        Action::ConfigurationDone => configured = true,
+        Action::Abort => {
+          adapter.writer.borrow_mut().event("terminated", json!({}));
+          return;
+        }
+        _ => {}
+      }
+    }
+    let compilation_unit = compilation_unit.unwrap();
+
+    let writer = adapter.writer.clone();
+    capture_output(Some(Box::new(move |text: String| writer.borrow_mut().event("output", json!({ "category": "stdout", "output": text })))));
+    debug_context.adapter = Some(adapter);
+    let result = compilation_unit.debug("main".to_string(), "main".to_string(), vec![Value::Unsigned32(69)], None, &mut debug_context);
+    capture_output(None);
+    let mut adapter = debug_context.adapter.take().unwrap();
+
+    if !adapter.aborted {
+      let (category, output, exit_code) = match result {
+        Ok(Some(value)) => ("console", format!("Program returned {}\n", ValueFormatter::new(None).format(&value)), 0),
+        Ok(None) => ("console", "Program returned None\n".to_string(), 0),
+        Err(exception) => ("stderr", format!("Exception: {}\n", exception.message), 1),
+      };
+      let mut writer = adapter.writer.borrow_mut();
+      writer.event("output", json!({ "category": category, "output": output }));
+      writer.event("exited", json!({ "exitCode": exit_code }));
+    }
+    adapter.writer.borrow_mut().event("terminated", json!({}));
+    if adapter.aborted {
+      return;
+    }
+
+    // the client still gets to look around and disconnect once the program is done
+    while let Ok(request) = adapter.requests.recv() {
+      if let Action::Abort = adapter.handle(&request, &mut debug_context, Some(&compilation_unit)) {
+        break;
+      }
+    }
+  }
+
+  fn launch(&mut self, arguments: &JsonValue) -> Result<CompilationUnit, String> {
+    if let Some(program) = arguments["program"].as_str() {
+      self.program = Some(program.to_string());
+    }
+    self.stop_on_entry = arguments["stopOnEntry"].as_bool().unwrap_or(false);
+    let Some(program) = &self.program else {
+      return Err("Expected a 'program' to launch".to_string());
+    };
+    // breakpoints come in with absolute paths so the program is compiled with one too
+    let program = match fs::canonicalize(program) {
+      Ok(path) => path.to_string_lossy().to_string(),
+      Err(_) => return Err(format!("Source file not found '{}'", program)),
+    };
+    Hydro::compile(program.as_str()).map_err(|errors| errors.join("\n"))
+  }
+
+  // called when the debugger stops the program. Blocks until the client resumes it
+  pub fn stopped(&mut self, debug_context: &mut DebugContext, compilation_unit: &CompilationUnit, execution_context: &mut ExecutionContext, reason: StopReason) -> Result<(), ReadlineError> {
+    if self.aborted {
+      return Err(ReadlineError::Interrupted);
+    }
+
+    let mut body = match reason {
+      StopReason::Entry => {
+        if self.stop_on_entry {
+          json!({ "reason": "entry" })
+        } else if debug_context.hit_break_point(execution_context) {
+          json!({ "reason": "breakpoint" })
+        } else {
+          return Ok(());
+        }
+      }
+      StopReason::Step => json!({ "reason": "step" }),
+      StopReason::Breakpoint => json!({ "reason": "breakpoint" }),
+      StopReason::Pause => json!({ "reason": "pause" }),
+      StopReason::Watchpoint(message) => json!({ "reason": "data breakpoint", "description": message.clone(), "text": message }),
+      // the exception is passed up through every caller and only needs to be shown once
+      StopReason::Exception(_) if self.exception_reported => return Ok(()),
+      StopReason::Exception(message) => {
+        self.exception_reported = true;
+        json!({ "reason": "exception", "description": "Paused on exception", "text": message })
+      }
+    };
+    body["threadId"] = json!(1);
+    body["allThreadsStopped"] = json!(true);
+    self.writer.borrow_mut().event("stopped", body);
+
+    self.frames = vec![execution_context.clone()];
+    while let Some(parent) = self.frames.last().unwrap().parent_execution_context.clone() {
+      self.frames.push(*parent);
+    }
+
+    loop {
+      let Ok(request) = self.requests.recv() else {
+        self.aborted = true;
+        return Err(ReadlineError::Eof);
+      };
+      match self.handle(&request, debug_context, Some(compilation_unit)) {
+        Action::Resume(step_mode) => {
+          debug_context.step_mode = step_mode;
+          self.frames.clear();
+          return Ok(());
+        }
+        Action::Abort => {
+          self.aborted = true;
+          return Err(ReadlineError::Interrupted);
+        }
+        _ => {}
+      }
+    }
+  }
+
+  // handles whatever came in while the program is running. Returns true if the program should stop
+  pub fn poll(&mut self, debug_context: &mut DebugContext, compilation_unit: &CompilationUnit) -> bool {
+    loop {
+      match self.requests.try_recv() {
+        Ok(request) => match self.handle(&request, debug_context, Some(compilation_unit)) {
+          Action::Pause => return true,
+          Action::Abort => {
+            self.aborted = true;
+            return true;
+          }
+          _ => {}
+        },
+        Err(TryRecvError::Empty) => return false,
+        Err(TryRecvError::Disconnected) => {
+          self.aborted = true;
+          return true;
+        }
+      }
+    }
+  }
+
+  fn handle(&mut self, request: &JsonValue, debug_context: &mut DebugContext, compilation_unit: Option<&CompilationUnit>) -> Action {
+    let arguments = &request["arguments"];
+    match request["command"].as_str().unwrap_or("") {
+      "initialize" => {
+        self.respond(
+          request,
+          json!({
+            "supportsConfigurationDoneRequest": true,
+            "supportsConditionalBreakpoints": true,
+            "supportsHitConditionalBreakpoints": true,
+            "supportsLogPoints": true,
+            "supportsEvaluateForHovers": true,
+            "supportsTerminateRequest": true,
+          }),
+        );
+      }
+      "launch" => match compilation_unit {
+        Some(_) => self.fail(request, "The program was already launched".to_string()),
+        None => return Action::Launch(arguments.clone()),
+      },
+      "setBreakpoints" => {
+        let body = self.set_break_points(arguments, debug_context, compilation_unit);
+        self.respond(request, body);
+      }
+      "setExceptionBreakpoints" => self.respond(request, json!({})),
+      "configurationDone" => {
+        self.respond(request, json!({}));
+        return Action::ConfigurationDone;
+      }
+      "threads" => self.respond(request, json!({ "threads": [{ "id": 1, "name": "main" }] })),
+      "stackTrace" => {
+        let body = self.stack_trace(compilation_unit);
+        self.respond(request, body);
+      }
+      "scopes" => {
+        let frame_id = arguments["frameId"].as_u64().unwrap_or(0);
+        self.respond(
+          request,
+          json!({
+            "scopes": [
+              { "name": "Locals", "variablesReference": frame_id * 2 + 1, "expensive": false },
+              { "name": "Stack", "variablesReference": frame_id * 2 + 2, "expensive": false },
+            ]
+          }),
+        );
+      }
+      "variables" => {
+        let body = self.variables(arguments["variablesReference"].as_u64().unwrap_or(0), compilation_unit);
+        self.respond(request, body);
+      }
+      "evaluate" => match self.evaluate(arguments["expression"].as_str().unwrap_or("").trim(), arguments["frameId"].as_u64().unwrap_or(0) as usize, compilation_unit) {
+        Ok(body) => self.respond(request, body),
+        Err(message) => self.fail(request, message),
+      },
+      "continue" => {
+        self.respond(request, json!({ "allThreadsContinued": true }));
+        return self.resume(None);
+      }
+      "next" => {
+        self.respond(request, json!({}));
+        return self.resume(self.frames.first().map(|frame| StepMode::Over(frame.depth())));
+      }
+      "stepIn" => {
+        self.respond(request, json!({}));
+        return self.resume(Some(StepMode::Into));
+      }
+      "stepOut" => {
+        self.respond(request, json!({}));
+        return self.resume(self.frames.first().map(|frame| StepMode::Out(frame.depth())));
+      }
+      "pause" => {
+        self.respond(request, json!({}));
+        if self.frames.is_empty() {
+          return Action::Pause;
+        }
+      }
+      "disconnect" | "terminate" => {
+        self.respond(request, json!({}));
+        return Action::Abort;
+      }
+      command => self.fail(request, format!("Unsupported request '{}'", command)),
+    }
+    Action::None
+  }
+
+  // resuming only means something while the program is stopped
+  fn resume(&self, step_mode: Option<StepMode>) -> Action {
+    if self.frames.is_empty() {
+      Action::None
+    } else {
+      Action::Resume(step_mode)
+    }
+  }
+
+  pub fn output(&self, category: &str, output: String) {
+    self.writer.borrow_mut().event("output", json!({ "category": category, "output": output }));
+  }
+
+  fn respond(&self, request: &JsonValue, body: JsonValue) {
+    self.writer.borrow_mut().send(json!({
+      "type": "response",
+      "request_seq": request["seq"],
+      "command": request["command"],
+      "success": true,
+      "body": body,
+    }));
+  }
+
+  fn fail(&self, request: &JsonValue, message: String) {
+    self.writer.borrow_mut().send(json!({
+      "type": "response",
+      "request_seq": request["seq"],
+      "command": request["command"],
+      "success": false,
+      "message": message,
+    }));
+  }
+
+  fn source(file: &str) -> JsonValue {
+    let name = Path::new(file).file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or(file.to_string());
+    json!({ "name": name, "path": file })
+  }
+
+  fn set_break_points(&mut self, arguments: &JsonValue, debug_context: &mut DebugContext, compilation_unit: Option<&CompilationUnit>) -> JsonValue {
+    let path = arguments["source"]["path"].as_str().or(arguments["source"]["name"].as_str()).unwrap_or("").to_string();
+    for id in self.source_break_points.remove(&path).unwrap_or_default() {
+      debug_context.delete_break_point(id);
+    }
+
+    let requested = match arguments["breakpoints"].as_array() {
+      Some(break_points) => break_points.clone(),
+      None => arguments["lines"].as_array().map(|lines| lines.iter().map(|line| json!({ "line": line })).collect()).unwrap_or_default(),
+    };
+
+    let mut results = Vec::new();
+    let mut ids = Vec::new();
+    for source_break_point in requested {
+      let line = source_break_point["line"].as_u64().unwrap_or(0);
+      let found = match compilation_unit {
+        Some(compilation_unit) => compilation_unit.find_source_line(path.as_str(), line as usize),
+        None => Vec::new(),
+      };
+      let verified_line = found
+        .first()
+        .and_then(|(module_name, function_name, program_counter)| compilation_unit?.get_source_location(module_name, function_name, *program_counter))
+        .map(|location| location.line);
+      let Some(verified_line) = verified_line else {
+        results.push(json!({ "verified": false, "line": line, "message": "Could not find any instructions at or after this line" }));
+        continue;
+      };
+      let condition = match source_break_point["condition"].as_str().filter(|condition| !condition.trim().is_empty()) {
+        Some(condition) => match Condition::parse(condition) {
+          Ok(condition) => Some(condition),
+          Err(message) => {
+            results.push(json!({ "verified": false, "line": line, "message": message }));
+            continue;
+          }
+        },
+        None => None,
+      };
+      // a hit condition of n stops on the nth hit and every one after it
+      let ignore_count = match source_break_point["hitCondition"].as_str().map(|hit_condition| hit_condition.trim().trim_start_matches(">=").trim().parse::<usize>()) {
+        Some(Ok(hits)) => hits.saturating_sub(1),
+        Some(Err(_)) => {
+          results.push(json!({ "verified": false, "line": line, "message": "Expected the hit condition to be a number" }));
+          continue;
+        }
+        None => 0,
+      };
+
+      for (module_name, function_name, program_counter) in found {
+        let mut break_point = Breakpoint::new(program_counter);
+        break_point.condition = condition.clone();
+        break_point.ignore_count = ignore_count;
+        break_point.log_message = source_break_point["logMessage"].as_str().map(|message| message.to_string());
+        ids.push(debug_context.set_break_point(module_name, function_name, break_point));
+      }
+      results.push(json!({ "verified": true, "line": verified_line, "source": DebugAdapter::source(path.as_str()) }));
+    }
+    self.source_break_points.insert(path, ids);
+    json!({ "breakpoints": results })
+  }
+
+  fn stack_trace(&self, compilation_unit: Option<&CompilationUnit>) -> JsonValue {
+    let mut stack_frames = Vec::new();
+    for (id, frame) in self.frames.iter().enumerate() {
+      let mut stack_frame = json!({
+        "id": id,
+        "name": format!("{}.{}", frame.current_module, frame.current_function),
+        "line": 0,
+        "column": 0,
+      });
+      if let Some(location) = compilation_unit.and_then(|compilation_unit| compilation_unit.get_source_location(frame.current_module.as_str(), frame.current_function.as_str(), frame.program_counter)) {
+        stack_frame["line"] = json!(location.line);
+        stack_frame["column"] = json!(location.column);
+        stack_frame["source"] = DebugAdapter::source(location.file.as_str());
+      }
+      stack_frames.push(stack_frame);
+    }
+    json!({ "stackFrames": stack_frames, "totalFrames": self.frames.len() })
+  }
+
+  fn variable(name: String, value: &Value, frame: &ExecutionContext, compilation_unit: Option<&CompilationUnit>) -> JsonValue {
+    let value_type = match compilation_unit {
+      Some(compilation_unit) => compilation_unit.type_of(value, frame.current_module.as_str()),
+      None => value.type_of(),
+    };
+    json!({ "name": name, "value": ValueFormatter::new(Some(frame)).format(value), "type": format!("{:?}", value_type), "variablesReference": 0 })
+  }
+
+  // odd references are the locals of a frame and even references are its operand stack
+  fn variables(&self, reference: u64, compilation_unit: Option<&CompilationUnit>) -> JsonValue {
+    if reference == 0 {
+      return json!({ "variables": [] });
+    }
+    let Some(frame) = self.frames.get(((reference - 1) / 2) as usize) else {
+      return json!({ "variables": [] });
+    };
+
+    let variables = if reference % 2 == 1 {
+      let mut names = frame.variables.keys().collect::<Vec<&String>>();
+      names.sort();
+      names.into_iter().map(|name| DebugAdapter::variable(name.clone(), &frame.variables[name], frame, compilation_unit)).collect::<Vec<JsonValue>>()
+    } else {
+      frame.stack.iter().rev().enumerate().map(|(index, value)| DebugAdapter::variable(format!("[{}]", index), value, frame, compilation_unit)).collect::<Vec<JsonValue>>()
+    };
+    json!({ "variables": variables })
+  }
+
+  // supports 'stack' and anything a breakpoint condition can read
+  fn evaluate(&self, expression: &str, frame_id: usize, compilation_unit: Option<&CompilationUnit>) -> Result<JsonValue, String> {
+    let Some(frame) = self.frames.get(frame_id) else {
+      return Err("Expressions can only be evaluated while the program is stopped".to_string());
+    };
+
+    if expression == "stack" {
+      let formatter = ValueFormatter::new(Some(frame));
+      let values = frame.stack.iter().rev().map(|value| formatter.format(value)).collect::<Vec<String>>();
+      return Ok(json!({ "result": format!("[{}]", values.join(", ")), "variablesReference": 0 }));
+    }
+    let value = ValuePath::parse(expression)?.resolve(frame)?;
+    let variable = DebugAdapter::variable(expression.to_string(), &value, frame, compilation_unit);
+    Ok(json!({ "result": variable["value"], "type": variable["type"], "variablesReference": 0 }))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::hydro::value::{Array, Type};
+  use std::io::Cursor;
+  use std::sync::mpsc::{RecvTimeoutError, Sender};
+  use std::time::Duration;
+
+  // hands every flushed message to the test
+  struct ChannelWriter {
+    buffer: Vec<u8>,
+    sender: Sender<Vec<u8>>,
+  }
+
+  impl Write for ChannelWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+      self.buffer.extend_from_slice(buf);
+      Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+      let _ = self.sender.send(std::mem::take(&mut self.buffer));
+      Ok(())
+    }
+  }
+
+  fn transcripts_directory() -> String {
+    format!("{}/src/hydro/debugadapter/transcripts", env!("CARGO_MANIFEST_DIR"))
+  }
+
+  fn substitute(value: &mut JsonValue) {
+    match value {
+      JsonValue::String(string) => *string = string.replace("${transcripts}", transcripts_directory().as_str()),
+      JsonValue::Array(values) => values.iter_mut().for_each(substitute),
+      JsonValue::Object(members) => members.values_mut().for_each(substitute),
+      _ => {}
+    }
+  }
+
+  // every member of the expected message has to be in the actual one. Arrays have to match element for element
+  fn matches(expected: &JsonValue, actual: &JsonValue) -> bool {
+    match (expected, actual) {
+      (JsonValue::Object(expected_members), JsonValue::Object(actual_members)) => {
+        expected_members.iter().all(|(name, expected_member)| actual_members.get(name).is_some_and(|actual_member| matches(expected_member, actual_member)))
+      }
+      (JsonValue::Array(expected_values), JsonValue::Array(actual_values)) => {
+        expected_values.len() == actual_values.len() && expected_values.iter().zip(actual_values).all(|(expected_value, actual_value)| matches(expected_value, actual_value))
+      }
+      _ => expected == actual,
+    }
+  }
+
+  // A transcript is a list of messages the client sends and the messages it expects back in order. Sent messages get
+  // their sequence numbers filled in and '${transcripts}' is replaced with the directory of the transcripts
+  fn replay(transcript: &str) {
+    let mut transcript = serde_json::from_str::<JsonValue>(transcript).unwrap();
+    substitute(&mut transcript);
+
+    let (request_sender, request_receiver) = channel();
+    let (output_sender, output_receiver) = channel();
+    let server = thread::spawn(move || DebugAdapter::run(request_receiver, Box::new(ChannelWriter { buffer: Vec::new(), sender: output_sender }), None));
+
+    let mut seq = 0;
+    for (index, entry) in transcript.as_array().unwrap().iter().enumerate() {
+      if let Some(request) = entry.get("send") {
+        seq += 1;
+        let mut request = request.clone();
+        request["seq"] = json!(seq);
+        request["type"] = json!("request");
+        request_sender.send(request).unwrap();
+      } else {
+        let expected = &entry["expect"];
+        let bytes = match output_receiver.recv_timeout(Duration::from_secs(10)) {
+          Ok(bytes) => bytes,
+          Err(RecvTimeoutError::Timeout) => panic!("Timed out waiting for message {}: {}", index, expected),
+          Err(RecvTimeoutError::Disconnected) => panic!("The adapter stopped before message {}: {}", index, expected),
+        };
+        let actual = DebugAdapter::read_message(&mut Cursor::new(bytes)).unwrap();
+        assert!(matches(expected, &actual), "Message {} did not match\nexpected: {}\nactual: {}", index, expected, actual);
+      }
+    }
+
+    drop(request_sender);
+    server.join().unwrap();
+  }
+
+  #[test]
+  fn the_stack_shows_bytes_that_are_not_utf8() {
+    let (_request_sender, request_receiver) = channel();
+    let mut adapter = DebugAdapter::new(request_receiver, Box::new(io::sink()), None);
+    let bytes = Value::Array(Array::new(Type::Unsigned8, Box::new(Value::Unsigned64(1)), vec![Value::Unsigned8(255)]));
+    adapter.frames.push(ExecutionContext {
+      parent_execution_context: None,
+      stack: vec![Value::Signed32(1), bytes],
+      program_counter: 0,
+      variables: HashMap::new(),
+      return_value: None,
+      current_function: "main".to_string(),
+      current_module: "main".to_string(),
+    });
+    assert_eq!(adapter.evaluate("stack", 0, None).unwrap()["result"], "[[255], 1]");
+  }
+
+  #[test]
+  fn breakpoints_and_inspection() {
+    replay(include_str!("transcripts/breakpoints.json"));
+  }
+
+  #[test]
+  fn stepping() {
+    replay(include_str!("transcripts/stepping.json"));
+  }
+
+  #[test]
+  fn exceptions() {
+    replay(include_str!("transcripts/exception.json"));
+  }
+
+  #[test]
+  fn conditions_and_logpoints() {
+    replay(include_str!("transcripts/conditions.json"));
+  }
+
+  #[test]
+  fn pausing() {
+    replay(include_str!("transcripts/pause.json"));
+  }
+}
@@ -1,16 +1,47 @@
+use crate::hydro::analyzer::effects::EffectAnalysis;
+use crate::hydro::breakpoint::{interpolate, Breakpoint, Condition, PathRoot, ValuePath, Watchpoint};
 use crate::hydro::compilationunit::CompilationUnit;
+use crate::hydro::controlflowgraph::ControlFlowGraph;
+use crate::hydro::coverage::Coverage;
+use crate::hydro::debugadapter::DebugAdapter;
 use crate::hydro::debugcontext::DebugConsoleCommandState::{ContinueConsole, ExitProgram, StartResumeExecution};
+use crate::hydro::executable::Executable;
 use crate::hydro::executioncontext::ExecutionContext;
 use crate::hydro::frontend::parser::Parser;
-use crate::hydro::value::Value;
+use crate::hydro::function::Function;
+use crate::hydro::inspector::ValueFormatter;
+use crate::hydro::instruction::{Call, Instruction};
+use crate::hydro::profiler::{hot_spots, share, HotSpotKind, InstructionProfile};
+use crate::hydro::recording::{ExecutionRecording, FrameState};
+use crate::hydro::reload::{compare, live_layouts};
+use crate::hydro::sampler::Sampler;
+use crate::hydro::trace::TraceWriter;
+use crate::hydro::value::{FunctionPointer, Value};
+use crate::hydro::Hydro;
+use crate::hydro::visualizer::callgraphvisualization::CallGraphVisualization;
+use crate::hydro::visualizer::controlflowvisualization::ControlFlowVisualization;
 use crate::hydro::visualizer::moduledependencyvisualization::ModuleDependencyVisualization;
+use crate::hydro::visualizer::GraphVisualization;
 use crate::util::metrictracker::{MetricResults, MetricTracker};
-use crate::util::debug_args::{DebugCli, DebugCommand, Visualization, TimeScale};
+use crate::util::debug_args::{BreakpointArg, DebugCli, DebugCommand, Visualization, TimeScale};
 
 use crate::clap::Parser as ClapParser;
 use rustyline::error::ReadlineError;
 use rustyline::{DefaultEditor, Result};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::path::Path;
+use std::rc::Rc;
+
+// debugger commands in the project directory that run before anything else, one per line
+pub const INIT_FILE_NAME: &str = ".hydrodebug";
+
+// instructions typed into 'eval' run as this function of the current module
+const EVAL_FUNCTION_NAME: &str = "<eval>";
+// so a loop typed into 'eval' can't hang the debugger
+const EVAL_INSTRUCTION_LIMIT: usize = 1_000_000;
+
+// in bytes
+pub const DEFAULT_RECORDING_BUDGET: usize = 64 * 1024 * 1024;
 
 pub enum DebugConsoleCommandState {
   ContinueConsole,
@@ -18,22 +49,95 @@ pub enum DebugConsoleCommandState {
   StartResumeExecution,
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum StopReason {
+  Entry,
+  Step,
+  Breakpoint,
+  Pause,
+  // the message says what changed
+  Watchpoint(String),
+  Exception(String),
+}
+
+// Stepping by call depth. Over stops at the next instruction that isn't inside of a deeper call and out stops once the
+// current function has returned. Until stops at a program counter of the current function or once it has returned
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StepMode {
+  Into,
+  Over(usize),
+  Out(usize),
+  Until(usize, usize),
+}
+
 pub struct DebugContext {
   pub step: Option<usize>,
+  pub step_mode: Option<StepMode>,
+  // how many more times the step mode has to be satisfied before stopping
+  step_repeat: usize,
+  // set by 'finish' to show what the function stepped out of returned
+  show_return_value: bool,
+  // when set the debugger is driven by a debug adapter client instead of the console
+  pub adapter: Option<DebugAdapter>,
+  // never stops or opens the console so a program can be measured from start to finish
+  pub headless: bool,
 
   pub metric_tracker: MetricTracker,
 
-  pub break_points: HashMap<String, HashMap<String, Vec<usize>>>,
+  pub break_points: HashMap<String, HashMap<String, Vec<Breakpoint>>>,
+  pub watch_points: Vec<Watchpoint>,
+  next_break_point_id: usize,
+  // every instruction that ran so the debugger can step backwards. None when recording is off
+  pub recording: Option<ExecutionRecording>,
+  // how often every instruction ran and for how long
+  pub profile: InstructionProfile,
   pub profile_ranges: HashMap<String, HashMap<String, Vec<(String, usize, usize)>>>,
+  // writes a record of every instruction that runs. None when there is no trace
+  pub trace: Option<TraceWriter>,
+  // which instructions ran and which way branches went. None when coverage is off
+  pub coverage: Option<Coverage>,
+  // samples the call stack every so often. While it is set calls aren't timed and instructions aren't profiled
+  pub sampler: Option<Sampler>,
+  // the program's entry file so 'reload' can compile it again
+  pub source_file: Option<String>,
+  // the newest version of the program after a reload. Calls made after it run this while frames that were already
+  // running finish with the compilation unit they started in
+  pub reloaded: Option<Rc<CompilationUnit>>,
+
+  // commands the console runs before it asks for input
+  pub script: VecDeque<String>,
+  // leave the program once the script is done instead of asking for more commands
+  pub exit_after_script: bool,
+  script_failed: bool,
+  // where the console history is kept. None keeps no history
+  pub history_file: Option<String>,
 }
 
 impl DebugContext {
   pub fn new() -> Self {
     Self {
       step: None,
+      step_mode: None,
+      step_repeat: 0,
+      show_return_value: false,
+      adapter: None,
+      headless: false,
       metric_tracker: MetricTracker::new(),
       break_points: HashMap::new(),
+      watch_points: Vec::new(),
+      next_break_point_id: 0,
+      recording: Some(ExecutionRecording::new(DEFAULT_RECORDING_BUDGET)),
+      profile: InstructionProfile::new(),
       profile_ranges: HashMap::new(),
+      trace: None,
+      coverage: None,
+      sampler: None,
+      source_file: None,
+      reloaded: None,
+      script: VecDeque::new(),
+      exit_after_script: false,
+      script_failed: false,
+      history_file: Some("history.txt".to_string()),
     }
   }
 
@@ -54,6 +158,7 @@ impl DebugContext {
 
   pub fn console(&mut self, compilation_unit: &CompilationUnit, module: &String, execution_context: &mut Option<&mut ExecutionContext>, final_return_value: Option<Value>) -> Result<()> {
     self.metric_tracker.pause_all();
+    self.profile.pause();
     println!("{}Entering the Hydro Debugger!!{}", DebugContext::ansi_color_code("red"), DebugContext::ansi_color_code("cyan"));
     println!("{}Type 'help' to get a list of debugger commands :){}", DebugContext::ansi_color_code("red"), DebugContext::ansi_color_code("cyan"));
     if final_return_value.is_some() {
@@ -62,7 +167,7 @@ impl DebugContext {
         "{}{}{}",
         DebugContext::ansi_color_code("magenta"),
         match final_return_value {
-          Some(value) => value.to_string(),
+          Some(value) => ValueFormatter::new(None).format(&value),
           None => "None".to_string(),
         },
         DebugContext::ansi_color_code("cyan")
@@ -71,46 +176,81 @@ impl DebugContext {
     print!("{}", DebugContext::ansi_color_code("cyan"));
 
     let mut rl = DefaultEditor::new()?;
-    if rl.load_history("history.txt").is_err() {
-      println!("No previous history.");
+    if let Some(history_file) = &self.history_file {
+      if rl.load_history(history_file).is_err() && self.script.is_empty() {
+        println!("No previous history.");
+      }
     }
     loop {
-      let readline = rl.readline("> ");
+      let from_script = !self.script.is_empty();
+      let readline = match self.script.pop_front() {
+        Some(line) => {
+          println!("> {}", line);
+          Ok(line)
+        }
+        None if self.exit_after_script => self.exit_program(&mut rl),
+        None => rl.readline("> "),
+      };
       match readline {
         Ok(line) => {
-          rl.add_history_entry(line.as_str())?;
+          if !from_script {
+            rl.add_history_entry(line.as_str())?;
+          }
           let mut parsed = line.split_ascii_whitespace().map(|x| x.to_string()).collect::<Vec<String>>();
           parsed.insert(0, "debugger".to_string());
 
           match DebugCli::try_parse_from(parsed) {
             Ok(arguments) => {
-              let should_continue = match arguments.command {
-                DebugCommand::Breakpoint { location, program_counter } => {
-                  let target_module = compilation_unit.get_module(location.module.as_str());
-
-                  let value = match target_module {
-                    Some(module) => match module.functions.get(location.function.as_str()) {
-                      Some(target_function) => Ok(program_counter),/* TODO fix label
-                      match program_counter.parse::<usize>() {
-                        Ok(value) => Ok(value),
-                        Err(_) => match target_function.jump_labels.get(program_counter.as_str()) {
-                          Some(value) => Ok(*value),
-                          None => Err(format!("Label '{}' does not exist for function '{}' in module '{}'", program_counter, function_name, module_name)),
-                        },
-                      },*/
-                      None => Err(format!("Function '{}' does not exist in module '{}'", location.function, location.module)),
-                    },
-                    None => Err(format!("Module '{}' does not exist", location.module)),
-                  };
+              // while going through the recording every command looks at the past instead of the live program
+              let mut past_context = self.recording.as_ref().and_then(|recording| recording.cursor.and_then(|step| recording.context_at(step)));
+              let in_past = past_context.is_some();
+              let mut viewed_context = match past_context.as_mut() {
+                Some(context) => Some(context),
+                None => execution_context.as_deref_mut(),
+              };
+              let execution_context = &mut viewed_context;
+              if in_past && matches!(arguments.command, DebugCommand::Push { .. } | DebugCommand::Pop | DebugCommand::Set { .. } | DebugCommand::Reload { .. }) {
+                println!("ERROR: The past can't be changed. Use 'continue' to go back to the present :(");
+                continue;
+              }
+              if in_past && matches!(arguments.command, DebugCommand::Next { .. } | DebugCommand::Finish | DebugCommand::Until { .. }) {
+                println!("ERROR: Only 'step' moves through the recording. Use 'continue' to go back to the present :(");
+                continue;
+              }
 
-                  match value {
-                    Ok(value) => {
-                      println!("Setting break point at {} -> {} -> pc {}", location.module, location.function, value);
-                      self.set_break_point(location.module, location.function, value);
+              // commands that look code up see the newest version after a reload since that is what new calls run. The ones
+              // about the paused frames keep the compilation unit those frames are running
+              let latest = self.reloaded.clone();
+              let current_unit = latest.as_deref().unwrap_or(compilation_unit);
+              let should_continue = match arguments.command {
+                DebugCommand::Breakpoint { location, condition, ignore, log } => {
+                  let condition = if condition.is_empty() { Ok(None) } else { Condition::parse(condition.join(" ").as_str()).map(Some) };
+                  match condition.and_then(|condition| self.resolve_break_point(current_unit, location).map(|break_points| (condition, break_points))) {
+                    Ok((condition, break_points)) => {
+                      for (module_name, function_name, program_counter) in break_points {
+                        let mut break_point = Breakpoint::new(program_counter);
+                        break_point.condition = condition.clone();
+                        break_point.ignore_count = ignore;
+                        break_point.log_message = if log.is_empty() { None } else { Some(log.join(" ")) };
+                        let description = break_point.describe();
+                        let id = self.set_break_point(module_name.clone(), function_name.clone(), break_point);
+                        match current_unit.get_source_location(module_name.as_str(), function_name.as_str(), program_counter) {
+                          Some(source_location) => println!("Setting break point {} at {} -> {} -> {} ({}:{})", id, module_name, function_name, description, source_location.file, source_location.line),
+                          None => println!("Setting break point {} at {} -> {} -> {}", id, module_name, function_name, description),
+                        }
+                      }
                       Ok(ContinueConsole)
                     }
                     Err(message) => Err(message),
                   }
+                }
+                DebugCommand::Breakpoints => {
+                  self.print_break_points();
+                  Ok(ContinueConsole)
+                }
+                DebugCommand::Call { module: module_name, function, arguments } => match &execution_context {
+                  Some(context) => self.call(current_unit, context, module_name, function, arguments).map(|_| ContinueConsole),
+                  None => Err("Functions can only be called while the program is paused :(".to_string()),
                 },
                 DebugCommand::CallGraph { time_scale } => {
                   // TODO add a filter for the stack to the debug console args
@@ -127,15 +267,89 @@ impl DebugContext {
                   Some(_) => Ok(StartResumeExecution),
                   None => Err("Not in a continuable context :(".to_string()),
                 },
+                DebugCommand::Delete { id } => {
+                  if self.delete_break_point(id) {
+                    println!("Deleted {}", id);
+                    Ok(ContinueConsole)
+                  } else {
+                    Err(format!("There is no breakpoint or watchpoint {} :(", id))
+                  }
+                }
+                DebugCommand::Disable { id } | DebugCommand::Enable { id } => {
+                  let enabled = matches!(arguments.command, DebugCommand::Enable { .. });
+                  if self.enable_break_point(id, enabled) {
+                    println!("{} {}", if enabled { "Enabled" } else { "Disabled" }, id);
+                    Ok(ContinueConsole)
+                  } else {
+                    Err(format!("There is no breakpoint or watchpoint {} :(", id))
+                  }
+                }
+                DebugCommand::Effects { module: effects_module, function } => {
+                  let module_name = effects_module.clone().or(execution_context.as_ref().map(|context| context.current_module.clone())).unwrap_or(module.clone());
+                  match current_unit.get_module(module_name.as_str()) {
+                    Some(found_module) => {
+                      let analysis = EffectAnalysis::run(current_unit);
+                      let mut function_names = match function {
+                        Some(function_name) => vec![function_name.clone()],
+                        None => found_module.functions.keys().chain(found_module.intrinsics.keys()).cloned().collect::<Vec<String>>(),
+                      };
+                      function_names.sort();
+                      for function_name in function_names {
+                        if found_module.functions.contains_key(function_name.as_str()) || found_module.intrinsics.contains_key(function_name.as_str()) {
+                          println!("{}.{}: {}", module_name, function_name, analysis.get(module_name.as_str(), function_name.as_str()).labels().join(", "));
+                        } else {
+                          println!("Could not find function '{}' in module '{}' :(", function_name, module_name);
+                        }
+                      }
+                      Ok(ContinueConsole)
+                    }
+                    None => Err(format!("Could not find module '{}' :(", module_name)),
+                  }
+                }
+                DebugCommand::Eval { instructions } => match &execution_context {
+                  Some(context) => self.eval(current_unit, context, instructions.join(" ").replace(';', "\n")).map(|_| ContinueConsole),
+                  None => Err("There is no current execution context to evaluate in :(".to_string()),
+                },
                 DebugCommand::Exit => Ok(ExitProgram),
-                DebugCommand::HotPath => {
-                  todo!("I was going to implement this but then I didn't :(");
-                  //Ok(ContinueConsole)
+                DebugCommand::Finish => match &execution_context {
+                  Some(context) => {
+                    println!("Running until {}.{} returns...", context.current_module, context.current_function);
+                    self.step_mode = Some(StepMode::Out(context.depth()));
+                    self.show_return_value = true;
+                    Ok(StartResumeExecution)
+                  }
+                  None => Err("Not in a continuable context :(".to_string()),
+                },
+                DebugCommand::HotPath { count } => {
+                  self.print_hot_path(current_unit, count);
+                  Ok(ContinueConsole)
+                }
+                DebugCommand::Inspect { target, offset, count, depth } => match &execution_context {
+                  Some(context) => match ValuePath::parse(target.as_str()).and_then(|path| path.resolve(context)) {
+                    Ok(value) => {
+                      for line in ValueFormatter::new(Some(context)).inspect(target.as_str(), &value, offset, count, depth) {
+                        println!("{}", line);
+                      }
+                      Ok(ContinueConsole)
+                    }
+                    Err(message) => Err(message),
+                  },
+                  None => Err("Not in a context that has values to inspect :(".to_string()),
                 },
                 DebugCommand::Instruction => match &execution_context {
                   Some(context) => {
                     println!("Module: '{}' Function: '{}' at PC: {}", context.current_module, context.current_function, context.program_counter);
-                    println!("{:?}", compilation_unit.get_module(module).unwrap().functions.get(context.current_function.as_str()).unwrap().body[context.program_counter]);
+                    match compilation_unit.get_source_location(context.current_module.as_str(), context.current_function.as_str(), context.program_counter) {
+                      Some(location) => {
+                        println!("At {}:{}", location.file, location.line);
+                        match location.source_line() {
+                          Some(source) => println!("  {}", source),
+                          None => {}
+                        }
+                      }
+                      None => {}
+                    }
+                    println!("{:?}", compilation_unit.get_function(context.current_module.as_str(), context.current_function.as_str()).unwrap().body[context.program_counter]);
                     Ok(ContinueConsole)
                   }
                   None => Err("We are not in an execution context so there are no current isntructions :(".to_string()),
@@ -151,7 +365,7 @@ impl DebugContext {
                 DebugCommand::Pop => match execution_context {
                   Some(context) => match context.stack.pop() {
                     Some(value) => {
-                      println!("Popped value: {:?}", value);
+                      println!("Popped value: {}", ValueFormatter::new(Some(context)).format(&value));
                       Ok(ContinueConsole)
                     }
                     None => {
@@ -171,17 +385,31 @@ impl DebugContext {
                   },
                   None => Err("Not in a context that has a stack :(".to_string()),
                 },
+                DebugCommand::Next { count } => match &execution_context {
+                  Some(context) => {
+                    println!("Stepping over {}...", count);
+                    self.step_mode = Some(StepMode::Over(context.depth()));
+                    self.step_repeat = count.saturating_sub(1);
+                    Ok(StartResumeExecution)
+                  }
+                  None => Err("Not in a continuable context :(".to_string()),
+                },
                 DebugCommand::Run => match &execution_context {
                   Some(_) => Ok(StartResumeExecution),
                   None => Err("Not in a runnable context :(".to_string()),
                 },
+                DebugCommand::Set { target, value_type, value } => match execution_context {
+                  Some(context) => self.set(context, target, value_type, value).map(|_| ContinueConsole),
+                  None => Err("There is no current execution context to change :(".to_string()),
+                },
                 DebugCommand::Stack { size } => match &execution_context {
                   Some(context) => {
                     let length = context.stack.len() - size.min(context.stack.len());
                     let mut top_of_stack = context.stack.iter().skip(length.max(0)).map(|x| x.clone()).collect::<Vec<Value>>();
                     top_of_stack.reverse();
+                    let formatter = ValueFormatter::new(Some(context));
                     for (value, idx) in top_of_stack.iter().zip(0..top_of_stack.len()) {
-                      println!("[{}] {:?}", idx, value);
+                      println!("[{}] {}", idx, formatter.format(value));
                     }
                     Ok(ContinueConsole)
                   }
@@ -189,20 +417,110 @@ impl DebugContext {
                 },
                 DebugCommand::Stacktrace => match &execution_context {
                   Some(context) => {
-                    context.print_stacktrace();
+                    context.print_stacktrace(compilation_unit);
                     Ok(ContinueConsole)
                   }
                   None => Err("There is no current execution context to have a stacktrace :(".to_string()),
                 },
-                DebugCommand::Step { count } => {
-                  println!("Stepping by {}...", count);
-                  self.step = Some(count);
-                  Ok(StartResumeExecution)
+                DebugCommand::Step { count } => match self.recording.as_mut().and_then(|recording| recording.cursor.map(|cursor| (cursor, recording.present().unwrap_or(cursor)))) {
+                  // stepping forward through the recording until we are back at the present
+                  Some((cursor, present)) if cursor + (count as u64) < present => {
+                    self.recording.as_mut().unwrap().cursor = Some(cursor + count as u64);
+                    self.print_recorded_step(compilation_unit, cursor + count as u64);
+                    Ok(ContinueConsole)
+                  }
+                  Some((cursor, present)) => {
+                    self.recording.as_mut().unwrap().cursor = None;
+                    let remaining = (cursor + count as u64 - present) as usize;
+                    println!("Back at the present");
+                    if remaining == 0 {
+                      Ok(ContinueConsole)
+                    } else {
+                      println!("Stepping by {}...", remaining);
+                      self.step = Some(remaining);
+                      Ok(StartResumeExecution)
+                    }
+                  }
+                  None => {
+                    println!("Stepping by {}...", count);
+                    self.step = Some(count);
+                    Ok(StartResumeExecution)
+                  }
+                },
+                DebugCommand::StepBack { count } => match self.recording.as_mut() {
+                  Some(recording) => match (recording.current(), recording.first_step()) {
+                    (Some(current), Some(first_step)) if current > first_step => {
+                      let step = current.saturating_sub(count as u64).max(first_step);
+                      recording.cursor = Some(step);
+                      self.print_recorded_step(compilation_unit, step);
+                      Ok(ContinueConsole)
+                    }
+                    _ => Err("Already at the start of the recording :(".to_string()),
+                  },
+                  None => Err("Recording is turned off. Turn it on with 'record --budget <MiB>' :(".to_string()),
+                },
+                DebugCommand::ReverseContinue => match &self.recording {
+                  Some(recording) => match (recording.current(), recording.first_step()) {
+                    (Some(current), Some(first_step)) => {
+                      let found = recording.find_back(current, |_, frames| ExecutionRecording::to_context(frames).is_some_and(|context| self.would_break(&context)));
+                      let step = found.unwrap_or(first_step);
+                      self.recording.as_mut().unwrap().cursor = Some(step);
+                      match found {
+                        Some(_) => println!("Breakpoint hit"),
+                        None => println!("Reached the start of the recording"),
+                      }
+                      self.print_recorded_step(compilation_unit, step);
+                      Ok(ContinueConsole)
+                    }
+                    _ => Err("Nothing has been recorded yet :(".to_string()),
+                  },
+                  None => Err("Recording is turned off. Turn it on with 'record --budget <MiB>' :(".to_string()),
+                },
+                DebugCommand::LastChange { target } => match ValuePath::parse(target.as_str()) {
+                  Ok(path) => self.find_last_change(compilation_unit, &path).map(|_| ContinueConsole),
+                  Err(message) => Err(message),
+                },
+                DebugCommand::Record { budget, off } => {
+                  if off {
+                    self.recording = None;
+                  } else if let Some(budget) = budget {
+                    match self.recording.as_mut() {
+                      Some(recording) => recording.budget = budget * 1024 * 1024,
+                      None => self.recording = Some(ExecutionRecording::new(budget * 1024 * 1024)),
+                    }
+                  }
+                  match &self.recording {
+                    Some(recording) => match (recording.first_step(), recording.present()) {
+                      (Some(first_step), Some(present)) => println!("Recording steps {} to {} using {} of {} bytes", first_step, present, recording.size(), recording.budget),
+                      _ => println!("Recording with a budget of {} bytes. Nothing has been recorded yet", recording.budget),
+                    },
+                    None => println!("Recording is off"),
+                  }
+                  Ok(ContinueConsole)
+                }
+                DebugCommand::Until { position } => match &execution_context {
+                  Some(context) => {
+                    let function = compilation_unit.get_function(context.current_module.as_str(), context.current_function.as_str()).unwrap();
+                    let program_counter = match position.parse::<usize>() {
+                      Ok(program_counter) => Some(program_counter),
+                      Err(_) => function.jump_labels.get(position.as_str()).cloned(),
+                    };
+                    match program_counter {
+                      Some(program_counter) if program_counter < function.body.len() => {
+                        println!("Running until pc {} of {}.{}...", program_counter, context.current_module, context.current_function);
+                        self.step_mode = Some(StepMode::Until(context.depth(), program_counter));
+                        Ok(StartResumeExecution)
+                      }
+                      Some(program_counter) => Err(format!("{}.{} only has {} instructions so it never reaches pc {} :(", context.current_module, context.current_function, function.body.len(), program_counter)),
+                      None => Err(format!("Label '{}' does not exist in {}.{} :(", position, context.current_module, context.current_function)),
+                    }
+                  }
+                  None => Err("Not in a continuable context :(".to_string()),
                 },
                 DebugCommand::Variable { name } => match &execution_context {
                   Some(context) => match context.variables.get(name.as_str()) {
                     Some(value) => {
-                      println!("{} := {:?}", name, value);
+                      println!("{} := {}", name, ValueFormatter::new(Some(context)).format(value));
                       Ok(ContinueConsole)
                     }
                     None => {
@@ -215,46 +533,85 @@ impl DebugContext {
                 DebugCommand::Variables => match &execution_context {
                   Some(context) => {
                     // print that there are no variables if here
-                    for (variable_name, variable_value) in &context.variables {
-                      println!("{} := {:?}", variable_name, variable_value);
+                    let formatter = ValueFormatter::new(Some(context));
+                    let mut variables = context.variables.iter().collect::<Vec<(&String, &Value)>>();
+                    variables.sort_by(|a, b| a.0.cmp(b.0));
+                    for (variable_name, variable_value) in variables {
+                      println!("{} := {}", variable_name, formatter.format(variable_value));
                     }
                     Ok(ContinueConsole)
                   }
                   None => Err("Not in a context that has variables :(".to_string()),
                 },
-                // TODO this should be blocked through a rust trait
-                DebugCommand::Viz { visualization, format, output_file } => match which::which("dot") {
-                  Ok(_) => match visualization {
-                    Visualization::ModDep => {
-                      let viz = ModuleDependencyVisualization::create(compilation_unit, module);
-                      match format.as_str() {
-                        "png" => viz.png(output_file.clone()),
-                        "svg" => viz.svg(output_file.clone()),
-                        _ => {}
-                      }
-                      println!("Output {:?} viz to {} file {}", visualization, format, output_file);
+                DebugCommand::Watch { target } => match &execution_context {
+                  Some(context) => match ValuePath::parse(target.as_str()) {
+                    Ok(path) => {
+                      let watch_point = Watchpoint::new(path, context);
+                      let description = watch_point.describe();
+                      let id = self.set_watch_point(watch_point);
+                      println!("Setting watchpoint {}: {}", id, description);
                       Ok(ContinueConsole)
                     }
+                    Err(message) => Err(message),
                   },
-                  Err(_) => Err("Graphviz not installed. Please install to use visualizations".to_string()),
+                  None => Err("Watchpoints can only be set while the program is running :(".to_string()),
                 },
+                DebugCommand::Reload { force } => self.reload(current_unit, execution_context.as_deref(), force).map(|_| ContinueConsole),
+                DebugCommand::Viz { visualization, format, output_file, module: viz_module, function } => {
+                  let viz: std::result::Result<Box<dyn GraphVisualization>, String> = match visualization {
+                    Visualization::ModDep => Ok(Box::new(ModuleDependencyVisualization::create(current_unit, module))),
+                    Visualization::Cfg => {
+                      let module_name = viz_module.clone().or(execution_context.as_ref().map(|context| context.current_module.clone())).unwrap_or(module.clone());
+                      let function_name = function.clone().or(execution_context.as_ref().map(|context| context.current_function.clone())).unwrap_or("main".to_string());
+                      match current_unit.get_function(module_name.as_str(), function_name.as_str()) {
+                        Some(found_function) => Ok(Box::new(ControlFlowVisualization::create(&module_name, found_function))),
+                        None => Err(format!("Could not find function '{}' in module '{}' :(", function_name, module_name)),
+                      }
+                    }
+                    Visualization::CallGraph => Ok(Box::new(CallGraphVisualization::create(current_unit))),
+                  };
+                  match (viz, output_file, format.as_str()) {
+                    (Ok(viz), None, "text" | "ascii") => {
+                      print!("{}", viz.text(format.as_str() == "text"));
+                      Ok(ContinueConsole)
+                    }
+                    (Ok(_), None, _) => Err(format!("{} visualizations need an output file", format)),
+                    (Ok(viz), Some(output_file), _) => {
+                      let written = match format.as_str() {
+                        "png" => viz.png(output_file.clone()),
+                        "svg" => viz.svg(output_file.clone()),
+                        "dot" => viz.dot(output_file.clone()),
+                        _ => std::fs::write(output_file.clone(), viz.text(format.as_str() == "text")).map_err(|error| error.to_string()),
+                      };
+                      written.map(|_| {
+                        println!("Output {:?} viz to {} file {}", visualization, format, output_file);
+                        ContinueConsole
+                      })
+                    }
+                    (Err(message), _, _) => Err(message),
+                  }
+                }
               };
 
               match should_continue {
-                Ok(ExitProgram) => {
-                  print!("{}", DebugContext::ansi_color_code("reset"));
-                  panic!("Exiting the program...") // TODO this shouldn't panic here
+                Ok(ExitProgram) => self.exit_program(&mut rl),
+                Ok(StartResumeExecution) => {
+                  if let Some(recording) = self.recording.as_mut() {
+                    recording.cursor = None;
+                  }
+                  break;
                 }
-                Ok(StartResumeExecution) => break,
                 Ok(ContinueConsole) => continue,
                 Err(message) => {
                   println!("ERROR: {}", message);
+                  self.script_failed |= from_script;
                   continue;
                 }
               }
             },
             Err(message) => {
               println!("{}", message);
+              self.script_failed |= from_script;
             }
           }
         }
@@ -262,6 +619,8 @@ impl DebugContext {
           print!("{}", DebugContext::ansi_color_code("reset"));
           panic!("CTRL-C -- INTERRUPTED. Exiting...");
         }
+        // stdin ran out, which is what happens when commands are piped in
+        Err(ReadlineError::Eof) => self.exit_program(&mut rl),
         Err(err) => {
           println!("Error: {:?}", err);
         }
@@ -269,11 +628,276 @@ impl DebugContext {
     }
 
     print!("{}", DebugContext::ansi_color_code("reset"));
-    rl.save_history("history.txt")?;
+    if let Some(history_file) = &self.history_file {
+      rl.save_history(history_file)?;
+    }
     self.metric_tracker.start_all();
+    self.profile.resume();
+    Ok(())
+  }
+
+  // the parser panics on bad input so the panic is caught and turned into the error message
+  fn catch_parser_panic<T>(parse: impl FnOnce() -> T + std::panic::UnwindSafe) -> std::result::Result<T, String> {
+    let hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(|_| {}));
+    let result = std::panic::catch_unwind(parse);
+    std::panic::set_hook(hook);
+    result.map_err(|payload| match (payload.downcast_ref::<String>(), payload.downcast_ref::<&str>()) {
+      (Some(message), _) => message.clone(),
+      (None, Some(message)) => message.to_string(),
+      (None, None) => "Could not parse the instructions".to_string(),
+    })
+  }
+
+  fn parse_instructions(source: &str) -> std::result::Result<Function, String> {
+    Self::catch_parser_panic(|| Parser::from_source(EVAL_FUNCTION_NAME, source).parse_body_only(EVAL_FUNCTION_NAME))
+  }
+
+  fn reload(&mut self, compilation_unit: &CompilationUnit, context: Option<&ExecutionContext>, force: bool) -> std::result::Result<(), String> {
+    let source_file = self.source_file.clone().ok_or("There is no source file to reload :(".to_string())?;
+    let new_unit = Self::catch_parser_panic(|| Hydro::compile(source_file.as_str()))?.map_err(|errors| errors.join("\n"))?;
+    let changes = compare(compilation_unit, &new_unit);
+    if changes.is_empty() {
+      println!("Nothing changed in '{}'", source_file);
+      return Ok(());
+    }
+
+    // values that were built with the old shape of a layout would break the new code that reads them
+    let mut broken_layouts = Vec::new();
+    for change in &changes {
+      for layout in &change.layouts {
+        let live = context.map(|context| live_layouts(context, change.module_name.as_str(), layout.layout_name.as_str())).unwrap_or_default();
+        if !live.is_empty() {
+          broken_layouts.push(format!("{}.{} is still held by {}", change.module_name, layout.describe(), live.join(", ")));
+        }
+      }
+    }
+    for broken_layout in &broken_layouts {
+      println!("Layout {}", broken_layout);
+    }
+    if !broken_layouts.is_empty() && !force {
+      return Err("Live values use layouts that changed shape. Use 'reload --force' to swap the new code in anyway :(".to_string());
+    }
+
+    for change in &changes {
+      println!("  {}", change.describe());
+      // breakpoints are kept by program counter so they may be on a different instruction now
+      for function_name in &change.changed_functions {
+        let break_point_count = self.break_points.get(&change.module_name).and_then(|functions| functions.get(function_name)).map(|break_points| break_points.len()).unwrap_or(0);
+        if break_point_count > 0 {
+          println!("  {} breakpoint(s) in {}.{} stay at the same program counters and may need to be set again", break_point_count, change.module_name, function_name);
+        }
+      }
+    }
+    self.reloaded = Some(Rc::new(new_unit));
+    println!("Reloaded '{}'. Running functions finish with the code they started with and new calls use the new code", source_file);
+    Ok(())
+  }
+
+  fn eval(&self, compilation_unit: &CompilationUnit, context: &ExecutionContext, source: String) -> std::result::Result<(), String> {
+    let function = Self::parse_instructions(source.as_str())?;
+    // added to a copy of the current module so labels and calls resolve like they would in the paused function
+    let mut scratch_unit = CompilationUnit::new();
+    scratch_unit.merge(compilation_unit);
+    scratch_unit.get_module_mut(context.current_module.as_str()).unwrap().functions.insert(function.name.clone(), function.clone());
+
+    let mut copy = context.clone();
+    copy.current_function = function.name.clone();
+    copy.program_counter = 0;
+    copy.return_value = None;
+    let mut executed = 0;
+    while copy.program_counter < function.body.len() {
+      if executed == EVAL_INSTRUCTION_LIMIT {
+        return Err(format!("Gave up after running {} instructions :(", EVAL_INSTRUCTION_LIMIT));
+      }
+      executed += 1;
+      match function.body[copy.program_counter].execute(&scratch_unit, &mut copy) {
+        Ok(true) => {}
+        Ok(false) => break,
+        Err(exception) => return Err(format!("Exception: {}", exception.message)),
+      }
+    }
+
+    let formatter = ValueFormatter::new(Some(&copy));
+    if let Some(value) = &copy.return_value {
+      println!("Returned {}", formatter.format(value));
+    }
+    for (index, value) in copy.stack.iter().rev().enumerate() {
+      println!("[{}] {}", index, formatter.format(value));
+    }
+    Ok(())
+  }
+
+  fn set(&self, context: &mut ExecutionContext, target: String, value_type: String, value: String) -> std::result::Result<(), String> {
+    let path = ValuePath::parse(target.as_str())?;
+    let value = Parser::create_value_from_type_string(value_type, value).map_err(|message| format!("Error while parsing value: {}", message))?;
+    // resolving first makes sure the target exists since modify panics on indexes that are out of bounds
+    let old_value = path.resolve(context)?;
+    match (&path.root, path.to_reference()) {
+      (_, Some(reference)) => context.modify(&reference, value.clone()).map_err(|exception| exception.message)?,
+      (PathRoot::Stack(index), None) if path.accessors.is_empty() => {
+        let slot = context.stack.len() - 1 - index;
+        context.stack[slot] = value.clone();
+      }
+      (_, None) => return Err("Only whole stack slots can be set. Use 'pop' and 'push' for values inside of them :(".to_string()),
+    }
+    let formatter = ValueFormatter::new(Some(context));
+    println!("{}: {} -> {}", path.to_string(), formatter.format(&old_value), formatter.format(&value));
+    Ok(())
+  }
+
+  fn call(&self, compilation_unit: &CompilationUnit, context: &ExecutionContext, module_name: String, function_name: String, arguments: Vec<String>) -> std::result::Result<(), String> {
+    let parameter_count = match compilation_unit.get_module(module_name.as_str()) {
+      Some(module) => match (module.functions.get(function_name.as_str()), module.intrinsics.get(function_name.as_str())) {
+        (Some(function), _) => function.parameters.len(),
+        (None, Some(intrinsic)) => intrinsic.parameters.len(),
+        (None, None) => return Err(format!("Could not find function '{}' in module '{}' :(", function_name, module_name)),
+      },
+      None => return Err(format!("Could not find module '{}' :(", module_name)),
+    };
+    if arguments.len() != parameter_count * 2 {
+      return Err(format!("{}.{} takes {} arguments given as type and value pairs like 'u32 3' :(", module_name, function_name, parameter_count));
+    }
+
+    // the call runs on a copy with an empty stack so whatever is left on it afterwards is the return value
+    let mut copy = context.clone();
+    copy.stack = Vec::new();
+    for pair in arguments.chunks(2) {
+      copy.stack.push(Parser::create_value_from_type_string(pair[0].clone(), pair[1].clone()).map_err(|message| format!("Error while parsing value: {}", message))?);
+    }
+    copy.stack.push(Value::FunctionPointer(FunctionPointer { module: Some(module_name.clone()), function: function_name.clone() }));
+    Call {}.execute(compilation_unit, &mut copy).map_err(|exception| format!("Exception: {}", exception.message))?;
+    match copy.stack.pop() {
+      Some(value) => println!("{}.{} returned {}", module_name, function_name, ValueFormatter::new(Some(context)).format(&value)),
+      None => println!("{}.{} returned nothing", module_name, function_name),
+    }
     Ok(())
   }
 
+  // the exit code says whether any scripted command failed so batch sessions can be checked in CI
+  pub fn exit_code(&self) -> i32 {
+    if self.script_failed {
+      1
+    } else {
+      0
+    }
+  }
+
+  fn exit_program(&self, rl: &mut DefaultEditor) -> ! {
+    print!("{}", DebugContext::ansi_color_code("reset"));
+    if let Some(history_file) = &self.history_file {
+      if let Err(error) = rl.save_history(history_file) {
+        println!("Could not save the history to '{}': {}", history_file, error);
+      }
+    }
+    std::process::exit(self.exit_code())
+  }
+
+  // queues one command per line. Blank lines and lines starting with '#' are skipped
+  pub fn load_script(&mut self, script: &str) {
+    self.script.extend(script.lines().map(|line| line.trim()).filter(|line| !line.is_empty() && !line.starts_with('#')).map(|line| line.to_string()));
+  }
+
+  // queues commands separated by ';', like the ones given to --commands. '\;' is a ';' that stays in the command so
+  // eval can be given more than one instruction
+  pub fn load_commands(&mut self, commands: &str) {
+    let mut script = String::new();
+    let mut characters = commands.chars().peekable();
+    while let Some(character) = characters.next() {
+      match character {
+        '\\' if characters.peek() == Some(&';') => script.push(characters.next().unwrap()),
+        ';' => script.push('\n'),
+        _ => script.push(character),
+      }
+    }
+    self.load_script(script.as_str());
+  }
+
+  // loads the init file from the project directory if there is one
+  pub fn load_init_file(&mut self, project_root: &Path) -> std::io::Result<()> {
+    let init_file = project_root.join(INIT_FILE_NAME);
+    if init_file.is_file() {
+      let script = std::fs::read_to_string(&init_file)?;
+      println!("Loading debugger commands from {}", init_file.display());
+      self.load_script(script.as_str());
+    }
+    Ok(())
+  }
+
+  // stops execution and hands control to whoever is driving the debugger until they resume
+  pub fn pause(&mut self, compilation_unit: &CompilationUnit, module: &String, execution_context: &mut ExecutionContext, reason: StopReason) -> Result<()> {
+    if self.headless {
+      return Ok(());
+    }
+    // a breakpoint or exception in the middle of a step cancels it
+    self.step_mode = None;
+    self.step_repeat = 0;
+    self.show_return_value = false;
+    match self.adapter.take() {
+      Some(mut adapter) => {
+        self.metric_tracker.pause_all();
+        self.profile.pause();
+        let result = adapter.stopped(self, compilation_unit, execution_context, reason);
+        self.adapter = Some(adapter);
+        self.metric_tracker.start_all();
+        self.profile.resume();
+        result
+      }
+      None => {
+        if let StopReason::Watchpoint(message) = &reason {
+          println!("{}", message);
+        }
+        self.console(compilation_unit, module, &mut Some(execution_context), None)
+      }
+    }
+  }
+
+  // checked before every instruction. Depth is the number of functions on the call stack
+  pub fn should_stop(&mut self, compilation_unit: &CompilationUnit, context: &ExecutionContext, depth: usize) -> Option<StopReason> {
+    let stepped = match self.step_mode {
+      Some(StepMode::Into) => true,
+      Some(StepMode::Over(step_depth)) => depth <= step_depth,
+      Some(StepMode::Out(step_depth)) => depth < step_depth,
+      Some(StepMode::Until(step_depth, program_counter)) => depth < step_depth || (depth == step_depth && context.program_counter == program_counter),
+      None => false,
+    };
+    if stepped && self.step_repeat > 0 {
+      self.step_repeat -= 1;
+    } else if stepped {
+      self.step_mode = None;
+      return Some(StopReason::Step);
+    }
+    if self.update_step() {
+      return Some(StopReason::Step);
+    }
+    if let Some(message) = self.hit_watch_point(context) {
+      return Some(StopReason::Watchpoint(message));
+    }
+    if self.hit_break_point(context) {
+      return Some(StopReason::Breakpoint);
+    }
+
+    let mut adapter = self.adapter.take()?;
+    let pause_requested = adapter.poll(self, compilation_unit);
+    self.adapter = Some(adapter);
+    if pause_requested {
+      Some(StopReason::Pause)
+    } else {
+      None
+    }
+  }
+
+  // called as a function finishes
+  pub fn returned(&mut self, context: &ExecutionContext, depth: usize) {
+    if self.show_return_value && self.step_mode == Some(StepMode::Out(depth)) {
+      let value = match &context.return_value {
+        Some(value) => ValueFormatter::new(Some(context)).format(value),
+        None => "nothing".to_string(),
+      };
+      println!("{}.{} returned {}", context.current_module, context.current_function, value);
+    }
+  }
+
   // return true if we should enter a debug console
   pub fn update_step(&mut self) -> bool {
     if self.step.is_some() && self.step.unwrap() != 0 {
@@ -286,34 +910,304 @@ impl DebugContext {
     false
   }
 
-  pub fn set_break_point(&mut self, module_name: String, function_name: String, break_point: usize) {
-    match self.break_points.get_mut(module_name.as_str()) {
-      Some(module_break_points) => match module_break_points.get_mut(function_name.as_str()) {
-        Some(function_break_points) => {
-          function_break_points.push(break_point);
-        }
-        None => {
-          module_break_points.insert(function_name.clone(), vec![break_point]);
-        }
+  // resolves the breakpoint command arguments into every (module, function, pc) the breakpoint should be set on
+  fn resolve_break_point(&self, compilation_unit: &CompilationUnit, location: BreakpointArg) -> std::result::Result<Vec<(String, String, usize)>, String> {
+    match (location.function, location.position) {
+      (Some(function_name), Some(position)) => match compilation_unit.get_module(location.target.as_str()) {
+        Some(module) => match module.functions.get(function_name.as_str()) {
+          Some(target_function) => match position.parse::<usize>() {
+            Ok(value) => Ok(vec![(location.target, function_name, value)]),
+            Err(_) => match target_function.jump_labels.get(position.as_str()) {
+              Some(value) => Ok(vec![(location.target, function_name, *value)]),
+              None => Err(format!("Label '{}' does not exist for function '{}' in module '{}'", position, function_name, location.target)),
+            },
+          },
+          None => Err(format!("Function '{}' does not exist in module '{}'", function_name, location.target)),
+        },
+        None => Err(format!("Module '{}' does not exist", location.target)),
       },
-      None => {
-        let mut module_break_points = HashMap::new();
-        module_break_points.insert(function_name.clone(), vec![break_point]);
-        self.break_points.insert(module_name.clone(), module_break_points);
+      (None, None) => match location.target.rsplit_once(':') {
+        Some((file_name, line)) => match line.parse::<usize>() {
+          Ok(line) => {
+            let found = compilation_unit.find_source_line(file_name, line);
+            if found.len() == 0 {
+              Err(format!("Could not find any instructions at or after '{}:{}'", file_name, line))
+            } else {
+              Ok(found)
+            }
+          }
+          Err(_) => Err(format!("Expected a line number after ':' but got '{}'", line)),
+        },
+        None => Err(format!("Expected a location in the form '<file>:<line>' but got '{}'", location.target)),
+      },
+      _ => Err("Expected either '<file>:<line>' or '<module> <function> <pc or label>' for the breakpoint location".to_string()),
+    }
+  }
+
+  // returns the id of the new breakpoint
+  pub fn set_break_point(&mut self, module_name: String, function_name: String, mut break_point: Breakpoint) -> usize {
+    self.next_break_point_id += 1;
+    break_point.id = self.next_break_point_id;
+    self.break_points.entry(module_name).or_default().entry(function_name).or_default().push(break_point);
+    self.next_break_point_id
+  }
+
+  // breakpoints and watchpoints share ids
+  pub fn set_watch_point(&mut self, mut watch_point: Watchpoint) -> usize {
+    self.next_break_point_id += 1;
+    watch_point.id = self.next_break_point_id;
+    self.watch_points.push(watch_point);
+    self.next_break_point_id
+  }
+
+  pub fn delete_break_point(&mut self, id: usize) -> bool {
+    let before = self.break_point_count();
+    for module_break_points in self.break_points.values_mut() {
+      for function_break_points in module_break_points.values_mut() {
+        function_break_points.retain(|break_point| break_point.id != id);
       }
     }
+    self.watch_points.retain(|watch_point| watch_point.id != id);
+    self.break_point_count() != before
   }
 
-  pub fn is_break_point(&self, module_name: String, function_name: String, program_counter: usize) -> bool {
-    match self.break_points.get(module_name.as_str()) {
-      Some(module_break_points) => match module_break_points.get(function_name.as_str()) {
-        Some(function_break_points) => function_break_points.contains(&program_counter),
-        None => false,
-      },
+  pub fn enable_break_point(&mut self, id: usize, enabled: bool) -> bool {
+    let mut found = false;
+    for module_break_points in self.break_points.values_mut() {
+      for break_point in module_break_points.values_mut().flatten().filter(|break_point| break_point.id == id) {
+        break_point.enabled = enabled;
+        found = true;
+      }
+    }
+    for watch_point in self.watch_points.iter_mut().filter(|watch_point| watch_point.id == id) {
+      watch_point.enabled = enabled;
+      found = true;
+    }
+    found
+  }
+
+  fn break_point_count(&self) -> usize {
+    self.break_points.values().flat_map(|module_break_points| module_break_points.values()).map(|function_break_points| function_break_points.len()).sum::<usize>() + self.watch_points.len()
+  }
+
+  // Counts a hit for every enabled breakpoint at the current instruction whose condition holds. Logpoints print their
+  // message here. Returns true if any of them should stop the program
+  pub fn hit_break_point(&mut self, context: &ExecutionContext) -> bool {
+    let Some(function_break_points) = self.break_points.get_mut(&context.current_module).and_then(|module_break_points| module_break_points.get_mut(&context.current_function)) else {
+      return false;
+    };
+
+    let mut should_stop = false;
+    let mut messages = Vec::new();
+    for break_point in function_break_points.iter_mut().filter(|break_point| break_point.enabled && break_point.program_counter == context.program_counter) {
+      match break_point.condition.as_ref().map(|condition| condition.evaluate(context)).unwrap_or(Ok(true)) {
+        Ok(true) => {}
+        Ok(false) => continue,
+        // a broken condition stops so it can be fixed
+        Err(message) => {
+          messages.push(format!("Breakpoint {} condition failed: {}", break_point.id, message));
+          should_stop = true;
+          continue;
+        }
+      }
+      break_point.hit_count += 1;
+      if break_point.hit_count <= break_point.ignore_count {
+        continue;
+      }
+      match &break_point.log_message {
+        Some(message) => messages.push(interpolate(message, context)),
+        None => should_stop = true,
+      }
+    }
+
+    for message in messages {
+      self.log(message);
+    }
+    should_stop
+  }
+
+  // whether a breakpoint would stop at the context without counting a hit or logging anything
+  fn would_break(&self, context: &ExecutionContext) -> bool {
+    match self.break_points.get(&context.current_module).and_then(|module_break_points| module_break_points.get(&context.current_function)) {
+      Some(function_break_points) => function_break_points.iter().any(|break_point| {
+        break_point.enabled && break_point.log_message.is_none() && break_point.program_counter == context.program_counter && break_point.condition.as_ref().map(|condition| condition.evaluate(context).unwrap_or(true)).unwrap_or(true)
+      }),
       None => false,
     }
   }
 
+  fn print_recorded_step(&self, compilation_unit: &CompilationUnit, step: u64) {
+    let Some(recording) = &self.recording else {
+      return;
+    };
+    if let Some(frames) = recording.frames_at(step) {
+      println!("Step {} of {}. Use 'continue' to go back to the present", step, recording.present().unwrap_or(step));
+      if let Some(context) = ExecutionRecording::to_context(&frames[frames.len() - 1..]) {
+        context.print_stacktrace(compilation_unit);
+      }
+    }
+  }
+
+  // Walks back through the recording from the step being looked at to find the instruction that last changed the
+  // value. Only the function call that is being looked at is searched
+  fn find_last_change(&mut self, compilation_unit: &CompilationUnit, path: &ValuePath) -> std::result::Result<(), String> {
+    let Some(recording) = &self.recording else {
+      return Err("Recording is turned off. Turn it on with 'record --budget <MiB>' :(".to_string());
+    };
+    let Some(current) = recording.current() else {
+      return Err("Nothing has been recorded yet :(".to_string());
+    };
+    let frames = recording.frames_at(current).unwrap();
+    let depth = frames.len();
+    let value_at = |frames: &[FrameState]| ExecutionRecording::to_context(&frames[..depth]).and_then(|context| path.resolve(&context).ok());
+    let value = value_at(&frames);
+
+    let mut previous = None;
+    let mut left_call = false;
+    let found = recording.find_back(current, |_, frames| {
+      if frames.len() < depth {
+        left_call = true;
+        return true;
+      }
+      previous = value_at(frames);
+      previous != value
+    });
+
+    let formatter = ValueFormatter::new(None);
+    let describe = |value: &Option<Value>| value.as_ref().map(|value| formatter.format(value)).unwrap_or("<undefined>".to_string());
+    match found {
+      Some(_) if left_call => println!("{} has not changed since {}.{} was called", path.to_string(), frames[depth - 1].module_name, frames[depth - 1].function_name),
+      Some(step) => {
+        println!("{} changed from {} to {}", path.to_string(), describe(&previous), describe(&value));
+        self.recording.as_mut().unwrap().cursor = Some(step);
+        self.print_recorded_step(compilation_unit, step);
+      }
+      None => println!("{} has not changed since the start of the recording", path.to_string()),
+    }
+    Ok(())
+  }
+
+  pub fn record(&mut self, context: &ExecutionContext) {
+    match self.sampler.as_mut() {
+      Some(sampler) => sampler.tick(context),
+      None => self.profile.enter(context.current_module.as_str(), context.current_function.as_str(), context.program_counter),
+    }
+    if let Some(recording) = self.recording.as_mut() {
+      recording.record(context);
+    }
+  }
+
+  // the step of the instruction about to run and the variables before it, when the trace wants a record of it
+  pub fn trace_start(&mut self, context: &ExecutionContext) -> Option<(u64, HashMap<String, Value>)> {
+    let step = self.trace.as_mut()?.start(context.current_module.as_str(), context.current_function.as_str())?;
+    Some((step, context.variables.clone()))
+  }
+
+  pub fn trace_end(&mut self, step: u64, program_counter: usize, instruction: &Instruction, variables_before: &HashMap<String, Value>, context: &ExecutionContext) {
+    if let Some(trace) = self.trace.as_mut() {
+      trace.record(step, program_counter, instruction, variables_before, context);
+    }
+  }
+
+  fn print_hot_path(&mut self, compilation_unit: &CompilationUnit, count: usize) {
+    // charge the instruction that is running right now before reading the clock
+    self.profile.pause();
+    let total_count = self.profile.total_count() as f64;
+    let total_time = self.profile.total_time().as_secs_f64();
+    if total_count == 0.0 {
+      println!("Nothing has been executed yet :(");
+      return;
+    }
+    println!("{} instructions executed in {:?}", total_count, self.profile.total_time());
+
+    let spots = hot_spots(compilation_unit, &self.profile);
+    for (title, kind) in [("Hottest blocks:", HotSpotKind::Block), ("Hottest loops:", HotSpotKind::Loop)] {
+      let spots = spots.iter().filter(|spot| spot.kind == kind).take(count).collect::<Vec<_>>();
+      if spots.is_empty() {
+        continue;
+      }
+      println!("{}", title);
+      for spot in spots {
+        println!(
+          "  {:>6.2}% instructions {:>6.2}% time  {}.{} {} [{}, {})",
+          share(spot.count as f64, total_count),
+          share(spot.time.as_secs_f64(), total_time),
+          spot.module_name,
+          spot.function_name,
+          spot.name,
+          spot.start,
+          spot.end
+        );
+      }
+    }
+
+    if let Some((module_name, function_name)) = self.profile.hottest_function() {
+      let function = compilation_unit.get_function(module_name.as_str(), function_name.as_str()).unwrap();
+      let profile = self.profile.get(module_name.as_str(), function_name.as_str()).unwrap();
+      let graph = ControlFlowGraph::build(function);
+      println!("Hottest function {}.{}:", module_name, function_name);
+      println!("{:>10} {:>7} {:>4}", "count", "time", "pc");
+      let mut last_line = None;
+      for block in &graph.blocks {
+        println!("{}:", graph.block_name(block.id));
+        for program_counter in block.start..block.end {
+          let counter = match profile.count_at(program_counter) {
+            0 => "-".to_string(),
+            hits => hits.to_string(),
+          };
+          let time = format!("{:.2}%", share(profile.time_at(program_counter).as_secs_f64(), total_time));
+          let source = match function.get_source_location(program_counter) {
+            Some(location) if last_line != Some(location.line) => {
+              last_line = Some(location.line);
+              format!("    // {}:{}", location.file, location.line)
+            }
+            _ => String::new(),
+          };
+          println!("{:>10} {:>7} {:>4}  {}{}", counter, time, program_counter, function.body[program_counter].to_string(), source);
+        }
+      }
+    }
+    self.profile.resume();
+  }
+
+  // the first change any watchpoint sees
+  fn hit_watch_point(&mut self, context: &ExecutionContext) -> Option<String> {
+    let mut changes = self.watch_points.iter_mut().filter_map(|watch_point| watch_point.check(context)).collect::<Vec<String>>();
+    if changes.is_empty() {
+      None
+    } else {
+      Some(changes.remove(0))
+    }
+  }
+
+  fn log(&self, message: String) {
+    match &self.adapter {
+      Some(adapter) => adapter.output("console", format!("{}\n", message)),
+      None => println!("{}", message),
+    }
+  }
+
+  fn print_break_points(&self) {
+    let mut lines = Vec::new();
+    for (module_name, module_break_points) in &self.break_points {
+      for (function_name, function_break_points) in module_break_points {
+        for break_point in function_break_points {
+          lines.push((break_point.id, format!("[{}] {}.{} {}", break_point.id, module_name, function_name, break_point.describe())));
+        }
+      }
+    }
+    for watch_point in &self.watch_points {
+      lines.push((watch_point.id, format!("[{}] {}", watch_point.id, watch_point.describe())));
+    }
+    if lines.is_empty() {
+      println!("No breakpoints or watchpoints set");
+    }
+    lines.sort();
+    for (_, line) in lines {
+      println!("{}", line);
+    }
+  }
+
   pub fn print_all_summarized_metrics(&self, unit: TimeScale) {
     for metric_result in &self.metric_tracker.get_results() {
       Self::print_metric(metric_result, unit);
@@ -381,6 +1275,19 @@ impl DebugContext {
     }
   }
 
+  // the timers around every function and call, which would drown out the real cost of short functions when sampling
+  pub fn start_metric(&mut self, context: &ExecutionContext, metric_name: &str) {
+    if self.sampler.is_none() {
+      self.metric_tracker.start(context.get_call_stack(), metric_name.to_string());
+    }
+  }
+
+  pub fn stop_metric(&mut self, context: &ExecutionContext, metric_name: &str) {
+    if self.sampler.is_none() {
+      self.metric_tracker.stop(context.get_call_stack(), metric_name.to_string());
+    }
+  }
+
   pub fn start_custom_metric(&mut self, stack: Vec<String>, metric_name: String) {
     self.metric_tracker.start(stack, metric_name);
   }
@@ -389,3 +1296,61 @@ impl DebugContext {
     self.metric_tracker.stop(stack, metric_name);
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::hydro::testing::{compile, debug_main};
+
+  fn scripted_debug_context(commands: &str) -> DebugContext {
+    let mut debug_context = DebugContext::new();
+    debug_context.recording = None;
+    debug_context.history_file = None;
+    debug_context.load_commands(commands);
+    debug_context
+  }
+
+  #[test]
+  fn commands_keep_escaped_separators_for_eval() {
+    let compilation_unit = compile("module main\n\nmain body\n    push s32 4\n    return\n");
+    let mut debug_context = scripted_debug_context(r"next; eval push s32 2\; push s32 3\; add; continue");
+    assert_eq!(debug_context.script, vec!["next", "eval push s32 2; push s32 3; add", "continue"]);
+    assert_eq!(debug_main(&compilation_unit, &mut debug_context).unwrap(), Some(Value::Signed32(4)));
+    assert_eq!(debug_context.exit_code(), 0);
+  }
+
+  #[test]
+  fn commands_after_a_reload_use_the_new_code() {
+    let directory = std::env::temp_dir().join(format!("hydro-reload-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    let source_file = directory.join("main.h2o");
+    // the old answer throws so an eval that still used it would fail the script
+    std::fs::write(&source_file, "module main\n\nfunction answer returns s32 body\n    pop\n    return\n\nmain body\n    push s32 1\n    return\n").unwrap();
+    let compilation_unit = Hydro::compile(source_file.to_str().unwrap()).unwrap();
+    std::fs::write(&source_file, "module main\n\nfunction answer returns s32 body\n    push s32 42\n    return\n\nmain body\n    push s32 1\n    return\n").unwrap();
+
+    let mut debug_context = scripted_debug_context(r"reload; eval push funcp main answer\; call; continue");
+    debug_context.source_file = Some(source_file.to_str().unwrap().to_string());
+    let result = debug_main(&compilation_unit, &mut debug_context);
+    std::fs::remove_dir_all(&directory).unwrap();
+    assert_eq!(result.unwrap(), Some(Value::Signed32(1)));
+    assert!(debug_context.reloaded.is_some());
+    assert_eq!(debug_context.exit_code(), 0);
+  }
+
+  #[test]
+  fn finish_shows_bytes_that_are_not_utf8() {
+    let compilation_unit = compile("module main\n\nfunction bytes returns string body\n    alloc array 1 u8\n    push u32 0\n    push u8 255\n    setindex\n    return\n\nmain body\n    push funcp main bytes\n    call\n    pop\n    push s32 1\n    return\n");
+    let mut debug_context = scripted_debug_context("step 2; finish; continue");
+    assert_eq!(debug_main(&compilation_unit, &mut debug_context).unwrap(), Some(Value::Signed32(1)));
+    assert_eq!(debug_context.exit_code(), 0);
+  }
+
+  #[test]
+  fn last_change_shows_bytes_that_are_not_utf8() {
+    let compilation_unit = compile("module main\n\nmain body\n    push vref bytes\n    alloc string\n    alloc array 1 u8\n    push u32 0\n    push u8 255\n    setindex\n    store\n    pop\n    push s32 1\n    return\n");
+    let mut debug_context = scripted_debug_context("record --budget 1; next 8; last-change bytes; continue");
+    assert_eq!(debug_main(&compilation_unit, &mut debug_context).unwrap(), Some(Value::Signed32(1)));
+    assert_eq!(debug_context.exit_code(), 0);
+  }
+}
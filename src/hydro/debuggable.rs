@@ -58,11 +58,14 @@ impl Instruction {
 
 impl Debuggable for Call {
   fn debug(&self, compilation_unit: &CompilationUnit, context: &mut ExecutionContext, debug_context: &mut DebugContext) -> Result<bool, Exception> {
-    let metric_name = "call".to_string();
-    debug_context.metric_tracker.start(context.get_call_stack(), metric_name.clone());
+    let metric_name = "call";
+    debug_context.start_metric(context, metric_name);
+    // after a reload new calls run the newest version of the program while this frame finishes with its own
+    let latest = debug_context.reloaded.clone();
+    let compilation_unit = latest.as_deref().unwrap_or(compilation_unit);
 
     if context.stack.len() < 1 {
-      debug_context.metric_tracker.stop(context.get_call_stack(), metric_name.clone());
+      debug_context.stop_metric(context, metric_name);
       return Err(Exception::new(context.clone(), "Unexpected number of stack values. Expected 1 and got none."));
     }
 
@@ -75,7 +78,7 @@ impl Debuggable for Call {
           Some(module_name) => match compilation_unit.get_module(module_name.as_str()) {
             Some(modu) => modu,
             None => {
-              debug_context.metric_tracker.stop(context.get_call_stack(), metric_name.clone());
+              debug_context.stop_metric(context, metric_name);
               return Err(Exception::new(context.clone(), format!("Could not find module '{}'", module_name).as_str()));
             }
           },
@@ -85,7 +88,7 @@ impl Debuggable for Call {
         match target_module.functions.get(func_pointer.function.clone().as_str()) {
           Some(target_function) => {
             if context.stack.len() < target_function.parameters.len() {
-              debug_context.metric_tracker.stop(context.get_call_stack(), metric_name.clone());
+              debug_context.stop_metric(context, metric_name);
               return Err(Exception::new(context.clone(), format!("Unexpected number of stack values. Expected {} and got {}.", target_function.parameters.len(), context.stack.len()).as_str()));
             }
 
@@ -111,7 +114,7 @@ impl Debuggable for Call {
                 None => {}
               },
               Err(e) => {
-                debug_context.metric_tracker.stop(context.get_call_stack(), metric_name.clone());
+                debug_context.stop_metric(context, metric_name);
                 return Err(e);
               }
             }
@@ -119,7 +122,7 @@ impl Debuggable for Call {
           None => match target_module.intrinsics.get(func_pointer.function.clone().as_str()) {
             Some(target_intrinsic) => {
               if context.stack.len() < target_intrinsic.parameters.len() {
-                debug_context.metric_tracker.stop(context.get_call_stack(), metric_name.clone());
+                debug_context.stop_metric(context, metric_name);
                 return Err(Exception::new(context.clone(), format!("Unexpected number of stack values. Expected {} and got {}.", target_intrinsic.parameters.len(), context.stack.len()).as_str()));
               }
 
@@ -132,7 +135,7 @@ impl Debuggable for Call {
               let code = match target_intrinsic.get_intrinsic_code("vm".to_string()) {
                 Ok(code) => code,
                 Err(message) => {
-                  debug_context.metric_tracker.stop(context.get_call_stack(), metric_name.clone());
+                  debug_context.stop_metric(context, metric_name);
                   return Err(Exception::new(context.clone(), message.as_str()));
                 }
               };
@@ -141,26 +144,26 @@ impl Debuggable for Call {
               match return_value {
                 Ok(mut values) => context.stack.append(&mut values),
                 Err(e) => {
-                  debug_context.metric_tracker.stop(context.get_call_stack(), metric_name.clone());
+                  debug_context.stop_metric(context, metric_name);
                   return Err(e);
                 }
               }
             }
             None => {
-              debug_context.metric_tracker.stop(context.get_call_stack(), metric_name.clone());
+              debug_context.stop_metric(context, metric_name);
               return Err(Exception::new(context.clone(), format!("Could not find function '{}' in module '{}'", func_pointer.function.clone().as_str(), target_module.name).as_str()));
             }
           },
         };
       }
       _ => {
-        debug_context.metric_tracker.stop(context.get_call_stack(), metric_name.clone());
+        debug_context.stop_metric(context, metric_name);
         return Err(Exception::new(context.clone(), "Non-invokable value was attempted to be invoked"));
       }
     }
 
     context.program_counter += 1;
-    debug_context.metric_tracker.stop(context.get_call_stack(), metric_name.clone());
+    debug_context.stop_metric(context, metric_name);
     Ok(true)
   }
 }
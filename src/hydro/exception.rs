@@ -1,3 +1,4 @@
+use crate::hydro::compilationunit::CompilationUnit;
 use crate::hydro::executioncontext::ExecutionContext;
 
 #[derive(Debug, Clone, PartialEq)]
@@ -11,8 +12,8 @@ impl Exception {
     Self { context, message: message.to_string() }
   }
 
-  pub fn print_stacktrace(&self) {
+  pub fn print_stacktrace(&self, compilation_unit: &CompilationUnit) {
     println!("EXCEPTION: {}", self.message.clone());
-    self.context.print_stacktrace();
+    self.context.print_stacktrace(compilation_unit);
   }
 }
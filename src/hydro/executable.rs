@@ -359,8 +359,8 @@ impl Executable for Xor {
 
 impl Executable for Not {
   fn execute(&self, _compilation_unit: &CompilationUnit, context: &mut ExecutionContext) -> Result<bool, Exception> {
-    if context.stack.len() < 2 {
-      return Err(Exception::new(context.clone(), "Unexpected number of stack values. Expected 2 and got 1."));
+    if context.stack.len() < 1 {
+      return Err(Exception::new(context.clone(), "Unexpected number of stack values. Expected 1 and got none."));
     }
 
     let a = context.stack.pop().unwrap();
@@ -591,13 +591,37 @@ impl Executable for Call {
 }
 
 impl Executable for Return {
-  fn execute(&self, _compilation_unit: &CompilationUnit, context: &mut ExecutionContext) -> Result<bool, Exception> {
-    if context.stack.len() < 1 {
-      return Err(Exception::new(context.clone(), "Unexpected number of stack values. Expected 1 and got none."));
-    }
+  fn execute(&self, compilation_unit: &CompilationUnit, context: &mut ExecutionContext) -> Result<bool, Exception> {
+    let return_type = match compilation_unit.get_function(context.current_module.as_str(), context.current_function.as_str()) {
+      Some(current_function) => current_function.return_type.clone(),
+      None => None,
+    };
 
-    let result = context.stack.pop().unwrap();
-    context.return_value = Some(result);
+    match return_type {
+      Some(Type::Void) => {
+        context.return_value = None;
+      }
+      Some(expected_type) => {
+        if context.stack.len() < 1 {
+          return Err(Exception::new(context.clone(), format!("Unexpected number of stack values. Expected a return value of type {:?} and got none.", expected_type).as_str()));
+        }
+
+        let result = context.stack.pop().unwrap();
+        let result_type = compilation_unit.type_of(&result, context.current_module.as_str());
+        if !Type::subset(&result_type, &expected_type) {
+          return Err(Exception::new(context.clone(), format!("Unexpected return type found {:?} but expected {:?}", result_type, expected_type).as_str()));
+        }
+        context.return_value = Some(result);
+      }
+      None => {
+        if context.stack.len() < 1 {
+          return Err(Exception::new(context.clone(), "Unexpected number of stack values. Expected 1 and got none."));
+        }
+
+        let result = context.stack.pop().unwrap();
+        context.return_value = Some(result);
+      }
+    }
 
     context.program_counter += 1;
     Ok(false)
@@ -653,7 +677,7 @@ impl Executable for GetArrayIndex {
     let array = context.stack.pop().unwrap();
 
     match index.to_u64() {
-      Ok(result) => match array.index(result) {
+      Ok(result) => match if self.bounds_checked { array.index(result) } else { array.index_unchecked(result) } {
         Ok(value_from_array) => {
           context.stack.push(array);
           context.stack.push(value_from_array);
@@ -679,7 +703,7 @@ impl Executable for SetArrayIndex {
     let mut array = context.stack.pop().unwrap();
 
     match index.to_u64() {
-      Ok(result) => match array.set_index(result, value) {
+      Ok(result) => match if self.bounds_checked { array.set_index(result, value) } else { array.set_index_unchecked(result, value) } {
         Ok(()) => context.stack.push(array),
         Err(message) => return Err(Exception::new(context.clone(), message.as_str())),
       },
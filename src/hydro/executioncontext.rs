@@ -1,5 +1,6 @@
 use super::value::Value;
 
+use crate::hydro::compilationunit::CompilationUnit;
 use crate::hydro::exception::Exception;
 use crate::hydro::value::Reference;
 use ocean_macros::{make_add_operations, make_bit_operations, make_comparison_operations};
@@ -30,6 +31,14 @@ impl ExecutionContext {
     }
   }
 
+  // how many functions are on the call stack including this one
+  pub fn depth(&self) -> usize {
+    match &self.parent_execution_context {
+      Some(parent) => parent.depth() + 1,
+      None => 1,
+    }
+  }
+
   pub fn get_call_stack(&self) -> Vec<String> {
     let mut results = Vec::new();
     match &self.parent_execution_context {
@@ -43,10 +52,19 @@ impl ExecutionContext {
     results
   }
 
-  pub fn print_stacktrace(&self) {
-    println!("\tModule: '{}' Function: '{}' at PC: {}", self.current_module, self.current_function, self.program_counter);
+  pub fn print_stacktrace(&self, compilation_unit: &CompilationUnit) {
+    match compilation_unit.get_source_location(self.current_module.as_str(), self.current_function.as_str(), self.program_counter) {
+      Some(location) => {
+        println!("\tModule: '{}' Function: '{}' at PC: {} ({}:{})", self.current_module, self.current_function, self.program_counter, location.file, location.line);
+        match location.source_line() {
+          Some(source) => println!("\t\t{}", source),
+          None => {}
+        }
+      }
+      None => println!("\tModule: '{}' Function: '{}' at PC: {}", self.current_module, self.current_function, self.program_counter),
+    }
     match &self.parent_execution_context {
-      Some(next_context) => next_context.print_stacktrace(),
+      Some(next_context) => next_context.print_stacktrace(compilation_unit),
       None => {}
     }
   }
@@ -2,6 +2,7 @@ use crate::hydro::function::Function;
 use crate::hydro::instruction::*;
 use crate::hydro::layouttemplate::LayoutTemplate;
 use crate::hydro::module::Module;
+use crate::hydro::value::Type;
 
 pub trait Binaryable {
   fn output(&self, start_offset: usize) -> Vec<u8>;
@@ -19,14 +20,9 @@ pub trait Binaryable {
 
 impl Binaryable for Module {
   fn output(&self, start_offset: usize) -> Vec<u8> {
+    // the modules it uses are only written by name. Each of them is written on its own
     let mut results = Vec::new();
-
-    for (_, module) in &self.modules {
-      let mut module_output = module.output(results.len());
-      results.append(&mut module_output);
-    }
-
-    let module_start_offset = start_offset + results.len();
+    let module_start_offset = start_offset;
     results.push(b'M');
 
     let module_name_length = self.name.len();
@@ -39,7 +35,7 @@ impl Binaryable for Module {
 
     let using_offset = 25 + module_name_length + module_start_offset;
     let mut using_bytes = Vec::new();
-    for (module_name, _) in &self.modules {
+    for module_name in &self.modules {
       using_bytes.push(b'U');
       let using_module_name_length = module_name.clone().len();
       if using_module_name_length >= 65536 {
@@ -99,11 +95,170 @@ impl Binaryable for LayoutTemplate {
 
 impl Binaryable for Function {
   fn output(&self, start_offset: usize) -> Vec<u8> {
-    vec![b'F']
+    let mut results = vec![b'F'];
+    let function_name_length = self.name.len();
+    results.push(((function_name_length >> 8) & 255) as u8);
+    results.push((function_name_length & 255) as u8);
+    results.append(&mut self.name.clone().into_bytes());
+
+    let parameter_count = self.parameters.len();
+    if parameter_count >= 65536 {
+      panic!("Too many parameters :(");
+    }
+    results.push(((parameter_count >> 8) & 255) as u8);
+    results.push((parameter_count & 255) as u8);
+    for parameter in &self.parameters {
+      results.append(&mut parameter.output(start_offset + results.len()));
+    }
+
+    match &self.return_type {
+      Some(return_type) => {
+        results.push(1);
+        results.append(&mut return_type.output(start_offset + results.len()));
+      }
+      None => results.push(0),
+    }
+
+    results
   }
 
+  // only the header is written so the function comes back without a body
   fn input(index: &mut usize, input_bytes: &Vec<u8>) -> Self {
-    todo!()
+    if input_bytes[*index] != b'F' {
+      panic!("Expected a function but found '{}' :(", input_bytes[*index] as char);
+    }
+    let name_length = ((input_bytes[*index + 1] as usize) << 8) | input_bytes[*index + 2] as usize;
+    let name = String::from_utf8(input_bytes[*index + 3..*index + 3 + name_length].to_vec()).expect("Function names should be utf8 :(");
+    *index += 3 + name_length;
+
+    let parameter_count = ((input_bytes[*index] as usize) << 8) | input_bytes[*index + 1] as usize;
+    *index += 2;
+    let parameters = (0..parameter_count).map(|_| Type::input(index, input_bytes)).collect::<Vec<Type>>();
+
+    let has_return_type = input_bytes[*index] == 1;
+    *index += 1;
+    let mut function = Function::new(name, parameters, Vec::new());
+    if has_return_type {
+      function.return_type = Some(Type::input(index, input_bytes));
+    }
+    function
+  }
+}
+
+impl Binaryable for Type {
+  fn output(&self, start_offset: usize) -> Vec<u8> {
+    match self {
+      Type::Any => vec![b'*'],
+      Type::Void => vec![b'v'],
+      Type::Boolean => vec![b'b'],
+      Type::Unsigned8 => vec![b'u', 8],
+      Type::Unsigned16 => vec![b'u', 16],
+      Type::Unsigned32 => vec![b'u', 32],
+      Type::Unsigned64 => vec![b'u', 64],
+      Type::Unsigned128 => vec![b'u', 128],
+      Type::Signed8 => vec![b's', 8],
+      Type::Signed16 => vec![b's', 16],
+      Type::Signed32 => vec![b's', 32],
+      Type::Signed64 => vec![b's', 64],
+      Type::Signed128 => vec![b's', 128],
+      Type::Float32 => vec![b'f', 32],
+      Type::Float64 => vec![b'f', 64],
+      Type::Reference(subtype) => {
+        let mut results = vec![b'&'];
+        results.append(&mut subtype.output(start_offset + 1));
+        results
+      }
+      Type::Array(length, subtype) => {
+        let mut results = vec![b'['];
+        match length {
+          Some(length) => {
+            results.push(1);
+            results.append(&mut Type::output_usize(*length as usize));
+          }
+          None => results.push(0),
+        }
+        results.append(&mut subtype.output(start_offset + results.len()));
+        results
+      }
+      Type::FunctionPointer(parameters, return_type) => {
+        if parameters.len() >= 65536 {
+          panic!("Too many parameters :(");
+        }
+        let mut results = vec![b'p', ((parameters.len() >> 8) & 255) as u8, (parameters.len() & 255) as u8];
+        for parameter in parameters {
+          results.append(&mut parameter.output(start_offset + results.len()));
+        }
+        results.append(&mut return_type.output(start_offset + results.len()));
+        results
+      }
+      Type::Layout(module_name, layout_name, _) => {
+        let mut results = vec![b'{'];
+        for name in [module_name, layout_name] {
+          results.push(((name.len() >> 8) & 255) as u8);
+          results.push((name.len() & 255) as u8);
+          results.append(&mut name.clone().into_bytes());
+        }
+        results
+      }
+    }
+  }
+
+  fn input(index: &mut usize, input_bytes: &Vec<u8>) -> Self {
+    let mut next = || {
+      let byte = input_bytes[*index];
+      *index += 1;
+      byte
+    };
+    let marker = next();
+    match marker {
+      b'*' => Type::Any,
+      b'v' => Type::Void,
+      b'b' => Type::Boolean,
+      b'u' | b's' | b'f' => match (marker, next()) {
+        (b'u', 8) => Type::Unsigned8,
+        (b'u', 16) => Type::Unsigned16,
+        (b'u', 32) => Type::Unsigned32,
+        (b'u', 64) => Type::Unsigned64,
+        (b'u', 128) => Type::Unsigned128,
+        (b's', 8) => Type::Signed8,
+        (b's', 16) => Type::Signed16,
+        (b's', 32) => Type::Signed32,
+        (b's', 64) => Type::Signed64,
+        (b's', 128) => Type::Signed128,
+        (b'f', 32) => Type::Float32,
+        (b'f', 64) => Type::Float64,
+        (marker, width) => panic!("Unexpected bit width {} for type '{}' :(", width, marker as char),
+      },
+      b'&' => Type::Reference(Box::new(Type::input(index, input_bytes))),
+      b'[' => {
+        let length = match next() {
+          0 => None,
+          _ => {
+            let length = input_bytes[*index..*index + 4].iter().fold(0u64, |length, byte| (length << 8) | *byte as u64);
+            *index += 4;
+            Some(length)
+          }
+        };
+        Type::Array(length, Box::new(Type::input(index, input_bytes)))
+      }
+      b'p' => {
+        let parameter_count = ((next() as usize) << 8) | next() as usize;
+        let parameters = (0..parameter_count).map(|_| Type::input(index, input_bytes)).collect::<Vec<Type>>();
+        Type::FunctionPointer(parameters, Box::new(Type::input(index, input_bytes)))
+      }
+      b'{' => {
+        let mut names = Vec::new();
+        for _ in 0..2 {
+          let length = ((input_bytes[*index] as usize) << 8) | input_bytes[*index + 1] as usize;
+          names.push(String::from_utf8(input_bytes[*index + 2..*index + 2 + length].to_vec()).expect("Layout names should be utf8 :("));
+          *index += 2 + length;
+        }
+        let layout_name = names.pop().unwrap();
+        // the members aren't part of the bytes. They come from the layout template once the module is loaded
+        Type::Layout(names.pop().unwrap(), layout_name, None)
+      }
+      marker => panic!("Unexpected type marker '{}' :(", marker as char),
+    }
   }
 }
 
@@ -139,8 +294,8 @@ impl Binaryable for Instruction {
       Instruction::Return(x) => x.output(start_offset),
       Instruction::Load(x) => x.output(start_offset),
       Instruction::Store(x) => x.output(start_offset),
-      Instruction::ArrayIndex(x) => x.output(start_offset),
-      Instruction::LayoutIndex(x) => x.output(start_offset),
+      Instruction::GetArrayIndex(x) => x.output(start_offset),
+      Instruction::GetLayoutIndex(x) => x.output(start_offset),
       Instruction::Allocate(x) => x.output(start_offset),
       _ => panic!("Binaryable not implemented for supplied type"),
     }
@@ -148,8 +303,8 @@ impl Binaryable for Instruction {
 
   fn input(index: &mut usize, input_bytes: &Vec<u8>) -> Self {
     match input_bytes[*index].clone() {
-      b':' => Instruction::PushValue(PushValue::input(index, &input_bytes)),
-      b'.' => Instruction::PopValue(PopValue::input(index, input_bytes)),
+      b':' => Instruction::PushValue(Push::input(index, &input_bytes)),
+      b'.' => Instruction::PopValue(Pop::input(index, input_bytes)),
       b'+' => Instruction::Add(Add::input(index, input_bytes)),
       b'-' => Instruction::Subtract(Subtract::input(index, input_bytes)),
       b'*' => Instruction::Multiply(Multiply::input(index, input_bytes)),
@@ -177,15 +332,15 @@ impl Binaryable for Instruction {
       b'r' => Instruction::Return(Return::input(index, input_bytes)),
       b'g' => Instruction::Load(Load::input(index, input_bytes)),
       b's' => Instruction::Store(Store::input(index, input_bytes)),
-      b'i' => Instruction::ArrayIndex(ArrayIndex::input(index, input_bytes)),
-      b'm' => Instruction::LayoutIndex(LayoutIndex::input(index, input_bytes)),
+      b'i' => Instruction::GetArrayIndex(GetArrayIndex::input(index, input_bytes)),
+      b'm' => Instruction::GetLayoutIndex(GetLayoutIndex::input(index, input_bytes)),
       b'[' => Instruction::Allocate(Allocate::input(index, input_bytes)),
       _ => panic!("Binaryable not implemented for supplied type"),
     }
   }
 }
 
-impl Binaryable for PushValue {
+impl Binaryable for Push {
   fn output(&self, start_offset: usize) -> Vec<u8> {
     todo!()
   }
@@ -195,7 +350,7 @@ impl Binaryable for PushValue {
   }
 }
 
-impl Binaryable for PopValue {
+impl Binaryable for Pop {
   fn output(&self, start_offset: usize) -> Vec<u8> {
     vec![b'.']
   }
@@ -475,7 +630,7 @@ impl Binaryable for Store {
   }
 }
 
-impl Binaryable for ArrayIndex {
+impl Binaryable for GetArrayIndex {
   fn output(&self, start_offset: usize) -> Vec<u8> {
     vec![b'i']
   }
@@ -485,7 +640,7 @@ impl Binaryable for ArrayIndex {
   }
 }
 
-impl Binaryable for LayoutIndex {
+impl Binaryable for GetLayoutIndex {
   fn output(&self, start_offset: usize) -> Vec<u8> {
     vec![b'l']
   }
@@ -504,3 +659,53 @@ impl Binaryable for Allocate {
     todo!()
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn types_round_trip() {
+    // more than a byte can count
+    let parameters = (0..300).map(|index| if index % 2 == 0 { Type::Signed32 } else { Type::Array(Some(0), Box::new(Type::Unsigned8)) }).collect::<Vec<Type>>();
+    let types = vec![
+      Type::Any,
+      Type::Void,
+      Type::Boolean,
+      Type::Unsigned128,
+      Type::Signed8,
+      Type::Float64,
+      Type::Reference(Box::new(Type::Signed32)),
+      Type::Array(Some(300), Box::new(Type::Array(None, Box::new(Type::Unsigned16)))),
+      Type::Array(Some(0), Box::new(Type::Boolean)),
+      Type::FunctionPointer(vec![Type::Unsigned64, Type::Layout("main".to_string(), "point".to_string(), None)], Box::new(Type::Void)),
+      Type::FunctionPointer(parameters.clone(), Box::new(Type::Boolean)),
+    ];
+    let mut bytes = Vec::new();
+    for value_type in &types {
+      bytes.append(&mut value_type.output(bytes.len()));
+    }
+    let mut index = 0;
+    let read = types.iter().map(|_| Type::input(&mut index, &bytes)).collect::<Vec<Type>>();
+    assert_eq!(read, types);
+    assert_eq!(index, bytes.len());
+
+    let functions = vec![
+      Function::new("square".to_string(), parameters.clone(), Vec::new()).returns(Type::FunctionPointer(parameters, Box::new(Type::Void))),
+      Function::new("main".to_string(), Vec::new(), Vec::new()),
+    ];
+    let mut bytes = Vec::new();
+    for function in &functions {
+      bytes.append(&mut function.output(bytes.len()));
+    }
+    let mut index = 0;
+    for function in &functions {
+      let read = Function::input(&mut index, &bytes);
+      assert_eq!(read.name, function.name);
+      assert_eq!(read.parameters, function.parameters);
+      assert_eq!(read.return_type, function.return_type);
+      assert!(read.body.is_empty());
+    }
+    assert_eq!(index, bytes.len());
+  }
+}
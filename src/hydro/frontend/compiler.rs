@@ -1,8 +1,13 @@
 // TODO use crate::hydro::frontend::binaryable::Binaryable;
+use crate::hydro::analyzer::boundscheck::BoundsCheckElimination;
+use crate::hydro::analyzer::effects::EffectAnalysis;
 use crate::hydro::compilationunit::CompilationUnit;
 use crate::hydro::frontend::parser::Parser;
+use crate::hydro::linter::{Lint, Linter};
 use crate::hydro::module::Module;
+use crate::hydro::optimizer::Optimizer;
 use crate::hydro::Hydro;
+use crate::util::errors::Severity;
 use std::fs::File;
 use std::io::{Error, Write};
 use std::path::Path;
@@ -46,6 +51,58 @@ impl Hydro {
     }
   }
 
+  pub fn optimize(compilation_unit: &mut CompilationUnit, opt_level: u8) {
+    if opt_level == 0 {
+      return;
+    }
+    let now = Instant::now();
+    let report = Optimizer::new(opt_level).optimize(compilation_unit);
+    let new_now = Instant::now();
+    println!("Optimization Completed In: {:?}", new_now.duration_since(now));
+    report.print();
+  }
+
+  // proves which array accesses can skip their runtime bounds check. Returns false if an access is always out of bounds
+  pub fn check_bounds(compilation_unit: &mut CompilationUnit) -> bool {
+    let now = Instant::now();
+    let report = BoundsCheckElimination {}.run(compilation_unit);
+    let new_now = Instant::now();
+    println!("Bounds Check Analysis Completed In: {:?}", new_now.duration_since(now));
+    report.print();
+    !report.has_errors()
+  }
+
+  // lints the modules declared in the source file but not the modules it pulls in. Returns true if any errors were found
+  pub fn lint(compilation_unit: &CompilationUnit, file_path: &str, allowed: Vec<String>, print_effects: bool) -> bool {
+    let module_names = match Parser::new(Path::new(file_path)) {
+      Ok(mut parser) => parser.parse().iter().map(|module| module.name.clone()).collect::<Vec<String>>(),
+      Err(_) => Vec::new(),
+    };
+    let allowed = allowed.iter().filter_map(|name| Lint::from_name(name.as_str())).collect::<Vec<Lint>>();
+
+    let report = Linter::new(allowed).lint(compilation_unit, &module_names);
+    report.print();
+    if print_effects {
+      Hydro::print_effects(compilation_unit, &module_names);
+    }
+    report.has_errors()
+  }
+
+  pub fn print_effects(compilation_unit: &CompilationUnit, module_names: &Vec<String>) {
+    let analysis = EffectAnalysis::run(compilation_unit);
+    println!("Function Effects:");
+    for module_name in module_names {
+      let Some(module) = compilation_unit.get_module(module_name.as_str()) else {
+        continue;
+      };
+      let mut function_names = module.functions.keys().collect::<Vec<&String>>();
+      function_names.sort();
+      for function_name in function_names {
+        println!("  {}.{}: {}", module_name, function_name, analysis.get(module_name.as_str(), function_name.as_str()).labels().join(", "));
+      }
+    }
+  }
+
   fn internal_compile(file_path: &str, project_root: &str, std_root: Option<String>) -> Result<CompilationUnit, (bool, Vec<String>)> {
     let path = Path::new(file_path);
     println!("Compiling '{}' (absolute '{:?}' from '{:?}')", path.display(), fs::canonicalize(path), env::current_dir());
@@ -163,7 +220,7 @@ impl Hydro {
       }
     };
     let mut file = File::create(Path::new(path.as_str()))?;
-    file.write(bytes.as_slice())?;
+    file.write_all(bytes.as_slice())?;
     Ok(())
   }
 }
@@ -1,4 +1,4 @@
-//TODO mod binaryable;
+mod binaryable;
 pub mod compiler;
 pub mod parser;
 pub mod tokentype;
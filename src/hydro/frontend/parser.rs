@@ -4,6 +4,7 @@ use crate::hydro::instruction::*;
 use crate::hydro::intrinsic::Intrinsic;
 use crate::hydro::layouttemplate::LayoutTemplate;
 use crate::hydro::module::Module;
+use crate::hydro::sourcelocation::SourceLocation;
 use crate::hydro::value::{Array, FunctionPointer, LayoutIndexRef, Reference, Type, Value, VariableRef};
 use crate::util::token::{Token, TokenTrait};
 use std::collections::HashMap;
@@ -12,6 +13,7 @@ use std::io;
 use std::io::Read;
 
 pub struct Parser {
+  file_name: String,
   file_contents: Vec<char>,
   current_token: Option<Token<TokenType>>,
   current_index: usize,
@@ -26,6 +28,7 @@ impl Parser {
     file.read_to_string(&mut file_contents)?;
 
     Ok(Self {
+      file_name: path.display().to_string(),
       file_contents: file_contents.chars().collect::<Vec<char>>(),
       current_token: None,
       current_index: 0,
@@ -34,6 +37,27 @@ impl Parser {
     })
   }
 
+  // for source that isn't in a file like instructions typed into the debugger
+  pub fn from_source(file_name: &str, source: &str) -> Self {
+    Self {
+      file_name: file_name.to_string(),
+      file_contents: source.chars().collect::<Vec<char>>(),
+      current_token: None,
+      current_index: 0,
+      current_line: 1,
+      current_column: 1,
+    }
+  }
+
+  // a function body without the module and function declarations around it
+  pub fn parse_body_only(&mut self, function_name: &str) -> Function {
+    let function = self.parse_body(Function::build(function_name));
+    if let Some(token) = self.token() {
+      panic!("Expected to have an instruction here but read {} :(", token);
+    }
+    function
+  }
+
   pub fn parse(&mut self) -> Vec<Module> {
     let mut modules = Vec::new();
     while self.token().is_some() {
@@ -59,7 +83,9 @@ impl Parser {
 
       match current_token.token_type {
         TokenType::Using => {
+          let location = self.location(&current_token);
           let module_name = self.parse_using();
+          module.using_locations.insert(module_name.clone(), location);
           module = module.import_unresolved(module_name);
         }
         TokenType::Layout => {
@@ -84,6 +110,10 @@ impl Parser {
     module
   }
 
+  fn location(&self, token: &Token<TokenType>) -> SourceLocation {
+    SourceLocation::new(self.file_name.clone(), token.line.0, token.column.0).spanning(token.offset)
+  }
+
   fn parse_using(&mut self) -> String {
     let _ = self.expect_token_type(TokenType::Using);
     self.consume();
@@ -147,14 +177,21 @@ impl Parser {
     self.consume();
 
     let mut function = Function::build(identifier_token.lexeme.as_str());
+    function.location = Some(self.location(&identifier_token));
     // parse params
     loop {
-      let id_token = self.expect_one_of(vec![TokenType::Type, TokenType::Identifier, TokenType::This, TokenType::Array, TokenType::Body]);
+      let id_token = self.expect_one_of(vec![TokenType::Type, TokenType::Identifier, TokenType::This, TokenType::Array, TokenType::Returns, TokenType::Body]);
       match id_token.token_type {
         TokenType::Identifier | TokenType::Type | TokenType::Array | TokenType::This => {
           let param_type = self.parse_type();
           function = function.parameter(param_type);
         }
+        TokenType::Returns => {
+          self.consume();
+          let return_type = self.parse_type();
+          function = function.returns(return_type);
+          break;
+        }
         TokenType::Body => break,
         _ => {}
       }
@@ -163,7 +200,10 @@ impl Parser {
     let _ = self.expect_token_type(TokenType::Body);
     self.consume();
 
-    // parse insts
+    self.parse_body(function)
+  }
+
+  fn parse_body(&mut self, mut function: Function) -> Function {
     loop {
       let Some(inst_token) = self.token() else {
         break
@@ -205,13 +245,16 @@ impl Parser {
         | TokenType::GetIndex
         | TokenType::SetIndex
         | TokenType::Cast => {
+          let location = self.location(&inst_token);
           let instruction = self.parse_instruction();
+          function.add_source_location(function.body.len(), location);
           function = function.inst(instruction);
         }
         TokenType::Label => {
           self.consume();
           let target_name_token = self.expect_token_type(TokenType::Identifier);
           self.consume();
+          function.label_locations.insert(target_name_token.lexeme.clone(), self.location(&target_name_token));
           function.add_label(target_name_token.lexeme, function.body.len());
         }
         TokenType::Module | TokenType::Function | TokenType::Layout | TokenType::Using | TokenType::Main | TokenType::Intrinsic => break,
@@ -246,6 +289,7 @@ impl Parser {
         "f32" => Type::Float32,
         "f64" => Type::Float64,
         "string" => Type::Array(None, Box::new(Type::Unsigned8)),
+        "void" => Type::Void,
         _ => panic!("Unexpected type string"),
       },
       TokenType::Identifier | TokenType::This => {
@@ -433,7 +477,7 @@ impl Parser {
             self.consume();
             Instruction::GetLayoutIndex(GetLayoutIndex { member: token.lexeme })
           }
-          None => Instruction::GetArrayIndex(GetArrayIndex {}),
+          None => Instruction::GetArrayIndex(GetArrayIndex { bounds_checked: true }),
         }
       }
       TokenType::SetIndex => {
@@ -443,7 +487,7 @@ impl Parser {
             self.consume();
             Instruction::SetLayoutIndex(SetLayoutIndex { member: token.lexeme })
           }
-          None => Instruction::SetArrayIndex(SetArrayIndex {}),
+          None => Instruction::SetArrayIndex(SetArrayIndex { bounds_checked: true }),
         }
       }
       _ => panic!("Unexpected token. Expected an instruction :("),
@@ -481,12 +525,13 @@ impl Parser {
     self.consume();
 
     let mut layout_template = LayoutTemplate::build(identifier_token.lexeme.as_str());
+    layout_template.location = Some(self.location(&identifier_token));
 
     loop {
       let type_token = self.expect_token();
       match type_token.token_type {
         TokenType::Type => self.consume(),
-        TokenType::Module | TokenType::Function | TokenType::Layout | TokenType::Using | TokenType::Intrinsic => break,
+        TokenType::Module | TokenType::Function | TokenType::Layout | TokenType::Using | TokenType::Main | TokenType::Intrinsic => break,
         _ => panic!("Expected to have a type token here :("),
       }
 
@@ -696,6 +741,7 @@ impl Parser {
           "string" => TokenType::Type,
           "bool" => TokenType::Type,
           "any" => TokenType::Type,
+          "void" => TokenType::Type,
           "true" => TokenType::True,
           "false" => TokenType::False,
           "main" => TokenType::Main,
@@ -708,6 +754,7 @@ impl Parser {
           "intrinsic" => TokenType::Intrinsic,
           "target" => TokenType::Target,
           "body" => TokenType::Body,
+          "returns" => TokenType::Returns,
           "layout" => TokenType::Layout,
           "array" => TokenType::Array,
           "this" => TokenType::This,
@@ -18,6 +18,7 @@ pub enum TokenType {
   Intrinsic,
   Target,
   Body,
+  Returns,
   Layout,
   Array,
   This,
@@ -1,6 +1,8 @@
 use crate::hydro::function::Target::{Index, Label};
 use crate::hydro::instruction::*;
+use crate::hydro::sourcelocation::SourceLocation;
 use crate::hydro::value::{Reference, Type, Value, VariableRef};
+use itertools::Itertools;
 use std::collections::HashMap;
 use ocean_macros::New;
 
@@ -15,8 +17,16 @@ pub struct Function {
   pub name: String,
   pub parameters: Vec<Type>,
   pub body: Vec<Instruction>,
+  #[default(None)]
+  pub return_type: Option<Type>,
   #[default(HashMap::new())]
   pub jump_labels: HashMap<String, usize>,
+  #[default(HashMap::new())]
+  pub source_locations: HashMap<usize, SourceLocation>,
+  #[default(HashMap::new())]
+  pub label_locations: HashMap<String, SourceLocation>,
+  #[default(None)]
+  pub location: Option<SourceLocation>,
 }
 
 impl Function {
@@ -30,10 +40,124 @@ impl Function {
     }
   }
 
+  // functions without a declared return type can return anything so we treat it as 'any' here
+  pub fn to_type(&self) -> Type {
+    Type::FunctionPointer(self.parameters.clone(), Box::new(self.return_type.clone().unwrap_or(Type::Any)))
+  }
+
   pub fn add_label(&mut self, name: String, target: usize) {
     self.jump_labels.insert(name, target);
   }
 
+  pub fn get_label(&self, target: usize) -> Option<String> {
+    self.jump_labels.iter().filter(|(_, index)| **index == target).map(|(label, _)| label.clone()).sorted().next()
+  }
+
+  pub fn add_source_location(&mut self, target: usize, location: SourceLocation) {
+    self.source_locations.insert(target, location);
+  }
+
+  pub fn get_source_location(&self, target: usize) -> Option<&SourceLocation> {
+    self.source_locations.get(&target)
+  }
+
+  // finds the first instruction on the given line or the closest one after it if there is nothing on that line
+  pub fn find_source_line(&self, file_name: &str, line: usize) -> Option<(usize, usize)> {
+    self
+      .source_locations
+      .iter()
+      .filter(|(_, location)| location.is_file(file_name) && location.line >= line)
+      .map(|(index, location)| (location.line, *index))
+      .min()
+  }
+
+  pub fn is_jump_target(&self, target: usize) -> bool {
+    if self.jump_labels.values().any(|index| *index == target) {
+      return true;
+    }
+    self.body.iter().any(|instruction| match instruction {
+      Instruction::Jump(jump) => matches!(jump.target, Index(index) if index == target),
+      Instruction::Branch(branch) => matches!(branch.true_target, Index(index) if index == target) || matches!(branch.false_target, Index(index) if index == target),
+      _ => false,
+    })
+  }
+
+  // the instructions that can execute directly after the instruction at index. Falling off the end of the body is not included
+  pub fn successors(&self, index: usize) -> Result<Vec<usize>, String> {
+    let successors = match &self.body[index] {
+      Instruction::Jump(jump) => vec![self.get_target_pointer(jump.target.clone())?],
+      Instruction::Branch(branch) => vec![self.get_target_pointer(branch.true_target.clone())?, self.get_target_pointer(branch.false_target.clone())?],
+      Instruction::Return(_) => vec![],
+      _ => vec![index + 1],
+    };
+    Ok(successors.into_iter().filter(|successor| *successor < self.body.len()).collect())
+  }
+
+  // like successors but targets that don't resolve are left out instead of failing the whole instruction
+  pub fn known_successors(&self, index: usize) -> Vec<usize> {
+    let targets = match &self.body[index] {
+      Instruction::Jump(jump) => vec![jump.target.clone()],
+      Instruction::Branch(branch) => vec![branch.true_target.clone(), branch.false_target.clone()],
+      _ => return self.successors(index).unwrap_or_default(),
+    };
+    targets.into_iter().filter_map(|target| self.get_target_pointer(target).ok()).filter(|successor| *successor < self.body.len()).collect()
+  }
+
+  // Replaces the instructions in [start, end) with the replacement instructions and fixes up labels,
+  // index targets, and source locations. Anything that pointed inside of the replaced range now points at start
+  pub fn replace_range(&mut self, start: usize, end: usize, replacement: Vec<Instruction>) {
+    let removed = end - start;
+    let added = replacement.len();
+    let remap = |index: usize| -> usize {
+      if index < start {
+        index
+      } else if index < end {
+        start
+      } else {
+        index - removed + added
+      }
+    };
+
+    let location = (start..end).find_map(|index| self.source_locations.get(&index).cloned());
+    self.body.splice(start..end, replacement);
+
+    for index in self.jump_labels.values_mut() {
+      *index = remap(*index);
+    }
+
+    for instruction in &mut self.body {
+      match instruction {
+        Instruction::Jump(jump) => {
+          if let Index(index) = jump.target {
+            jump.target = Index(remap(index));
+          }
+        }
+        Instruction::Branch(branch) => {
+          if let Index(index) = branch.true_target {
+            branch.true_target = Index(remap(index));
+          }
+          if let Index(index) = branch.false_target {
+            branch.false_target = Index(remap(index));
+          }
+        }
+        _ => {}
+      }
+    }
+
+    let mut source_locations = HashMap::new();
+    for (index, source_location) in self.source_locations.drain() {
+      if index < start || index >= end {
+        source_locations.insert(remap(index), source_location);
+      }
+    }
+    if let Some(location) = location {
+      for index in start..(start + added) {
+        source_locations.insert(index, location.clone());
+      }
+    }
+    self.source_locations = source_locations;
+  }
+
   pub fn build(name: &str) -> Self {
     Function::new(name.to_string(), Vec::new(), Vec::new())
   }
@@ -43,6 +167,11 @@ impl Function {
     self
   }
 
+  pub fn returns(mut self, return_type: Type) -> Self {
+    self.return_type = Some(return_type);
+    self
+  }
+
   pub fn inst(mut self, instruction: Instruction) -> Self {
     self.body.push(instruction);
     self
@@ -133,3 +262,36 @@ impl Function {
     self
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::hydro::testing::{function_with_body, listing};
+
+  fn lines(function: &Function) -> Vec<usize> {
+    (0..function.body.len()).map(|index| function.source_locations[&index].line).collect()
+  }
+
+  #[test]
+  fn replace_range_remaps_what_follows() {
+    let mut function = function_with_body("f returns s32", &["push s32 1", "label inside", "push s32 2", "add", "label after", "jump inside", "jump after"]);
+    function.body[4] = Instruction::Jump(Jump { target: Index(3) });
+    function.replace_range(1, 3, vec![Instruction::PushValue(Push { value: Value::Signed32(3) })]);
+
+    assert_eq!(listing(&function), vec!["push Signed32 1", "push Signed32 3", "jump inside", "jump @2"]);
+    assert_eq!((function.jump_labels["inside"], function.jump_labels["after"]), (1, 2));
+    // the replacement takes the location of the first replaced instruction
+    assert_eq!(lines(&function), vec![4, 6, 9, 10]);
+  }
+
+  #[test]
+  fn replace_range_points_into_the_range_at_its_start() {
+    let mut function = function_with_body("f bool returns s32", &["branch yes no", "label yes", "push s32 1", "return", "label no", "push s32 2", "return"]);
+    function.body[0] = Instruction::Branch(Branch { true_target: Index(2), false_target: Index(4) });
+    function.replace_range(1, 3, vec![Instruction::PushValue(Push { value: Value::Signed32(5) }), Instruction::PushValue(Push { value: Value::Signed32(6) }), Instruction::Add(Add {})]);
+
+    assert_eq!(listing(&function), vec!["branch @1 @5", "push Signed32 5", "push Signed32 6", "add", "push Signed32 2", "return"]);
+    assert_eq!((function.jump_labels["yes"], function.jump_labels["no"]), (1, 4));
+    assert_eq!(lines(&function), vec![4, 6, 6, 6, 9, 10]);
+  }
+}
@@ -0,0 +1,256 @@
+use crate::hydro::executioncontext::ExecutionContext;
+use crate::hydro::value::{Array, Reference, Type, Value};
+use std::ops::Deref;
+
+// Formats values for people instead of with {:?}. u8 arrays that are valid UTF-8 are shown as strings, layouts as
+// 'module.layout { member: value }', and references as what they point at. Long arrays and deep nesting are cut off so
+// a single value can't flood the terminal
+
+pub const DEFAULT_MAX_ELEMENTS: usize = 16;
+pub const DEFAULT_MAX_DEPTH: usize = 4;
+
+pub struct ValueFormatter<'a> {
+  // the rest of an array is elided after this many elements
+  pub max_elements: usize,
+  pub max_depth: usize,
+  // resolves references and the 'this' module of layouts. Without it references are shown as paths
+  context: Option<&'a ExecutionContext>,
+}
+
+impl<'a> ValueFormatter<'a> {
+  pub fn new(context: Option<&'a ExecutionContext>) -> Self {
+    Self { max_elements: DEFAULT_MAX_ELEMENTS, max_depth: DEFAULT_MAX_DEPTH, context }
+  }
+
+  pub fn format(&self, value: &Value) -> String {
+    self.format_at(value, 0)
+  }
+
+  fn format_at(&self, value: &Value, depth: usize) -> String {
+    match value {
+      Value::Array(array) => match as_string(array) {
+        Some(text) => format!("{:?}", text),
+        None if depth >= self.max_depth => "[…]".to_string(),
+        None => {
+          let mut elements = array.values.iter().take(self.max_elements).map(|element| self.format_at(element, depth + 1)).collect::<Vec<String>>();
+          if array.values.len() > self.max_elements {
+            elements.push(format!("… {} more", array.values.len() - self.max_elements));
+          }
+          format!("[{}]", elements.join(", "))
+        }
+      },
+      Value::Layout(layout) => {
+        let name = format!("{}.{}", self.module_name(&layout.module_name), layout.layout_name);
+        if depth >= self.max_depth {
+          return format!("{} {{ … }}", name);
+        }
+        let mut members = layout.values.iter().collect::<Vec<(&String, &Value)>>();
+        members.sort_by(|a, b| a.0.cmp(b.0));
+        let members = members.iter().map(|(member, value)| format!("{}: {}", member, self.format_at(value, depth + 1))).collect::<Vec<String>>();
+        format!("{} {{ {} }}", name, members.join(", "))
+      }
+      Value::FunctionPointer(pointer) => match &pointer.module {
+        Some(module_name) => format!("funcp {}.{}", module_name, pointer.function),
+        None => format!("funcp {}", pointer.function),
+      },
+      Value::Reference(reference) => match self.context.map(|context| context.resolve(value.clone())) {
+        Some(Ok(target)) => format!("&{} -> {}", reference_path(reference), self.format_at(&target, depth + 1)),
+        Some(Err(_)) => format!("&{} (dangling)", reference_path(reference)),
+        None => format!("&{}", reference_path(reference)),
+      },
+      _ => value.to_string(),
+    }
+  }
+
+  // a tree with one line per member or element. Only the elements in [offset, offset + count) of the top array are
+  // listed so big arrays can be paged through
+  pub fn inspect(&self, name: &str, value: &Value, offset: usize, count: usize, depth: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    self.inspect_at(name, value, offset, count, depth, 0, &mut lines);
+    lines
+  }
+
+  fn inspect_at(&self, name: &str, value: &Value, offset: usize, count: usize, depth: usize, level: usize, lines: &mut Vec<String>) {
+    let indent = "  ".repeat(level);
+    let target = match value {
+      Value::Reference(_) => self.context.and_then(|context| context.resolve(value.clone()).ok()).unwrap_or(value.clone()),
+      _ => value.clone(),
+    };
+    let expandable = match &target {
+      Value::Array(array) => as_string(array).is_none(),
+      Value::Layout(_) => true,
+      _ => false,
+    };
+    if !expandable || level >= depth {
+      lines.push(format!("{}{}: {} = {}", indent, name, self.type_name(value), self.format(value)));
+      return;
+    }
+
+    lines.push(format!("{}{}: {}", indent, name, self.type_name(value)));
+    match &target {
+      Value::Array(array) => {
+        let end = array.values.len().min(offset.saturating_add(count));
+        for index in offset.min(end)..end {
+          self.inspect_at(format!("[{}]", index).as_str(), &array.values[index], 0, self.max_elements, depth, level + 1, lines);
+        }
+        if offset > 0 || end < array.values.len() {
+          let more = if end < array.values.len() { format!(". Use --offset {} to see more", end) } else { String::new() };
+          lines.push(format!("{}  … showing [{}, {}) of {}{}", indent, offset.min(end), end, array.values.len(), more));
+        }
+      }
+      Value::Layout(layout) => {
+        let mut members = layout.values.iter().collect::<Vec<(&String, &Value)>>();
+        members.sort_by(|a, b| a.0.cmp(b.0));
+        for (member, member_value) in members {
+          self.inspect_at(member, member_value, 0, self.max_elements, depth, level + 1, lines);
+        }
+      }
+      _ => {}
+    }
+  }
+
+  pub fn type_name(&self, value: &Value) -> String {
+    match value {
+      Value::Array(array) => match as_string(array) {
+        Some(_) => "string".to_string(),
+        None => format!("array {} {}", array.values.len(), format_type(&array.value_type)),
+      },
+      Value::Layout(layout) => format!("{}.{}", self.module_name(&layout.module_name), layout.layout_name),
+      Value::Reference(_) => match self.context.and_then(|context| context.resolve(value.clone()).ok()) {
+        Some(target) => format!("ref {}", self.type_name(&target)),
+        None => "ref".to_string(),
+      },
+      // typing a function pointer needs the compilation unit, and the value already names the function
+      Value::FunctionPointer(_) => "funcp".to_string(),
+      _ => format_type(&value.type_of()),
+    }
+  }
+
+  fn module_name(&self, module_name: &String) -> String {
+    match (module_name.as_str(), self.context) {
+      ("this", Some(context)) => context.current_module.clone(),
+      _ => module_name.clone(),
+    }
+  }
+}
+
+// the text of a u8 array if it is valid UTF-8
+pub fn as_string(array: &Array) -> Option<String> {
+  if array.value_type != Type::Unsigned8 {
+    return None;
+  }
+  let bytes = array.values.iter().map(|value| value.to_u8().ok()).collect::<Option<Vec<u8>>>()?;
+  String::from_utf8(bytes).ok()
+}
+
+// types as they are written in hydro source
+pub fn format_type(value_type: &Type) -> String {
+  match value_type {
+    Type::Any => "any".to_string(),
+    Type::Void => "void".to_string(),
+    Type::Boolean => "bool".to_string(),
+    Type::Unsigned8 => "u8".to_string(),
+    Type::Unsigned16 => "u16".to_string(),
+    Type::Unsigned32 => "u32".to_string(),
+    Type::Unsigned64 => "u64".to_string(),
+    Type::Unsigned128 => "u128".to_string(),
+    Type::Signed8 => "s8".to_string(),
+    Type::Signed16 => "s16".to_string(),
+    Type::Signed32 => "s32".to_string(),
+    Type::Signed64 => "s64".to_string(),
+    Type::Signed128 => "s128".to_string(),
+    Type::Float32 => "f32".to_string(),
+    Type::Float64 => "f64".to_string(),
+    Type::Array(Some(length), subtype) => format!("array {} {}", length, format_type(subtype)),
+    Type::Array(None, subtype) => format!("array {}", format_type(subtype)),
+    Type::Layout(module_name, layout_name, _) => format!("{}.{}", module_name, layout_name),
+    Type::FunctionPointer(_, _) => "funcp".to_string(),
+    Type::Reference(subtype) => format!("ref {}", format_type(subtype)),
+  }
+}
+
+fn reference_path(reference: &Reference) -> String {
+  match reference {
+    Reference::Variable(variable) => variable.name.clone(),
+    Reference::ArrayIndex(index) => format!("{}[{}]", inner_path(index.reference.deref()), index.index.to_string()),
+    Reference::LayoutIndex(member) => format!("{}.{}", inner_path(member.reference.deref()), member.index),
+  }
+}
+
+fn inner_path(value: &Value) -> String {
+  match value {
+    Value::Reference(reference) => reference_path(reference),
+    other => other.to_string(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::hydro::value::{ArrayIndexRef, FunctionPointer, Layout, VariableRef};
+  use std::collections::HashMap;
+
+  fn array(value_type: Type, values: Vec<Value>) -> Value {
+    Value::Array(Array::new(value_type, Box::new(Value::Unsigned64(values.len() as u64)), values))
+  }
+
+  fn context() -> ExecutionContext {
+    let mut variables = HashMap::new();
+    variables.insert("numbers".to_string(), array(Type::Signed32, (0..40).map(Value::Signed32).collect()));
+    ExecutionContext {
+      parent_execution_context: None,
+      stack: Vec::new(),
+      program_counter: 0,
+      variables,
+      return_value: None,
+      current_function: "main".to_string(),
+      current_module: "main".to_string(),
+    }
+  }
+
+  #[test]
+  fn strings_layouts_and_references_are_readable() {
+    let context = context();
+    let formatter = ValueFormatter::new(Some(&context));
+    assert_eq!(formatter.format(&Value::string("hi \"there\"".to_string())), "\"hi \\\"there\\\"\"");
+    assert_eq!(formatter.format(&array(Type::Unsigned8, vec![Value::Unsigned8(0xff)])), "[255]");
+
+    let mut members = HashMap::new();
+    members.insert("y".to_string(), Value::Signed32(2));
+    members.insert("x".to_string(), Value::Signed32(1));
+    members.insert("name".to_string(), Value::string("origin".to_string()));
+    let point = Value::Layout(Layout::new("this".to_string(), "point".to_string(), members));
+    assert_eq!(formatter.format(&point), "main.point { name: \"origin\", x: 1, y: 2 }");
+
+    let element = Value::Reference(Reference::ArrayIndex(ArrayIndexRef::new(Box::new(Value::Reference(Reference::Variable(VariableRef::new("numbers".to_string())))), Box::new(Value::Unsigned64(3)))));
+    assert_eq!(formatter.format(&element), "&numbers[3] -> 3");
+    assert_eq!(ValueFormatter::new(None).format(&element), "&numbers[3]");
+  }
+
+  #[test]
+  fn large_arrays_are_elided_and_paged() {
+    let context = context();
+    let formatter = ValueFormatter::new(Some(&context));
+    let numbers = context.variables.get("numbers").unwrap();
+    assert!(formatter.format(numbers).ends_with("15, … 24 more]"));
+
+    let lines = formatter.inspect("numbers", numbers, 38, 5, 2);
+    assert_eq!(lines, vec!["numbers: array 40 s32", "  [38]: s32 = 38", "  [39]: s32 = 39", "  … showing [38, 40) of 40"]);
+  }
+
+  #[test]
+  fn function_pointers_and_references_have_types() {
+    let context = context();
+    let formatter = ValueFormatter::new(Some(&context));
+    let pointer = Value::FunctionPointer(FunctionPointer { module: Some("main".to_string()), function: "answer".to_string() });
+    assert_eq!(formatter.inspect("f", &pointer, 0, 16, 2), vec!["f: funcp = funcp main.answer"]);
+    let local = Value::FunctionPointer(FunctionPointer { module: None, function: "answer".to_string() });
+    assert_eq!(ValueFormatter::new(None).inspect("f", &local, 0, 16, 2), vec!["f: funcp = funcp answer"]);
+
+    let element = Value::Reference(Reference::ArrayIndex(ArrayIndexRef::new(Box::new(Value::Reference(Reference::Variable(VariableRef::new("numbers".to_string())))), Box::new(Value::Unsigned64(3)))));
+    assert_eq!(formatter.inspect("r", &element, 0, 16, 2), vec!["r: ref s32 = &numbers[3] -> 3"]);
+    let missing = Value::Reference(Reference::Variable(VariableRef::new("missing".to_string())));
+    assert_eq!(formatter.inspect("r", &missing, 0, 16, 2), vec!["r: ref = &missing (dangling)"]);
+    assert_eq!(ValueFormatter::new(None).inspect("r", &element, 0, 16, 2), vec!["r: ref = &numbers[3]"]);
+  }
+}
@@ -1,4 +1,4 @@
-use super::value::Value;
+use super::value::{Reference, Value};
 use crate::hydro::function::Target;
 use crate::hydro::value::Type;
 use ocean_macros::Debuggable;
@@ -169,10 +169,16 @@ pub struct Load {}
 pub struct Store {}
 
 #[derive(Debug, Clone, Debuggable)]
-pub struct SetArrayIndex {}
+pub struct SetArrayIndex {
+  // cleared when the compiler proved every index at this site is in bounds
+  pub bounds_checked: bool,
+}
 
 #[derive(Debug, Clone, Debuggable)]
-pub struct GetArrayIndex {}
+pub struct GetArrayIndex {
+  // cleared when the compiler proved every index at this site is in bounds
+  pub bounds_checked: bool,
+}
 
 #[derive(Debug, Clone, Debuggable)]
 pub struct SetLayoutIndex {
@@ -199,3 +205,113 @@ pub struct AllocateArray {
   pub array_size: Option<u64>,
   pub array_sub_type: Type,
 }
+
+impl Instruction {
+  // the number of values the instruction pops and then pushes. Calls and returns depend on runtime values so they have none
+  pub fn stack_effect(&self) -> Option<(usize, usize)> {
+    match self {
+      Instruction::PushValue(_) => Some((0, 1)),
+      Instruction::PopValue(_) => Some((1, 0)),
+      Instruction::Duplicate(duplicate) => Some((1 + duplicate.offset, 2 + duplicate.offset)),
+      Instruction::Swap(_) => Some((2, 2)),
+      Instruction::Rotate(rotate) => Some((rotate.size.unsigned_abs() as usize, rotate.size.unsigned_abs() as usize)),
+      Instruction::Add(_)
+      | Instruction::Subtract(_)
+      | Instruction::Multiply(_)
+      | Instruction::Divide(_)
+      | Instruction::Modulo(_)
+      | Instruction::LeftShift(_)
+      | Instruction::RightShift(_)
+      | Instruction::BitwiseAnd(_)
+      | Instruction::BitwiseOr(_)
+      | Instruction::BitwiseXor(_)
+      | Instruction::And(_)
+      | Instruction::Or(_)
+      | Instruction::Xor(_)
+      | Instruction::Equal(_)
+      | Instruction::NotEqual(_)
+      | Instruction::LessThan(_)
+      | Instruction::GreaterThan(_)
+      | Instruction::LessThanEqual(_)
+      | Instruction::GreaterThanEqual(_) => Some((2, 1)),
+      Instruction::BitwiseNot(_) | Instruction::Not(_) | Instruction::Cast(_) => Some((1, 1)),
+      Instruction::Jump(_) => Some((0, 0)),
+      Instruction::Branch(_) => Some((1, 0)),
+      Instruction::Call(_) | Instruction::Return(_) => None,
+      Instruction::Load(_) => Some((1, 1)),
+      Instruction::Store(_) => Some((2, 1)),
+      Instruction::GetArrayIndex(_) => Some((2, 2)),
+      Instruction::SetArrayIndex(_) => Some((3, 1)),
+      Instruction::GetLayoutIndex(_) => Some((1, 2)),
+      Instruction::SetLayoutIndex(_) => Some((2, 1)),
+      Instruction::Allocate(_) => Some((1, 1)),
+      Instruction::AllocateArray(allocate) => Some((if allocate.array_size.is_none() { 1 } else { 0 }, 1)),
+    }
+  }
+}
+
+impl Instruction {
+  // a short assembly style rendering for disassembly and visualizations. This isn't guaranteed to parse back in
+  pub fn to_string(&self) -> String {
+    match self {
+      Instruction::PushValue(push) => match &push.value {
+        Value::FunctionPointer(pointer) => format!("push funcp {} {}", pointer.module.clone().unwrap_or("this".to_string()), pointer.function),
+        Value::Reference(Reference::Variable(variable)) => format!("push vref {}", variable.name),
+        Value::Reference(reference) => format!("push {:?}", reference),
+        Value::Array(array) if Type::subset(&array.value_type, &Type::Unsigned8) => format!("push string {:?}", push.value.to_string()),
+        value => format!("push {:?} {}", value.type_of(), value.to_string()),
+      },
+      Instruction::PopValue(_) => "pop".to_string(),
+      Instruction::Duplicate(duplicate) => match duplicate.offset {
+        0 => "duplicate".to_string(),
+        offset => format!("duplicate {}", offset),
+      },
+      Instruction::Swap(_) => "swap".to_string(),
+      Instruction::Rotate(rotate) => format!("rotate {}", rotate.size),
+      Instruction::Add(_) => "add".to_string(),
+      Instruction::Subtract(_) => "subtract".to_string(),
+      Instruction::Multiply(_) => "multiply".to_string(),
+      Instruction::Divide(_) => "divide".to_string(),
+      Instruction::Modulo(_) => "modulo".to_string(),
+      Instruction::LeftShift(_) => "shiftleft".to_string(),
+      Instruction::RightShift(_) => "shiftright".to_string(),
+      Instruction::BitwiseAnd(_) => "bitand".to_string(),
+      Instruction::BitwiseOr(_) => "bitor".to_string(),
+      Instruction::BitwiseXor(_) => "bitxor".to_string(),
+      Instruction::BitwiseNot(_) => "bitnot".to_string(),
+      Instruction::And(_) => "and".to_string(),
+      Instruction::Or(_) => "or".to_string(),
+      Instruction::Xor(_) => "xor".to_string(),
+      Instruction::Not(_) => "not".to_string(),
+      Instruction::Equal(_) => "equal".to_string(),
+      Instruction::NotEqual(_) => "notequal".to_string(),
+      Instruction::LessThan(_) => "lessthan".to_string(),
+      Instruction::GreaterThan(_) => "greaterthan".to_string(),
+      Instruction::LessThanEqual(_) => "lessthanequal".to_string(),
+      Instruction::GreaterThanEqual(_) => "greaterthanequal".to_string(),
+      Instruction::Jump(jump) => format!("jump {}", target_string(&jump.target)),
+      Instruction::Branch(branch) => format!("branch {} {}", target_string(&branch.true_target), target_string(&branch.false_target)),
+      Instruction::Call(_) => "call".to_string(),
+      Instruction::Return(_) => "return".to_string(),
+      Instruction::Cast(cast) => format!("cast {:?}", cast.to_type),
+      Instruction::Load(_) => "load".to_string(),
+      Instruction::Store(_) => "store".to_string(),
+      Instruction::GetArrayIndex(_) => "getindex".to_string(),
+      Instruction::SetArrayIndex(_) => "setindex".to_string(),
+      Instruction::GetLayoutIndex(get_layout_index) => format!("getindex {}", get_layout_index.member),
+      Instruction::SetLayoutIndex(set_layout_index) => format!("setindex {}", set_layout_index.member),
+      Instruction::Allocate(allocate) => format!("alloc {:?}", allocate.allocated_type),
+      Instruction::AllocateArray(allocate) => match allocate.array_size {
+        Some(size) => format!("alloc array {} {:?}", size, allocate.array_sub_type),
+        None => format!("alloc array {:?}", allocate.array_sub_type),
+      },
+    }
+  }
+}
+
+fn target_string(target: &Target) -> String {
+  match target {
+    Target::Label(label) => label.clone(),
+    Target::Index(index) => format!("@{}", index),
+  }
+}
@@ -2,6 +2,7 @@ use crate::hydro::exception::Exception;
 use crate::hydro::executioncontext::ExecutionContext;
 use crate::hydro::value::Value;
 use lazy_static::lazy_static;
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::io;
 use std::io::Write;
@@ -12,6 +13,26 @@ lazy_static! {
   pub static ref INTRINSIC_MANAGER: IntrinsicManager = IntrinsicManager::initialize();
 }
 
+thread_local! {
+  // the debug adapter owns stdin and stdout so it takes the program output and sends it to the client instead
+  static OUTPUT_CAPTURE: RefCell<Option<Box<dyn FnMut(String)>>> = RefCell::new(None);
+}
+
+pub fn capture_output(capture: Option<Box<dyn FnMut(String)>>) {
+  OUTPUT_CAPTURE.with(|output_capture| *output_capture.borrow_mut() = capture);
+}
+
+fn is_output_captured() -> bool {
+  OUTPUT_CAPTURE.with(|output_capture| output_capture.borrow().is_some())
+}
+
+fn write_output(text: String) {
+  OUTPUT_CAPTURE.with(|output_capture| match output_capture.borrow_mut().as_mut() {
+    Some(capture) => capture(text),
+    None => print!("{}", text),
+  });
+}
+
 type IntrinsicFunction = fn(&ExecutionContext, Vec<Value>) -> Result<Vec<Value>, Exception>;
 
 pub struct IntrinsicManager {
@@ -47,7 +68,7 @@ fn print(context: &ExecutionContext, args: Vec<Value>) -> Result<Vec<Value>, Exc
   if args.len() != 1 {
     Err(Exception::new(context.clone(), format!("Expected 1 argument for print but got {}", args.len()).as_str()))
   } else {
-    print!("{}", args[0].to_string());
+    write_output(args[0].to_string());
     Ok(Vec::new())
   }
 }
@@ -56,7 +77,7 @@ fn println(context: &ExecutionContext, args: Vec<Value>) -> Result<Vec<Value>, E
   if args.len() != 1 {
     Err(Exception::new(context.clone(), format!("Expected 1 argument for print but got {}", args.len()).as_str()))
   } else {
-    println!("{}", args[0].to_string());
+    write_output(format!("{}\n", args[0].to_string()));
     Ok(Vec::new())
   }
 }
@@ -65,6 +86,9 @@ fn flush(context: &ExecutionContext, args: Vec<Value>) -> Result<Vec<Value>, Exc
   if args.len() != 0 {
     Err(Exception::new(context.clone(), format!("Expected 0 arguments for readline but got {}", args.len()).as_str()))
   } else {
+    if is_output_captured() {
+      return Ok(Vec::new());
+    }
     match io::stdout().flush() {
       Ok(()) => Ok(Vec::new()),
       Err(io_error) => Err(Exception::new(context.clone(), io_error.to_string().as_str())),
@@ -75,6 +99,8 @@ fn flush(context: &ExecutionContext, args: Vec<Value>) -> Result<Vec<Value>, Exc
 fn readline(context: &ExecutionContext, args: Vec<Value>) -> Result<Vec<Value>, Exception> {
   if args.len() != 0 {
     Err(Exception::new(context.clone(), format!("Expected 0 arguments for readline but got {}", args.len()).as_str()))
+  } else if is_output_captured() {
+    Err(Exception::new(context.clone(), "Cannot read input while the debug adapter is using stdin"))
   } else {
     let mut input = String::new();
     match io::stdin().read_line(&mut input) {
@@ -1,3 +1,4 @@
+use crate::hydro::sourcelocation::SourceLocation;
 use crate::hydro::value::{Layout, Type, Value};
 use std::collections::HashMap;
 use ocean_macros::New;
@@ -6,11 +7,13 @@ use ocean_macros::New;
 pub struct LayoutTemplate {
   pub name: String,
   pub members: HashMap<String, Value>,
+  #[default(None)]
+  pub location: Option<SourceLocation>,
 }
 
 impl LayoutTemplate {
   pub fn build(name: &str) -> Self {
-    Self { name: name.to_string(), members: HashMap::new() }
+    Self { name: name.to_string(), members: HashMap::new(), location: None }
   }
 
   pub fn member(mut self, name: &str, value: Value) -> Self {
@@ -0,0 +1,24 @@
+use crate::hydro::analyzer::analysiscontext::AnalysisWarningKind;
+use crate::hydro::analyzer::functionanalysis::analyze_function;
+use crate::hydro::compilationunit::CompilationUnit;
+use crate::hydro::linter::{Lint, LintDiagnostic, LintPass};
+use crate::hydro::module::Module;
+
+// surfaces the warnings found by abstractly interpreting each function
+pub struct AnalysisLints {}
+
+impl LintPass for AnalysisLints {
+  fn lint(&self, compilation_unit: &CompilationUnit, module: &Module, diagnostics: &mut Vec<LintDiagnostic>) {
+    for function in module.functions.values() {
+      let analysis = analyze_function(compilation_unit, module.name.as_str(), function);
+      for warning in analysis.warnings {
+        let lint = match warning.kind {
+          AnalysisWarningKind::DivisorMayBeZero => Lint::DivisorMayBeZero,
+          AnalysisWarningKind::BranchAlwaysTaken => Lint::BranchAlwaysTaken,
+          AnalysisWarningKind::IndexMayBeOutOfBounds => Lint::IndexMayBeOutOfBounds,
+        };
+        diagnostics.push(LintDiagnostic::new(lint, function.get_source_location(warning.program_counter), warning.message));
+      }
+    }
+  }
+}
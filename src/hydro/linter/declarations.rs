@@ -0,0 +1,125 @@
+use crate::hydro::callgraph::CallGraph;
+use crate::hydro::compilationunit::CompilationUnit;
+use crate::hydro::instruction::Instruction;
+use crate::hydro::linter::{Lint, LintDiagnostic, LintPass};
+use crate::hydro::module::Module;
+use crate::hydro::value::{Type, Value};
+use std::collections::HashSet;
+
+// Module level lints for 'using' statements, functions, and layouts that nothing refers to
+pub struct DeclarationLints {}
+
+impl LintPass for DeclarationLints {
+  fn lint(&self, compilation_unit: &CompilationUnit, module: &Module, diagnostics: &mut Vec<LintDiagnostic>) {
+    self.lint_usings(module, diagnostics);
+    self.lint_functions(compilation_unit, module, diagnostics);
+    self.lint_layouts(compilation_unit, module, diagnostics);
+  }
+}
+
+impl DeclarationLints {
+  fn lint_usings(&self, module: &Module, diagnostics: &mut Vec<LintDiagnostic>) {
+    let mut referenced = HashSet::new();
+    for function in module.functions.values() {
+      for (module_name, _) in CallGraph::find_function_pointers(function, module.name.as_str()) {
+        referenced.insert(module_name);
+      }
+      // a module can also be used only for its layouts
+      for (module_name, _) in DeclarationLints::find_layouts(function.body.iter(), module.name.as_str()) {
+        referenced.insert(module_name);
+      }
+    }
+
+    let mut usings = module.using_locations.keys().collect::<Vec<&String>>();
+    usings.sort();
+    for using in usings {
+      if !referenced.contains(using) {
+        diagnostics.push(LintDiagnostic::new(Lint::UnusedUsing, module.using_locations.get(using), format!("Module '{}' is imported but never referenced", using)));
+      }
+    }
+  }
+
+  fn lint_functions(&self, compilation_unit: &CompilationUnit, module: &Module, diagnostics: &mut Vec<LintDiagnostic>) {
+    let call_graph = CallGraph::build(compilation_unit);
+    let mut called = HashSet::new();
+    for (caller, callees) in &call_graph.edges {
+      called.extend(callees.iter().filter(|callee| *callee != caller).cloned());
+    }
+
+    let mut function_names = module.functions.keys().filter(|name| name.as_str() != "main").collect::<Vec<&String>>();
+    function_names.sort();
+    for function_name in function_names {
+      if !called.contains(&(module.name.clone(), function_name.clone())) {
+        let function = &module.functions[function_name];
+        diagnostics.push(LintDiagnostic::new(Lint::UnusedFunction, function.location.as_ref(), format!("Function '{}.{}' is never called", module.name, function_name)));
+      }
+    }
+  }
+
+  fn lint_layouts(&self, compilation_unit: &CompilationUnit, module: &Module, diagnostics: &mut Vec<LintDiagnostic>) {
+    let mut instantiated = HashSet::new();
+    for module_name in compilation_unit.get_module_names() {
+      let other_module = compilation_unit.get_module(module_name.as_str()).unwrap();
+      for function in other_module.functions.values() {
+        instantiated.extend(DeclarationLints::find_layouts(function.body.iter().filter(|instruction| !matches!(instruction, Instruction::Cast(_))), module_name.as_str()));
+      }
+      // a layout nested inside of another layout gets created with it
+      for template in other_module.layout_templates.values() {
+        for member in template.members.values() {
+          DeclarationLints::collect_value_layouts(member, &mut instantiated);
+        }
+      }
+    }
+
+    let mut layout_names = module.layout_templates.keys().collect::<Vec<&String>>();
+    layout_names.sort();
+    for layout_name in layout_names {
+      if !instantiated.contains(&(module.name.clone(), layout_name.clone())) {
+        let layout = &module.layout_templates[layout_name];
+        diagnostics.push(LintDiagnostic::new(Lint::UnusedLayout, layout.location.as_ref(), format!("Layout '{}.{}' is never instantiated", module.name, layout_name)));
+      }
+    }
+  }
+
+  fn find_layouts<'a>(instructions: impl Iterator<Item = &'a Instruction>, module_name: &str) -> HashSet<(String, String)> {
+    let mut layouts = HashSet::new();
+    for instruction in instructions {
+      match instruction {
+        Instruction::PushValue(push) => DeclarationLints::collect_value_layouts(&push.value, &mut layouts),
+        Instruction::Allocate(allocate) => DeclarationLints::collect_type_layouts(&allocate.allocated_type, module_name, &mut layouts),
+        Instruction::AllocateArray(allocate) => DeclarationLints::collect_type_layouts(&allocate.array_sub_type, module_name, &mut layouts),
+        Instruction::Cast(cast) => DeclarationLints::collect_type_layouts(&cast.to_type, module_name, &mut layouts),
+        _ => {}
+      }
+    }
+    layouts
+  }
+
+  fn collect_type_layouts(found_type: &Type, module_name: &str, layouts: &mut HashSet<(String, String)>) {
+    match found_type {
+      Type::Layout(layout_module, layout_name, _) => {
+        let layout_module = if layout_module == "this" { module_name } else { layout_module.as_str() };
+        layouts.insert((layout_module.to_string(), layout_name.clone()));
+      }
+      Type::Array(_, subtype) | Type::Reference(subtype) => DeclarationLints::collect_type_layouts(subtype, module_name, layouts),
+      _ => {}
+    }
+  }
+
+  fn collect_value_layouts(value: &Value, layouts: &mut HashSet<(String, String)>) {
+    match value {
+      Value::Layout(layout) => {
+        layouts.insert((layout.module_name.clone(), layout.layout_name.clone()));
+        for member in layout.values.values() {
+          DeclarationLints::collect_value_layouts(member, layouts);
+        }
+      }
+      Value::Array(array) => {
+        for element in &array.values {
+          DeclarationLints::collect_value_layouts(element, layouts);
+        }
+      }
+      _ => {}
+    }
+  }
+}
@@ -0,0 +1,31 @@
+use crate::hydro::analyzer::dataflow::definiteinitialization::{find_uninitialized_uses, DefiniteInitialization};
+use crate::hydro::analyzer::dataflow::reachingdefinitions::ReachingDefinitions;
+use crate::hydro::analyzer::dataflow::solve;
+use crate::hydro::analyzer::dataflow::variableaccess::find_variable_accesses;
+use crate::hydro::analyzer::functionanalysis::analyze_function;
+use crate::hydro::compilationunit::CompilationUnit;
+use crate::hydro::controlflowgraph::ControlFlowGraph;
+use crate::hydro::linter::{Lint, LintDiagnostic, LintPass};
+use crate::hydro::module::Module;
+
+pub struct InitializationLints {}
+
+impl LintPass for InitializationLints {
+  fn lint(&self, compilation_unit: &CompilationUnit, module: &Module, diagnostics: &mut Vec<LintDiagnostic>) {
+    for function in module.functions.values() {
+      let accesses = find_variable_accesses(function, &analyze_function(compilation_unit, module.name.as_str(), function));
+      let graph = ControlFlowGraph::build(function);
+      let initialized = solve(&DefiniteInitialization { accesses: &accesses }, function, &graph);
+      let reaching = solve(&ReachingDefinitions { accesses: &accesses }, function, &graph);
+
+      for uninitialized in find_uninitialized_uses(&accesses, &initialized, &reaching) {
+        let message = if uninitialized.never_initialized {
+          format!("Variable '{}' is used before it is ever stored", uninitialized.variable)
+        } else {
+          format!("Variable '{}' may be used before it is stored on some paths", uninitialized.variable)
+        };
+        diagnostics.push(LintDiagnostic::new(Lint::MaybeUninitialized, function.get_source_location(uninitialized.program_counter), message));
+      }
+    }
+  }
+}
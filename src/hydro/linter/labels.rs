@@ -0,0 +1,45 @@
+use crate::hydro::compilationunit::CompilationUnit;
+use crate::hydro::function::Target;
+use crate::hydro::instruction::Instruction;
+use crate::hydro::linter::{Lint, LintDiagnostic, LintPass};
+use crate::hydro::module::Module;
+use std::collections::HashSet;
+
+pub struct LabelLints {}
+
+impl LintPass for LabelLints {
+  fn lint(&self, _compilation_unit: &CompilationUnit, module: &Module, diagnostics: &mut Vec<LintDiagnostic>) {
+    for function in module.functions.values() {
+      let mut referenced = HashSet::new();
+      for (index, instruction) in function.body.iter().enumerate() {
+        let targets = match instruction {
+          Instruction::Jump(jump) => vec![&jump.target],
+          Instruction::Branch(branch) => vec![&branch.true_target, &branch.false_target],
+          _ => continue,
+        };
+        for target in targets {
+          if let Target::Label(label) = target {
+            referenced.insert(label.clone());
+            if !function.jump_labels.contains_key(label.as_str()) {
+              diagnostics.push(LintDiagnostic::new(
+                Lint::UndefinedLabel,
+                function.get_source_location(index),
+                format!("Label '{}' is not defined in function '{}.{}'", label, module.name, function.name),
+              ));
+            }
+          }
+        }
+      }
+
+      for label in function.jump_labels.keys() {
+        if !referenced.contains(label) {
+          diagnostics.push(LintDiagnostic::new(
+            Lint::UnusedLabel,
+            function.label_locations.get(label),
+            format!("Label '{}' is never jumped to", label),
+          ));
+        }
+      }
+    }
+  }
+}
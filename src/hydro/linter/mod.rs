@@ -0,0 +1,294 @@
+use crate::hydro::compilationunit::CompilationUnit;
+use crate::hydro::linter::analysis::AnalysisLints;
+use crate::hydro::linter::declarations::DeclarationLints;
+use crate::hydro::linter::initialization::InitializationLints;
+use crate::hydro::linter::labels::LabelLints;
+use crate::hydro::linter::unreachable::UnreachableLints;
+use crate::hydro::linter::variables::VariableLints;
+use crate::hydro::module::Module;
+use crate::hydro::sourcelocation::SourceLocation;
+use crate::util::errors::{Error, ErrorMetadata, Severity};
+use std::collections::HashMap;
+use std::fs;
+
+pub mod analysis;
+pub mod declarations;
+pub mod initialization;
+pub mod labels;
+pub mod unreachable;
+pub mod variables;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Lint {
+  UndefinedLabel,
+  UnusedLabel,
+  UnreachableInstruction,
+  StoredNeverLoaded,
+  AllocWithoutUse,
+  UnusedUsing,
+  UnusedFunction,
+  UnusedLayout,
+  DivisorMayBeZero,
+  BranchAlwaysTaken,
+  IndexMayBeOutOfBounds,
+  MaybeUninitialized,
+}
+
+impl Lint {
+  pub fn all() -> Vec<Lint> {
+    vec![
+      Lint::UndefinedLabel,
+      Lint::UnusedLabel,
+      Lint::UnreachableInstruction,
+      Lint::StoredNeverLoaded,
+      Lint::AllocWithoutUse,
+      Lint::UnusedUsing,
+      Lint::UnusedFunction,
+      Lint::UnusedLayout,
+      Lint::DivisorMayBeZero,
+      Lint::BranchAlwaysTaken,
+      Lint::IndexMayBeOutOfBounds,
+      Lint::MaybeUninitialized,
+    ]
+  }
+
+  pub fn name(&self) -> &'static str {
+    match self {
+      Lint::UndefinedLabel => "undefined-label",
+      Lint::UnusedLabel => "unused-label",
+      Lint::UnreachableInstruction => "unreachable-instruction",
+      Lint::StoredNeverLoaded => "stored-never-loaded",
+      Lint::AllocWithoutUse => "alloc-without-use",
+      Lint::UnusedUsing => "unused-using",
+      Lint::UnusedFunction => "unused-function",
+      Lint::UnusedLayout => "unused-layout",
+      Lint::DivisorMayBeZero => "divisor-may-be-zero",
+      Lint::BranchAlwaysTaken => "branch-always-taken",
+      Lint::IndexMayBeOutOfBounds => "index-may-be-out-of-bounds",
+      Lint::MaybeUninitialized => "maybe-uninitialized",
+    }
+  }
+
+  pub fn from_name(name: &str) -> Option<Lint> {
+    Lint::all().into_iter().find(|lint| lint.name() == name)
+  }
+
+  pub fn severity(&self) -> Severity {
+    match self {
+      Lint::UndefinedLabel => Severity::Error,
+      Lint::UnusedLabel | Lint::UnusedUsing | Lint::UnusedLayout => Severity::Hint,
+      Lint::UnreachableInstruction
+      | Lint::StoredNeverLoaded
+      | Lint::AllocWithoutUse
+      | Lint::UnusedFunction
+      | Lint::DivisorMayBeZero
+      | Lint::BranchAlwaysTaken
+      | Lint::IndexMayBeOutOfBounds
+      | Lint::MaybeUninitialized => Severity::Warning,
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct LintDiagnostic {
+  pub lint: Lint,
+  pub location: Option<SourceLocation>,
+  pub message: String,
+}
+
+impl LintDiagnostic {
+  pub fn new(lint: Lint, location: Option<&SourceLocation>, message: String) -> Self {
+    Self { lint, location: location.cloned(), message }
+  }
+
+  pub fn to_error(&self) -> Error {
+    let span = self.location.as_ref().map(|location| location.span).unwrap_or((0, 0));
+    Error::new_with_metadata(
+      self.lint.severity(),
+      span,
+      format!("{} [{}]", self.message, self.lint.name()),
+      ErrorMetadata::new().suggestion(format!("Add '% allow({})' above this line to suppress", self.lint.name())),
+    )
+  }
+}
+
+pub trait LintPass {
+  fn lint(&self, compilation_unit: &CompilationUnit, module: &Module, diagnostics: &mut Vec<LintDiagnostic>);
+}
+
+pub struct Linter {
+  allowed: Vec<Lint>,
+  passes: Vec<Box<dyn LintPass>>,
+}
+
+impl Linter {
+  pub fn new(allowed: Vec<Lint>) -> Self {
+    Self {
+      allowed,
+      passes: vec![
+        Box::new(LabelLints {}),
+        Box::new(UnreachableLints {}),
+        Box::new(VariableLints {}),
+        Box::new(DeclarationLints {}),
+        Box::new(AnalysisLints {}),
+        Box::new(InitializationLints {}),
+      ],
+    }
+  }
+
+  pub fn lint(&self, compilation_unit: &CompilationUnit, module_names: &Vec<String>) -> LintReport {
+    let mut diagnostics = Vec::new();
+    for module_name in module_names {
+      if let Some(module) = compilation_unit.get_module(module_name.as_str()) {
+        for pass in &self.passes {
+          pass.lint(compilation_unit, module, &mut diagnostics);
+        }
+      }
+    }
+
+    // every file is read once for both the suppressions and the report
+    let mut sources = HashMap::new();
+    for location in diagnostics.iter().filter_map(|diagnostic| diagnostic.location.as_ref()) {
+      sources.entry(location.file.clone()).or_insert_with(|| fs::read_to_string(location.file.as_str()).ok());
+    }
+    let lines = sources.iter().filter_map(|(file, contents)| contents.as_ref().map(|contents| (file.as_str(), contents.lines().collect::<Vec<&str>>()))).collect::<HashMap<&str, Vec<&str>>>();
+
+    let mut diagnostics = diagnostics
+      .into_iter()
+      .filter(|diagnostic| !self.allowed.contains(&diagnostic.lint) && !diagnostic.location.as_ref().and_then(|location| lines.get(location.file.as_str())).is_some_and(|lines| Linter::is_suppressed(diagnostic, lines)))
+      .collect::<Vec<LintDiagnostic>>();
+    diagnostics.sort_by_key(|diagnostic| diagnostic.location.as_ref().map(|location| (location.file.clone(), location.line, location.column)));
+    LintReport { diagnostics, sources }
+  }
+
+  // a lint can be silenced with a '% allow(lint-name)' comment on the same line or the line before it
+  fn is_suppressed(diagnostic: &LintDiagnostic, lines: &[&str]) -> bool {
+    let Some(location) = &diagnostic.location else {
+      return false;
+    };
+    let marker = format!("allow({})", diagnostic.lint.name());
+    let is_marked = |line: usize| lines.get(line).and_then(|line| line.find('%').map(|start| line[start..].contains(marker.as_str()))).unwrap_or(false);
+    is_marked(location.line - 1) || (location.line >= 2 && is_marked(location.line - 2))
+  }
+}
+
+pub struct LintReport {
+  pub diagnostics: Vec<LintDiagnostic>,
+  // the contents of every file a diagnostic was found in, or None when it couldn't be read
+  sources: HashMap<String, Option<String>>,
+}
+
+impl LintReport {
+  pub fn has_errors(&self) -> bool {
+    self.diagnostics.iter().any(|diagnostic| matches!(diagnostic.lint.severity(), Severity::Error))
+  }
+
+  pub fn print(&self) {
+    for diagnostic in &self.diagnostics {
+      let error = diagnostic.to_error();
+      match &diagnostic.location {
+        Some(location) => match self.sources.get(&location.file).and_then(|contents| contents.as_ref()) {
+          Some(contents) => error.display_message(contents.as_bytes(), &location.file, 1),
+          None => error.display_message_without_file(&location.file),
+        },
+        None => error.display_message_without_file(&"unknown".to_string()),
+      }
+    }
+
+    let count = |severity: &str| self.diagnostics.iter().filter(|diagnostic| diagnostic.lint.severity().name() == severity).count();
+    println!("Lint Completed: {} errors, {} warnings, {} hints", count("Error"), count("Warning"), count("Hint"));
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::hydro::frontend::parser::Parser;
+
+  // the lines the lint reports when the source is linted from a real file, since that is where suppressions are read from
+  fn lint_lines(lint: Lint, lines: &[&str]) -> Vec<usize> {
+    let directory = std::env::temp_dir().join(format!("hydro-lint-{}-{}", lint.name(), std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    let source_file = directory.join("main.h2o");
+    let source = lines.iter().map(|line| format!("{}\n", line)).collect::<String>();
+    fs::write(&source_file, source.as_str()).unwrap();
+
+    let mut compilation_unit = CompilationUnit::new();
+    for module in Parser::from_source(source_file.to_str().unwrap(), source.as_str()).parse() {
+      compilation_unit.add_module(&module);
+    }
+    let diagnostics = Linter::new(Vec::new()).lint(&compilation_unit, &vec!["main".to_string()]).diagnostics;
+    fs::remove_dir_all(&directory).unwrap();
+    diagnostics.into_iter().filter(|diagnostic| diagnostic.lint == lint).map(|diagnostic| diagnostic.location.unwrap().line).collect()
+  }
+
+  // the lint is reported on the given line and an allow comment right above that line silences it
+  fn check(lint: Lint, lines: &[&str], line: usize) {
+    assert_eq!(lint_lines(lint, lines), vec![line], "{} should be reported", lint.name());
+
+    let allow = format!("    % allow({})", lint.name());
+    let mut suppressed = lines.to_vec();
+    suppressed.insert(line - 1, allow.as_str());
+    assert_eq!(lint_lines(lint, &suppressed), Vec::<usize>::new(), "{} should be suppressed", lint.name());
+  }
+
+  #[test]
+  fn undefined_label() {
+    check(Lint::UndefinedLabel, &["module main", "main body", "    jump nowhere"], 3);
+  }
+
+  #[test]
+  fn unused_label() {
+    check(Lint::UnusedLabel, &["module main", "main body", "    label spare", "    return"], 3);
+  }
+
+  #[test]
+  fn unreachable_instruction() {
+    check(Lint::UnreachableInstruction, &["module main", "main body", "    return", "    push s32 1", "    return"], 4);
+  }
+
+  #[test]
+  fn stored_never_loaded() {
+    check(Lint::StoredNeverLoaded, &["module main", "main body", "    push vref x", "    alloc s32", "    push s32 1", "    store", "    pop", "    return"], 6);
+  }
+
+  #[test]
+  fn alloc_without_use() {
+    check(Lint::AllocWithoutUse, &["module main", "main body", "    push vref x", "    alloc s32", "    pop", "    return"], 4);
+  }
+
+  #[test]
+  fn unused_using() {
+    check(Lint::UnusedUsing, &["module main", "using other", "main body", "    return"], 2);
+  }
+
+  #[test]
+  fn unused_function() {
+    check(Lint::UnusedFunction, &["module main", "function spare body", "    return", "main body", "    return"], 2);
+  }
+
+  #[test]
+  fn unused_layout() {
+    check(Lint::UnusedLayout, &["module main", "layout point", "    s32 x", "main body", "    return"], 2);
+  }
+
+  #[test]
+  fn divisor_may_be_zero() {
+    check(Lint::DivisorMayBeZero, &["module main", "main body", "    push s32 1", "    push s32 0", "    divide", "    return"], 5);
+  }
+
+  #[test]
+  fn branch_always_taken() {
+    check(Lint::BranchAlwaysTaken, &["module main", "main body", "    push bool true", "    branch done done", "    label done", "    return"], 4);
+  }
+
+  #[test]
+  fn index_may_be_out_of_bounds() {
+    check(Lint::IndexMayBeOutOfBounds, &["module main", "main body", "    alloc array 4 s32", "    push u64 7", "    getindex", "    return"], 5);
+  }
+
+  #[test]
+  fn maybe_uninitialized() {
+    check(Lint::MaybeUninitialized, &["module main", "main body", "    push vref x", "    load", "    return"], 4);
+  }
+}
@@ -0,0 +1,39 @@
+use crate::hydro::compilationunit::CompilationUnit;
+use crate::hydro::linter::{Lint, LintDiagnostic, LintPass};
+use crate::hydro::module::Module;
+
+pub struct UnreachableLints {}
+
+impl LintPass for UnreachableLints {
+  fn lint(&self, _compilation_unit: &CompilationUnit, module: &Module, diagnostics: &mut Vec<LintDiagnostic>) {
+    for function in module.functions.values() {
+      let mut reachable = vec![false; function.body.len()];
+      let mut work_list = if function.body.is_empty() { vec![] } else { vec![0] };
+      while let Some(index) = work_list.pop() {
+        if reachable[index] {
+          continue;
+        }
+        reachable[index] = true;
+        // undefined labels are reported on their own so they are skipped here
+        work_list.extend(function.known_successors(index));
+      }
+
+      let mut index = 0;
+      while index < reachable.len() {
+        if reachable[index] {
+          index += 1;
+          continue;
+        }
+        let start = index;
+        while index < reachable.len() && !reachable[index] {
+          index += 1;
+        }
+        diagnostics.push(LintDiagnostic::new(
+          Lint::UnreachableInstruction,
+          function.get_source_location(start),
+          format!("{} unreachable instruction(s) in function '{}.{}'", index - start, module.name, function.name),
+        ));
+      }
+    }
+  }
+}
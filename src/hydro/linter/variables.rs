@@ -0,0 +1,161 @@
+use crate::hydro::compilationunit::CompilationUnit;
+use crate::hydro::function::Function;
+use crate::hydro::instruction::Instruction;
+use crate::hydro::linter::{Lint, LintDiagnostic, LintPass};
+use crate::hydro::module::Module;
+use crate::hydro::value::{Reference, Value};
+use std::collections::HashMap;
+
+// how many different stack shapes we follow into a single instruction before giving up on precision there
+const MAX_STATES_PER_INSTRUCTION: usize = 16;
+
+#[derive(Debug, Clone, Default)]
+struct VariableUsage {
+  stored_at: Option<usize>,
+  allocated_at: Option<usize>,
+  used: bool,
+}
+
+// Follows which variable each stack slot refers to so we can tell what ends up consuming a 'push vref'
+pub struct VariableLints {}
+
+impl LintPass for VariableLints {
+  fn lint(&self, _compilation_unit: &CompilationUnit, module: &Module, diagnostics: &mut Vec<LintDiagnostic>) {
+    for function in module.functions.values() {
+      let mut usages = self.find_usages(function).into_iter().collect::<Vec<(String, VariableUsage)>>();
+      usages.sort_by(|a, b| a.0.cmp(&b.0));
+
+      for (variable, usage) in usages {
+        if usage.used {
+          continue;
+        }
+        match (usage.stored_at, usage.allocated_at) {
+          (Some(stored_at), _) => diagnostics.push(LintDiagnostic::new(
+            Lint::StoredNeverLoaded,
+            function.get_source_location(stored_at),
+            format!("Variable '{}' is stored but never loaded", variable),
+          )),
+          (None, Some(allocated_at)) => diagnostics.push(LintDiagnostic::new(
+            Lint::AllocWithoutUse,
+            function.get_source_location(allocated_at),
+            format!("Variable '{}' is allocated but never used", variable),
+          )),
+          (None, None) => {}
+        }
+      }
+    }
+  }
+}
+
+impl VariableLints {
+  fn find_usages(&self, function: &Function) -> HashMap<String, VariableUsage> {
+    let mut usages: HashMap<String, VariableUsage> = HashMap::new();
+    let mut visited: HashMap<usize, Vec<Vec<Option<String>>>> = HashMap::new();
+    let mut work_list = vec![(0, vec![None; function.parameters.len()])];
+
+    while let Some((index, mut stack)) = work_list.pop() {
+      if index >= function.body.len() {
+        continue;
+      }
+      let states = visited.entry(index).or_default();
+      if states.contains(&stack) {
+        continue;
+      }
+      if states.len() >= MAX_STATES_PER_INSTRUCTION {
+        VariableLints::use_all(&mut stack, &mut usages);
+        if states.contains(&stack) {
+          continue;
+        }
+      }
+      states.push(stack.clone());
+
+      match &function.body[index] {
+        Instruction::PushValue(push) => {
+          let variable = VariableLints::base_variable(&push.value);
+          if let Some(variable) = &variable {
+            usages.entry(variable.clone()).or_default();
+          }
+          stack.push(variable);
+        }
+        Instruction::PopValue(_) => {
+          stack.pop();
+        }
+        Instruction::Duplicate(duplicate) if stack.len() > duplicate.offset => {
+          let top = stack.pop().unwrap();
+          stack.insert(stack.len() - duplicate.offset, top.clone());
+          stack.push(top);
+        }
+        Instruction::Swap(_) if stack.len() >= 2 => {
+          let length = stack.len();
+          stack.swap(length - 1, length - 2);
+        }
+        Instruction::Store(_) => {
+          let value = stack.pop().flatten();
+          VariableLints::use_variable(value, &mut usages);
+          let reference = stack.pop().flatten();
+          if let Some(variable) = &reference {
+            usages.entry(variable.clone()).or_default().stored_at.get_or_insert(index);
+          }
+          stack.push(reference);
+        }
+        Instruction::Allocate(_) => {
+          let reference = stack.pop().flatten();
+          if let Some(variable) = &reference {
+            usages.entry(variable.clone()).or_default().allocated_at.get_or_insert(index);
+          }
+          stack.push(reference);
+        }
+        Instruction::Call(_) => {
+          // we don't know how many values the call takes or gives back so anything on the stack counts as used
+          VariableLints::use_all(&mut stack, &mut usages);
+          stack.clear();
+        }
+        Instruction::Return(_) => {
+          VariableLints::use_all(&mut stack, &mut usages);
+          continue;
+        }
+        instruction => match instruction.stack_effect() {
+          Some((pops, pushes)) => {
+            for _ in 0..pops {
+              let popped = stack.pop().flatten();
+              VariableLints::use_variable(popped, &mut usages);
+            }
+            stack.extend(vec![None; pushes]);
+          }
+          None => {
+            VariableLints::use_all(&mut stack, &mut usages);
+            stack.clear();
+          }
+        },
+      }
+
+      for successor in function.known_successors(index) {
+        work_list.push((successor, stack.clone()));
+      }
+    }
+
+    usages
+  }
+
+  fn use_variable(variable: Option<String>, usages: &mut HashMap<String, VariableUsage>) {
+    if let Some(variable) = variable {
+      usages.entry(variable).or_default().used = true;
+    }
+  }
+
+  fn use_all(stack: &mut Vec<Option<String>>, usages: &mut HashMap<String, VariableUsage>) {
+    for slot in stack.iter_mut() {
+      VariableLints::use_variable(slot.take(), usages);
+    }
+  }
+
+  // index and layout references still read or write the variable they start from
+  fn base_variable(value: &Value) -> Option<String> {
+    match value {
+      Value::Reference(Reference::Variable(reference)) => Some(reference.name.clone()),
+      Value::Reference(Reference::ArrayIndex(reference)) => VariableLints::base_variable(&reference.reference),
+      Value::Reference(Reference::LayoutIndex(reference)) => VariableLints::base_variable(&reference.reference),
+      _ => None,
+    }
+  }
+}
@@ -3,6 +3,7 @@ use std::collections::HashMap;
 use crate::hydro::function::Function;
 use crate::hydro::intrinsic::Intrinsic;
 use crate::hydro::layouttemplate::LayoutTemplate;
+use crate::hydro::sourcelocation::SourceLocation;
 
 #[derive(Debug, Clone)]
 pub struct Module {
@@ -12,6 +13,7 @@ pub struct Module {
   pub layout_templates: HashMap<String, LayoutTemplate>,
   pub functions: HashMap<String, Function>,
   pub intrinsics: HashMap<String, Intrinsic>,
+  pub using_locations: HashMap<String, SourceLocation>,
 }
 
 impl Module {}
@@ -25,6 +27,7 @@ impl Module {
       layout_templates: layout_templates.iter().map(|x| (x.clone().name, x.clone())).collect::<HashMap<String, LayoutTemplate>>(),
       functions: functions.iter().map(|x| (x.clone().name, x.clone())).collect::<HashMap<String, Function>>(),
       intrinsics: HashMap::new(),
+      using_locations: HashMap::new(),
     }
   }
 
@@ -36,6 +39,7 @@ impl Module {
       layout_templates: HashMap::new(),
       functions: HashMap::new(),
       intrinsics: HashMap::new(),
+      using_locations: HashMap::new(),
     }
   }
 
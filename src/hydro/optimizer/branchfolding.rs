@@ -0,0 +1,80 @@
+use crate::hydro::function::Function;
+use crate::hydro::instruction::{Branch, Instruction, Jump, Pop};
+use crate::hydro::optimizer::FunctionPass;
+use crate::hydro::value::Value;
+
+// Replaces branches on constant conditions and branches with identical targets with unconditional jumps
+pub struct BranchFolding {}
+
+impl FunctionPass for BranchFolding {
+  fn name(&self) -> String {
+    "Branch Folding".to_string()
+  }
+
+  fn run(&self, function: &mut Function) -> usize {
+    let mut rewrites = 0;
+    let mut index = 0;
+    while index < function.body.len() {
+      let branch = match &function.body[index] {
+        Instruction::Branch(branch) => branch.clone(),
+        _ => {
+          index += 1;
+          continue;
+        }
+      };
+
+      let condition = match index.checked_sub(1).map(|previous| &function.body[previous]) {
+        Some(Instruction::PushValue(push)) if !function.is_jump_target(index) => match push.value {
+          Value::Boolean(condition) => Some(condition),
+          _ => None,
+        },
+        _ => None,
+      };
+
+      if let Some(condition) = condition {
+        let target = if condition { branch.true_target } else { branch.false_target };
+        function.replace_range(index - 1, index + 1, vec![Instruction::Jump(Jump { target })]);
+        rewrites += 1;
+      } else if self.same_target(function, &branch) {
+        // the condition still needs to come off of the stack
+        function.replace_range(index, index + 1, vec![Instruction::PopValue(Pop {}), Instruction::Jump(Jump { target: branch.true_target })]);
+        rewrites += 1;
+        index += 2;
+      } else {
+        index += 1;
+      }
+    }
+    rewrites
+  }
+}
+
+impl BranchFolding {
+  fn same_target(&self, function: &Function, branch: &Branch) -> bool {
+    match (function.get_target_pointer(branch.true_target.clone()), function.get_target_pointer(branch.false_target.clone())) {
+      (Ok(true_target), Ok(false_target)) => true_target == false_target,
+      _ => false,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::hydro::testing::{function_with_body, listing};
+
+  #[test]
+  fn folds_constant_conditions() {
+    let mut function = function_with_body("f returns s32", &["push bool false", "branch yes no", "label yes", "push s32 1", "return", "label no", "push s32 2", "return"]);
+    assert_eq!(BranchFolding {}.run(&mut function), 1);
+    assert_eq!(listing(&function), vec!["jump no", "push Signed32 1", "return", "push Signed32 2", "return"]);
+    assert_eq!((function.jump_labels["yes"], function.jump_labels["no"]), (1, 3));
+  }
+
+  #[test]
+  fn folds_branches_to_the_same_target() {
+    let mut function = function_with_body("f bool returns s32", &["branch done done", "label done", "push s32 1", "return"]);
+    assert_eq!(BranchFolding {}.run(&mut function), 1);
+    assert_eq!(listing(&function), vec!["pop", "jump done", "push Signed32 1", "return"]);
+    assert_eq!(function.jump_labels["done"], 2);
+  }
+}
@@ -0,0 +1,178 @@
+use crate::hydro::compilationunit::CompilationUnit;
+use crate::hydro::executioncontext::ExecutionContext;
+use crate::hydro::function::Function;
+use crate::hydro::instruction::{Instruction, Push};
+use crate::hydro::optimizer::FunctionPass;
+use crate::hydro::value::Value;
+use std::collections::HashMap;
+
+// Evaluates arithmetic, bitwise, logical, comparison, and cast instructions whose operands are all pushed constants
+pub struct ConstantFolding {}
+
+impl FunctionPass for ConstantFolding {
+  fn name(&self) -> String {
+    "Constant Folding".to_string()
+  }
+
+  fn run(&self, function: &mut Function) -> usize {
+    let mut rewrites = 0;
+    let mut index = 0;
+    while index < function.body.len() {
+      match self.fold_at(function, index) {
+        Some((length, value)) => {
+          function.replace_range(index, index + length, vec![Instruction::PushValue(Push { value })]);
+          rewrites += 1;
+          // the folded value may be the operand of another foldable instruction so back up to catch it
+          index = index.saturating_sub(2);
+        }
+        None => index += 1,
+      }
+    }
+    rewrites
+  }
+}
+
+impl ConstantFolding {
+  fn fold_at(&self, function: &Function, index: usize) -> Option<(usize, Value)> {
+    if let Some(value) = self.fold_binary(function, index) {
+      return Some((3, value));
+    }
+    if let Some(value) = self.fold_unary(function, index) {
+      return Some((2, value));
+    }
+    None
+  }
+
+  fn fold_binary(&self, function: &Function, index: usize) -> Option<Value> {
+    if index + 2 >= function.body.len() || function.is_jump_target(index + 1) || function.is_jump_target(index + 2) {
+      return None;
+    }
+    let (a, b) = match (&function.body[index], &function.body[index + 1]) {
+      (Instruction::PushValue(a), Instruction::PushValue(b)) => (&a.value, &b.value),
+      _ => return None,
+    };
+
+    ConstantFolding::fold(&function.body[index + 2], vec![a.clone(), b.clone()])
+  }
+
+  fn fold_unary(&self, function: &Function, index: usize) -> Option<Value> {
+    if index + 1 >= function.body.len() || function.is_jump_target(index + 1) {
+      return None;
+    }
+    let a = match &function.body[index] {
+      Instruction::PushValue(a) => &a.value,
+      _ => return None,
+    };
+
+    ConstantFolding::fold(&function.body[index + 1], vec![a.clone()])
+  }
+
+  // evaluates the instruction on constant operands when the vm would not throw or panic on them
+  pub fn fold(instruction: &Instruction, operands: Vec<Value>) -> Option<Value> {
+    let foldable = match (instruction, operands.as_slice()) {
+      (Instruction::Add(_) | Instruction::Subtract(_) | Instruction::Multiply(_), [a, b]) => is_numeric(a) && is_numeric(b),
+      (Instruction::Divide(_) | Instruction::Modulo(_), [a, b]) => is_numeric(a) && is_numeric(b) && !is_zero(b),
+      (Instruction::BitwiseAnd(_) | Instruction::BitwiseOr(_) | Instruction::BitwiseXor(_), [a, b]) => is_integer(a) && is_integer(b),
+      (Instruction::LeftShift(_) | Instruction::RightShift(_), [a, b]) => match (bit_width(a), b) {
+        (Some(width), Value::Unsigned8(amount)) => (*amount as u32) < width,
+        _ => false,
+      },
+      (Instruction::And(_) | Instruction::Or(_) | Instruction::Xor(_), [a, b]) => is_boolean(a) && is_boolean(b),
+      (
+        Instruction::Equal(_)
+        | Instruction::NotEqual(_)
+        | Instruction::LessThan(_)
+        | Instruction::GreaterThan(_)
+        | Instruction::LessThanEqual(_)
+        | Instruction::GreaterThanEqual(_),
+        [a, b],
+      ) => (is_boolean(a) && is_boolean(b)) || (is_integer(a) && is_integer(b)) || (is_float(a) && is_float(b)),
+      (Instruction::BitwiseNot(_), [a]) => is_integer(a),
+      (Instruction::Not(_), [a]) => is_boolean(a),
+      (Instruction::Cast(cast), [a]) => a.to_type(cast.to_type.clone()).is_ok(),
+      _ => false,
+    };
+    if !foldable {
+      return None;
+    }
+
+    ConstantFolding::evaluate(instruction, operands)
+  }
+
+  // runs the instruction against a scratch context so folded values match what the vm would have computed
+  fn evaluate(instruction: &Instruction, stack: Vec<Value>) -> Option<Value> {
+    let mut context = ExecutionContext {
+      parent_execution_context: None,
+      stack,
+      program_counter: 0,
+      variables: HashMap::new(),
+      return_value: None,
+      current_function: "".to_string(),
+      current_module: "".to_string(),
+    };
+    match instruction.execute(&CompilationUnit::new(), &mut context) {
+      Ok(_) if context.stack.len() == 1 => context.stack.pop(),
+      _ => None,
+    }
+  }
+}
+
+fn is_boolean(value: &Value) -> bool {
+  matches!(value, Value::Boolean(_))
+}
+
+fn is_integer(value: &Value) -> bool {
+  bit_width(value).is_some()
+}
+
+fn is_float(value: &Value) -> bool {
+  matches!(value, Value::Float32(_) | Value::Float64(_))
+}
+
+fn is_numeric(value: &Value) -> bool {
+  is_integer(value) || is_float(value)
+}
+
+fn bit_width(value: &Value) -> Option<u32> {
+  match value {
+    Value::Unsigned8(_) | Value::Signed8(_) => Some(8),
+    Value::Unsigned16(_) | Value::Signed16(_) => Some(16),
+    Value::Unsigned32(_) | Value::Signed32(_) => Some(32),
+    Value::Unsigned64(_) | Value::Signed64(_) => Some(64),
+    Value::Unsigned128(_) | Value::Signed128(_) => Some(128),
+    _ => None,
+  }
+}
+
+fn is_zero(value: &Value) -> bool {
+  match value {
+    Value::Float32(x) => *x == 0.0,
+    Value::Float64(x) => *x == 0.0,
+    Value::Unsigned128(x) => *x == 0,
+    Value::Signed128(x) => *x == 0,
+    _ => value.to_u64().map(|x| x == 0).unwrap_or(false),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::hydro::testing::{function_with_body, listing};
+
+  #[test]
+  fn folds_chains_of_constants() {
+    let mut function = function_with_body("f returns bool", &["push s32 2", "push s32 3", "add", "push s32 4", "multiply", "push s32 20", "equal", "not", "return"]);
+    assert_eq!(ConstantFolding {}.run(&mut function), 4);
+    assert_eq!(listing(&function), vec!["push Boolean false", "return"]);
+  }
+
+  #[test]
+  fn leaves_instructions_that_would_throw() {
+    let mut function = function_with_body("f returns s32", &["push s32 1", "push s32 0", "divide", "return"]);
+    assert_eq!(ConstantFolding {}.run(&mut function), 0);
+
+    // the multiply is a jump target so its operands aren't always the pushed constants
+    let mut function = function_with_body("f s32 returns s32", &["push s32 2", "push s32 3", "label again", "multiply", "jump again"]);
+    assert_eq!(ConstantFolding {}.run(&mut function), 0);
+  }
+}
@@ -0,0 +1,75 @@
+use crate::hydro::function::Function;
+use crate::hydro::optimizer::FunctionPass;
+
+// Removes instructions that can not be reached from the start of the function such as code after a return or jump
+pub struct DeadCodeElimination {}
+
+impl FunctionPass for DeadCodeElimination {
+  fn name(&self) -> String {
+    "Dead Code Elimination".to_string()
+  }
+
+  fn run(&self, function: &mut Function) -> usize {
+    let reachable = match self.find_reachable(function) {
+      Some(reachable) => reachable,
+      // a jump to a label that doesn't exist will throw at runtime so we leave the function alone
+      None => return 0,
+    };
+
+    let mut rewrites = 0;
+    let mut end = function.body.len();
+    while end > 0 {
+      if reachable[end - 1] {
+        end -= 1;
+        continue;
+      }
+      let mut start = end - 1;
+      while start > 0 && !reachable[start - 1] {
+        start -= 1;
+      }
+      function.replace_range(start, end, vec![]);
+      rewrites += 1;
+      end = start;
+    }
+    rewrites
+  }
+}
+
+impl DeadCodeElimination {
+  fn find_reachable(&self, function: &Function) -> Option<Vec<bool>> {
+    let mut reachable = vec![false; function.body.len()];
+    let mut work_list = Vec::new();
+    if !function.body.is_empty() {
+      work_list.push(0);
+    }
+    while let Some(index) = work_list.pop() {
+      if reachable[index] {
+        continue;
+      }
+      reachable[index] = true;
+      work_list.extend(function.successors(index).ok()?);
+    }
+    Some(reachable)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::hydro::testing::{function_with_body, listing};
+
+  #[test]
+  fn removes_unreachable_code() {
+    let mut function = function_with_body("f returns s32", &["jump end", "push s32 1", "pop", "label end", "push s32 2", "return", "push s32 3", "return"]);
+    assert_eq!(DeadCodeElimination {}.run(&mut function), 2);
+    assert_eq!(listing(&function), vec!["jump end", "push Signed32 2", "return"]);
+    assert_eq!(function.jump_labels["end"], 1);
+  }
+
+  #[test]
+  fn leaves_functions_with_unknown_labels() {
+    let mut function = function_with_body("f returns s32", &["jump nowhere", "push s32 1", "return"]);
+    assert_eq!(DeadCodeElimination {}.run(&mut function), 0);
+    assert_eq!(function.body.len(), 3);
+  }
+}
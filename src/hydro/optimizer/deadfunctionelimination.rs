@@ -0,0 +1,195 @@
+use crate::hydro::callgraph::CallGraph;
+use crate::hydro::compilationunit::CompilationUnit;
+use crate::hydro::instruction::Instruction;
+use crate::hydro::optimizer::OptimizationReport;
+use crate::hydro::value::{Type, Value};
+use std::collections::HashSet;
+
+// Removes the functions, intrinsics, layouts, and modules that can't be reached from main
+pub struct DeadFunctionElimination {}
+
+impl DeadFunctionElimination {
+  pub fn run(&self, compilation_unit: &mut CompilationUnit, report: &mut OptimizationReport) {
+    if compilation_unit.get_function("main", "main").is_none() {
+      return;
+    }
+
+    let call_graph = CallGraph::build(compilation_unit);
+    let (reachable_functions, reachable_layouts) = self.find_reachable(compilation_unit, &call_graph);
+
+    for module_name in compilation_unit.get_module_names() {
+      let module = compilation_unit.get_module_mut(module_name.as_str()).unwrap();
+
+      let mut removed_functions = module.functions.keys().chain(module.intrinsics.keys()).filter(|name| !reachable_functions.contains(&(module_name.clone(), name.to_string()))).cloned().collect::<Vec<String>>();
+      removed_functions.sort();
+      for function_name in removed_functions {
+        module.functions.remove(function_name.as_str());
+        module.intrinsics.remove(function_name.as_str());
+        report.removed_functions.push(format!("{}.{}", module_name, function_name));
+      }
+
+      let mut removed_layouts = module.layout_templates.keys().filter(|name| !reachable_layouts.contains(&(module_name.clone(), name.to_string()))).cloned().collect::<Vec<String>>();
+      removed_layouts.sort();
+      for layout_name in removed_layouts {
+        module.layout_templates.remove(layout_name.as_str());
+        report.removed_layouts.push(format!("{}.{}", module_name, layout_name));
+      }
+    }
+
+    let removed_modules = compilation_unit
+      .get_module_names()
+      .into_iter()
+      .filter(|module_name| {
+        let module = compilation_unit.get_module(module_name.as_str()).unwrap();
+        module_name != "main" && module.functions.is_empty() && module.intrinsics.is_empty() && module.layout_templates.is_empty()
+      })
+      .collect::<Vec<String>>();
+    for module_name in &removed_modules {
+      compilation_unit.remove_module(module_name.clone());
+      report.removed_modules.push(module_name.clone());
+    }
+    for module_name in compilation_unit.get_module_names() {
+      let module = compilation_unit.get_module_mut(module_name.as_str()).unwrap();
+      module.modules.retain(|imported| !removed_modules.contains(imported));
+    }
+  }
+
+  fn find_reachable(&self, compilation_unit: &CompilationUnit, call_graph: &CallGraph) -> (HashSet<(String, String)>, HashSet<(String, String)>) {
+    let mut reachable_functions = HashSet::new();
+    let mut reachable_layouts = HashSet::new();
+    let mut function_work_list = vec![("main".to_string(), "main".to_string())];
+    let mut layout_work_list = Vec::new();
+
+    while !function_work_list.is_empty() || !layout_work_list.is_empty() {
+      while let Some(current) = function_work_list.pop() {
+        if !reachable_functions.insert(current.clone()) {
+          continue;
+        }
+        function_work_list.extend(call_graph.callees(&current));
+
+        if let Some(function) = compilation_unit.get_function(current.0.as_str(), current.1.as_str()) {
+          let mut types = function.parameters.clone();
+          types.extend(function.return_type.clone());
+          for instruction in &function.body {
+            match instruction {
+              Instruction::PushValue(push) => self.collect_value_layouts(&push.value, &mut layout_work_list),
+              Instruction::Cast(cast) => types.push(cast.to_type.clone()),
+              Instruction::Allocate(allocate) => types.push(allocate.allocated_type.clone()),
+              Instruction::AllocateArray(allocate) => types.push(allocate.array_sub_type.clone()),
+              _ => {}
+            }
+          }
+          for found_type in types {
+            self.collect_type_layouts(&found_type, current.0.as_str(), &mut layout_work_list);
+          }
+        }
+      }
+
+      while let Some(current) = layout_work_list.pop() {
+        if !reachable_layouts.insert(current.clone()) {
+          continue;
+        }
+        let template = compilation_unit.get_module(current.0.as_str()).and_then(|module| module.layout_templates.get(current.1.as_str()));
+        if let Some(template) = template {
+          for member in template.members.values() {
+            self.collect_value_layouts(member, &mut layout_work_list);
+            let mut pointers = Vec::new();
+            CallGraph::collect_function_pointers(member, current.0.as_str(), &mut pointers);
+            function_work_list.extend(pointers);
+          }
+        }
+      }
+    }
+
+    (reachable_functions, reachable_layouts)
+  }
+
+  fn collect_type_layouts(&self, found_type: &Type, module_name: &str, layouts: &mut Vec<(String, String)>) {
+    match found_type {
+      Type::Layout(layout_module, layout_name, members) => {
+        let layout_module = if layout_module == "this" { module_name } else { layout_module.as_str() };
+        layouts.push((layout_module.to_string(), layout_name.clone()));
+        for member in members.iter().flat_map(|members| members.values()) {
+          self.collect_type_layouts(member, module_name, layouts);
+        }
+      }
+      Type::Array(_, subtype) | Type::Reference(subtype) => self.collect_type_layouts(subtype, module_name, layouts),
+      Type::FunctionPointer(parameters, return_type) => {
+        for parameter in parameters {
+          self.collect_type_layouts(parameter, module_name, layouts);
+        }
+        self.collect_type_layouts(return_type, module_name, layouts);
+      }
+      _ => {}
+    }
+  }
+
+  fn collect_value_layouts(&self, value: &Value, layouts: &mut Vec<(String, String)>) {
+    match value {
+      Value::Layout(layout) => {
+        layouts.push((layout.module_name.clone(), layout.layout_name.clone()));
+        for member in layout.values.values() {
+          self.collect_value_layouts(member, layouts);
+        }
+      }
+      Value::Array(array) => {
+        for element in &array.values {
+          self.collect_value_layouts(element, layouts);
+        }
+      }
+      _ => {}
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::hydro::testing::compile;
+  use crate::hydro::value::FunctionPointer;
+
+  fn eliminate(compilation_unit: &mut CompilationUnit) -> OptimizationReport {
+    let mut report = OptimizationReport::new(1);
+    DeadFunctionElimination {}.run(compilation_unit, &mut report);
+    report
+  }
+
+  fn function_names(compilation_unit: &CompilationUnit, module_name: &str) -> Vec<String> {
+    let mut names = compilation_unit.get_module(module_name).unwrap().functions.keys().cloned().collect::<Vec<String>>();
+    names.sort();
+    names
+  }
+
+  #[test]
+  fn unreachable_functions_layouts_and_modules_are_removed() {
+    let mut compilation_unit = compile(
+      "module helpers\n\nfunction unused returns s32 body\n    push s32 1\n    return\n\nmodule main\nusing helpers\n\nlayout point\n    s32 x\n\nlayout unused\n    s32 y\n\nfunction dead returns s32 body\n    push s32 2\n    return\n\nmain body\n    push vref p\n    alloc this point\n    pop\n    push s32 0\n    return\n",
+    );
+    let report = eliminate(&mut compilation_unit);
+
+    assert_eq!(report.removed_functions, vec!["helpers.unused", "main.dead"]);
+    assert_eq!(report.removed_layouts, vec!["main.unused"]);
+    assert_eq!(report.removed_modules, vec!["helpers"]);
+    assert_eq!(compilation_unit.get_module_names(), vec!["main"]);
+    assert_eq!(function_names(&compilation_unit, "main"), vec!["main"]);
+    let main = compilation_unit.get_module("main").unwrap();
+    assert_eq!(main.layout_templates.keys().collect::<Vec<&String>>(), vec!["point"]);
+    assert!(main.modules.is_empty());
+  }
+
+  #[test]
+  fn functions_only_stored_or_held_by_a_layout_are_kept() {
+    let mut compilation_unit = compile(
+      "module main\n\nlayout handlers\n    s32 count\n\nfunction stored returns s32 body\n    push s32 1\n    return\n\nfunction member returns s32 body\n    push s32 2\n    return\n\nfunction dead returns s32 body\n    push s32 3\n    return\n\nmain body\n    push vref callback\n    push funcp main stored\n    store\n    pop\n    push vref h\n    alloc this handlers\n    pop\n    push s32 0\n    return\n",
+    );
+    // layout members can only be given a function pointer outside of the source
+    let template = compilation_unit.get_module_mut("main").unwrap().layout_templates.get_mut("handlers").unwrap();
+    template.members.insert("callback".to_string(), Value::FunctionPointer(FunctionPointer { module: Some("main".to_string()), function: "member".to_string() }));
+    let report = eliminate(&mut compilation_unit);
+
+    assert_eq!(report.removed_functions, vec!["main.dead"]);
+    assert!(report.removed_layouts.is_empty());
+    assert!(report.removed_modules.is_empty());
+    assert_eq!(function_names(&compilation_unit, "main"), vec!["main", "member", "stored"]);
+  }
+}
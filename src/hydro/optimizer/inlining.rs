@@ -0,0 +1,294 @@
+use crate::hydro::callgraph::CallGraph;
+use crate::hydro::compilationunit::CompilationUnit;
+use crate::hydro::function::Function;
+use crate::hydro::instruction::{AllocateArray, Cast, Instruction, Push};
+use crate::hydro::optimizer::OptimizationReport;
+use crate::hydro::value::{ArrayIndexRef, FunctionPointer, LayoutIndexRef, Reference, Type, Value, VariableRef};
+
+// Replaces `push funcp` + `call` sequences with the body of small non-recursive callees. Inlined code skips the parameter and
+// return type checks that a real call would do so only straight line callees with a known stack effect are considered, and
+// only at call sites where the argument types and the type of the callee's result are known to pass those checks.
+pub struct Inlining {
+  pub max_callee_size: usize,
+  pub max_rounds: usize,
+}
+
+impl Inlining {
+  pub fn run(&self, compilation_unit: &mut CompilationUnit, report: &mut OptimizationReport) {
+    let mut inline_count = 0;
+    for _ in 0..self.max_rounds {
+      let call_graph = CallGraph::build(compilation_unit);
+      let reachable = call_graph.reachable_from(&("main".to_string(), "main".to_string()));
+      let mut callers = reachable.into_iter().collect::<Vec<(String, String)>>();
+      callers.sort();
+
+      let mut changed = false;
+      for (caller_module, caller_name) in callers {
+        let mut index = 0;
+        loop {
+          let caller = match compilation_unit.get_function(caller_module.as_str(), caller_name.as_str()) {
+            Some(caller) => caller,
+            // intrinsics don't have bodies to inline into
+            None => break,
+          };
+          if index + 1 >= caller.body.len() {
+            break;
+          }
+
+          let callee_key = match (&caller.body[index], &caller.body[index + 1]) {
+            (Instruction::PushValue(Push { value: Value::FunctionPointer(pointer) }), Instruction::Call(_)) if !caller.is_jump_target(index + 1) => {
+              (pointer.module.clone().unwrap_or(caller_module.clone()), pointer.function.clone())
+            }
+            _ => {
+              index += 1;
+              continue;
+            }
+          };
+
+          let callee = match compilation_unit.get_function(callee_key.0.as_str(), callee_key.1.as_str()) {
+            Some(callee) if callee_key != (caller_module.clone(), caller_name.clone()) && !call_graph.is_recursive(&callee_key) && self.is_inlinable(callee) && self.passes_type_checks(compilation_unit, caller, caller_module.as_str(), index, callee, callee_key.0.as_str()) => callee,
+            _ => {
+              index += 1;
+              continue;
+            }
+          };
+
+          let inlined_body = self.inline_body(callee, callee_key.0.as_str(), inline_count);
+          let inlined_length = inlined_body.len();
+          report.inlined.push(format!("{}.{} into {}.{} at pc {}", callee_key.0, callee_key.1, caller_module, caller_name, index));
+          inline_count += 1;
+          changed = true;
+
+          let caller = compilation_unit.get_module_mut(caller_module.as_str()).unwrap().functions.get_mut(caller_name.as_str()).unwrap();
+          caller.replace_range(index, index + 2, inlined_body);
+          index += inlined_length;
+        }
+      }
+
+      if !changed {
+        break;
+      }
+    }
+  }
+
+  fn is_inlinable(&self, callee: &Function) -> bool {
+    if callee.body.is_empty() || callee.body.len() > self.max_callee_size {
+      return false;
+    }
+    if !matches!(callee.body.last(), Some(Instruction::Return(_))) {
+      return false;
+    }
+    // references are resolved by name so a reference argument would point at a different variable once inlined
+    if callee.parameters.iter().any(|parameter| matches!(parameter, Type::Reference(_) | Type::Any)) {
+      return false;
+    }
+
+    let mut depth = callee.parameters.len();
+    for instruction in &callee.body[..callee.body.len() - 1] {
+      match instruction {
+        Instruction::Jump(_) | Instruction::Branch(_) | Instruction::Allocate(_) => return false,
+        _ => {}
+      }
+      match instruction.stack_effect() {
+        Some((pops, pushes)) if pops <= depth => depth = depth - pops + pushes,
+        _ => return false,
+      }
+    }
+
+    match callee.return_type {
+      Some(Type::Void) => depth == 0,
+      _ => depth == 1,
+    }
+  }
+
+  // the types a call at `index` would check. The arguments are followed from the start of their basic block, the callee's
+  // result from its parameter types, and anything that isn't known keeps the call
+  fn passes_type_checks(&self, compilation_unit: &CompilationUnit, caller: &Function, caller_module: &str, index: usize, callee: &Function, callee_module: &str) -> bool {
+    let mut block_start = index;
+    while block_start > 0 && !caller.is_jump_target(block_start) && !matches!(caller.body[block_start - 1], Instruction::Jump(_) | Instruction::Branch(_) | Instruction::Return(_)) {
+      block_start -= 1;
+    }
+    let stack = self.stack_types(compilation_unit, caller_module, &caller.body[block_start..index], Vec::new());
+    if stack.len() < callee.parameters.len() {
+      return false;
+    }
+    let arguments = &stack[stack.len() - callee.parameters.len()..];
+    let arguments_pass = callee.parameters.iter().zip(arguments).all(|(parameter, argument)| match argument {
+      Some(argument) => Type::subset(argument, parameter),
+      None => false,
+    });
+    if !arguments_pass {
+      return false;
+    }
+
+    match &callee.return_type {
+      None | Some(Type::Void) => true,
+      Some(return_type) => {
+        let parameters = callee.parameters.iter().map(|parameter| Some(parameter.clone())).collect();
+        match self.stack_types(compilation_unit, callee_module, &callee.body[..callee.body.len() - 1], parameters).last() {
+          Some(Some(result)) => Type::subset(result, return_type),
+          _ => false,
+        }
+      }
+    }
+  }
+
+  // the static types of a straight line of instructions where None is a value of unknown type. Values taken from below
+  // the starting stack are unknown as well
+  fn stack_types(&self, compilation_unit: &CompilationUnit, module: &str, instructions: &[Instruction], mut stack: Vec<Option<Type>>) -> Vec<Option<Type>> {
+    for instruction in instructions {
+      let (pops, pushes) = match instruction.stack_effect() {
+        Some(effect) => effect,
+        // a nested call leaves values of unknown types behind
+        None => {
+          stack.clear();
+          continue;
+        }
+      };
+      if stack.len() < pops {
+        stack.splice(0..0, std::iter::repeat(None).take(pops - stack.len()));
+      }
+      match instruction {
+        Instruction::PushValue(push) => stack.push(Some(compilation_unit.type_of(&push.value, module))),
+        Instruction::Duplicate(duplicate) => {
+          let value = stack.last().unwrap().clone();
+          stack.insert(stack.len() - 1 - duplicate.offset, value);
+        }
+        Instruction::Swap(_) => {
+          let length = stack.len();
+          stack.swap(length - 1, length - 2);
+        }
+        Instruction::Rotate(rotate) if rotate.size > 0 => {
+          let value = stack.remove(stack.len() - rotate.size as usize);
+          stack.push(value);
+        }
+        Instruction::Rotate(rotate) if rotate.size < 0 => {
+          let value = stack.pop().unwrap();
+          stack.insert(stack.len() + 1 - rotate.size.unsigned_abs() as usize, value);
+        }
+        Instruction::Rotate(_) => {}
+        // mixed operand types widen or change signedness so only matching ones keep their type
+        Instruction::Add(_)
+        | Instruction::Subtract(_)
+        | Instruction::Multiply(_)
+        | Instruction::Divide(_)
+        | Instruction::Modulo(_)
+        | Instruction::BitwiseAnd(_)
+        | Instruction::BitwiseOr(_)
+        | Instruction::BitwiseXor(_)
+        | Instruction::And(_)
+        | Instruction::Or(_)
+        | Instruction::Xor(_) => {
+          let right = stack.pop().unwrap();
+          let left = stack.pop().unwrap();
+          stack.push(match (left, right) {
+            (Some(left), Some(right)) if left == right => Some(left),
+            _ => None,
+          });
+        }
+        Instruction::Equal(_) | Instruction::NotEqual(_) | Instruction::LessThan(_) | Instruction::GreaterThan(_) | Instruction::LessThanEqual(_) | Instruction::GreaterThanEqual(_) => {
+          stack.truncate(stack.len() - 2);
+          stack.push(Some(Type::Boolean));
+        }
+        Instruction::Cast(cast) => {
+          stack.pop();
+          stack.push(Some(cast.to_type.clone()));
+        }
+        _ => {
+          stack.truncate(stack.len() - pops);
+          stack.extend(std::iter::repeat(None).take(pushes));
+        }
+      }
+    }
+    stack
+  }
+
+  fn inline_body(&self, callee: &Function, callee_module: &str, inline_id: usize) -> Vec<Instruction> {
+    let prefix = format!("{}.{}#{}.", callee_module, callee.name, inline_id);
+    callee.body[..callee.body.len() - 1]
+      .iter()
+      .map(|instruction| match instruction {
+        Instruction::PushValue(push) => Instruction::PushValue(Push { value: self.rewrite_value(&push.value, callee_module, prefix.as_str()) }),
+        Instruction::Cast(cast) => Instruction::Cast(Cast { to_type: self.rewrite_type(&cast.to_type, callee_module) }),
+        Instruction::AllocateArray(allocate) => Instruction::AllocateArray(AllocateArray {
+          array_size: allocate.array_size,
+          array_sub_type: self.rewrite_type(&allocate.array_sub_type, callee_module),
+        }),
+        _ => instruction.clone(),
+      })
+      .collect()
+  }
+
+  // variables get a unique prefix so they can't collide with the caller and module relative names are made absolute
+  fn rewrite_value(&self, value: &Value, callee_module: &str, prefix: &str) -> Value {
+    match value {
+      Value::FunctionPointer(pointer) => Value::FunctionPointer(FunctionPointer {
+        module: Some(pointer.module.clone().unwrap_or(callee_module.to_string())),
+        function: pointer.function.clone(),
+      }),
+      Value::Reference(Reference::Variable(reference)) => Value::Reference(Reference::Variable(VariableRef { name: format!("{}{}", prefix, reference.name) })),
+      Value::Reference(Reference::ArrayIndex(reference)) => Value::Reference(Reference::ArrayIndex(ArrayIndexRef {
+        reference: Box::new(self.rewrite_value(&reference.reference, callee_module, prefix)),
+        index: Box::new(self.rewrite_value(&reference.index, callee_module, prefix)),
+      })),
+      Value::Reference(Reference::LayoutIndex(reference)) => Value::Reference(Reference::LayoutIndex(LayoutIndexRef {
+        reference: Box::new(self.rewrite_value(&reference.reference, callee_module, prefix)),
+        index: reference.index.clone(),
+      })),
+      _ => value.clone(),
+    }
+  }
+
+  fn rewrite_type(&self, type_to_rewrite: &Type, callee_module: &str) -> Type {
+    match type_to_rewrite {
+      Type::Layout(module_name, layout_name, members) if module_name == "this" => Type::Layout(callee_module.to_string(), layout_name.clone(), members.clone()),
+      Type::Array(length, subtype) => Type::Array(*length, Box::new(self.rewrite_type(subtype, callee_module))),
+      Type::Reference(subtype) => Type::Reference(Box::new(self.rewrite_type(subtype, callee_module))),
+      _ => type_to_rewrite.clone(),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::hydro::optimizer::Optimizer;
+  use crate::hydro::testing::{compile, debug_main, headless_debug_context};
+  use crate::hydro::value::Value;
+
+  // runs main unoptimized and at O3 and returns the optimized result along with the calls that were inlined
+  fn run_at_o0_and_o3(source: &str) -> (Result<Option<Value>, String>, Vec<String>) {
+    let unoptimized = compile(source);
+    let expected = debug_main(&unoptimized, &mut headless_debug_context()).map_err(|exception| exception.message);
+
+    let mut optimized = compile(source);
+    let report = Optimizer::new(3).optimize(&mut optimized);
+    let result = debug_main(&optimized, &mut headless_debug_context()).map_err(|exception| exception.message);
+    assert_eq!(expected.is_ok(), result.is_ok(), "O0 gave {:?} and O3 gave {:?}", expected, result);
+    if expected.is_ok() {
+      assert_eq!(expected, result);
+    }
+    (result, report.inlined)
+  }
+
+  #[test]
+  fn inlined_calls_keep_the_type_checks() {
+    let helper = "function helper u32 returns u32 body\n    push u32 1\n    add\n    return\n\n";
+
+    let (result, inlined) = run_at_o0_and_o3(format!("module main\n\n{}main body\n    push u32 5\n    push funcp main helper\n    call\n    return\n", helper).as_str());
+    assert_eq!((result, inlined.len()), (Ok(Some(Value::Unsigned32(6))), 1));
+
+    // the argument doesn't have the parameter type
+    let (result, inlined) = run_at_o0_and_o3(format!("module main\n\n{}main body\n    push s64 -3\n    push funcp main helper\n    call\n    return\n", helper).as_str());
+    assert!(result.is_err() && inlined.is_empty());
+
+    // the result of a u64 division doesn't have the declared return type
+    let half = "function half u64 returns u8 body\n    push u64 2\n    divide\n    return\n\n";
+    let (result, inlined) = run_at_o0_and_o3(format!("module main\n\n{}main body\n    push u64 1000\n    push funcp main half\n    call\n    return\n", half).as_str());
+    assert!(result.is_err() && inlined.is_empty());
+
+    // the argument is the caller's parameter so its type isn't known where helper is called
+    let outer = "function outer u32 returns u32 body\n    push funcp main helper\n    call\n    return\n\n";
+    let (result, inlined) = run_at_o0_and_o3(format!("module main\n\n{}{}main body\n    push u32 5\n    push funcp main outer\n    call\n    return\n", helper, outer).as_str());
+    assert_eq!((result, inlined.len()), (Ok(Some(Value::Unsigned32(6))), 0));
+  }
+}
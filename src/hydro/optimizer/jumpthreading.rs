@@ -0,0 +1,102 @@
+use crate::hydro::function::{Function, Target};
+use crate::hydro::instruction::{Branch, Instruction, Jump};
+use crate::hydro::optimizer::FunctionPass;
+
+// Retargets jumps and branches that land on an unconditional jump and removes jumps to the next instruction
+pub struct JumpThreading {}
+
+impl FunctionPass for JumpThreading {
+  fn name(&self) -> String {
+    "Jump Threading".to_string()
+  }
+
+  fn run(&self, function: &mut Function) -> usize {
+    let mut rewrites = 0;
+
+    for index in 0..function.body.len() {
+      match function.body[index].clone() {
+        Instruction::Jump(jump) => {
+          if let Some(target) = self.thread(function, &jump.target) {
+            function.body[index] = Instruction::Jump(Jump { target });
+            rewrites += 1;
+          }
+        }
+        Instruction::Branch(branch) => {
+          let true_target = self.thread(function, &branch.true_target);
+          let false_target = self.thread(function, &branch.false_target);
+          if true_target.is_some() || false_target.is_some() {
+            function.body[index] = Instruction::Branch(Branch {
+              true_target: true_target.unwrap_or(branch.true_target),
+              false_target: false_target.unwrap_or(branch.false_target),
+            });
+            rewrites += 1;
+          }
+        }
+        _ => {}
+      }
+    }
+
+    let mut index = 0;
+    while index < function.body.len() {
+      let jumps_to_next = match &function.body[index] {
+        Instruction::Jump(jump) => function.get_target_pointer(jump.target.clone()) == Ok(index + 1),
+        _ => false,
+      };
+      if jumps_to_next {
+        function.replace_range(index, index + 1, vec![]);
+        rewrites += 1;
+      } else {
+        index += 1;
+      }
+    }
+
+    rewrites
+  }
+}
+
+impl JumpThreading {
+  // follows a chain of unconditional jumps and returns the final target if it is different from the original one
+  fn thread(&self, function: &Function, target: &Target) -> Option<Target> {
+    let mut visited = Vec::new();
+    let mut current = target.clone();
+    loop {
+      let index = function.get_target_pointer(current.clone()).ok()?;
+      if visited.contains(&index) {
+        // this is an infinite loop of jumps so keep the original target
+        return None;
+      }
+      visited.push(index);
+      match function.body.get(index) {
+        Some(Instruction::Jump(jump)) => current = jump.target.clone(),
+        _ => break,
+      }
+    }
+    if visited.len() > 1 {
+      Some(current)
+    } else {
+      None
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::hydro::testing::{function_with_body, listing};
+
+  #[test]
+  fn threads_jumps_through_jumps() {
+    let mut function = function_with_body("f bool returns s32", &["branch first second", "label first", "jump second", "label second", "jump third", "label third", "push s32 1", "return"]);
+    assert_eq!(JumpThreading {}.run(&mut function), 3);
+    // the jump that no longer has any way in is left for dead code elimination
+    assert_eq!(listing(&function), vec!["branch third third", "jump third", "push Signed32 1", "return"]);
+    assert_eq!(function.jump_labels["third"], 2);
+  }
+
+  #[test]
+  fn keeps_loops_of_jumps() {
+    let mut function = function_with_body("f", &["label spin", "jump again", "label again", "jump spin"]);
+    JumpThreading {}.run(&mut function);
+    assert!(function.body.iter().all(|instruction| matches!(instruction, Instruction::Jump(_))));
+  }
+}
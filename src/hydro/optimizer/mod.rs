@@ -0,0 +1,168 @@
+use crate::hydro::compilationunit::CompilationUnit;
+use crate::hydro::function::Function;
+use crate::hydro::optimizer::branchfolding::BranchFolding;
+use crate::hydro::optimizer::constantfolding::ConstantFolding;
+use crate::hydro::optimizer::deadcodeelimination::DeadCodeElimination;
+use crate::hydro::optimizer::deadfunctionelimination::DeadFunctionElimination;
+use crate::hydro::optimizer::inlining::Inlining;
+use crate::hydro::optimizer::jumpthreading::JumpThreading;
+use crate::hydro::optimizer::purecallevaluation::PureCallEvaluation;
+use crate::hydro::optimizer::redundantstackelimination::RedundantStackElimination;
+use std::collections::HashMap;
+
+pub mod branchfolding;
+pub mod constantfolding;
+pub mod deadcodeelimination;
+pub mod deadfunctionelimination;
+pub mod inlining;
+pub mod jumpthreading;
+pub mod purecallevaluation;
+pub mod redundantstackelimination;
+
+pub trait FunctionPass {
+  fn name(&self) -> String;
+  // returns the number of rewrites the pass made to the function
+  fn run(&self, function: &mut Function) -> usize;
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PassStatistics {
+  pub runs: usize,
+  pub rewrites: usize,
+  pub instructions_removed: usize,
+}
+
+pub struct OptimizationReport {
+  pub opt_level: u8,
+  pub instructions_before: usize,
+  pub instructions_after: usize,
+  pub pass_order: Vec<String>,
+  pub pass_statistics: HashMap<String, PassStatistics>,
+  pub inlined: Vec<String>,
+  pub evaluated_calls: Vec<String>,
+  pub removed_functions: Vec<String>,
+  pub removed_layouts: Vec<String>,
+  pub removed_modules: Vec<String>,
+}
+
+impl OptimizationReport {
+  pub fn new(opt_level: u8) -> Self {
+    Self {
+      opt_level,
+      instructions_before: 0,
+      instructions_after: 0,
+      pass_order: Vec::new(),
+      pass_statistics: HashMap::new(),
+      inlined: Vec::new(),
+      evaluated_calls: Vec::new(),
+      removed_functions: Vec::new(),
+      removed_layouts: Vec::new(),
+      removed_modules: Vec::new(),
+    }
+  }
+
+  pub fn record(&mut self, pass_name: String, rewrites: usize, instructions_removed: isize) {
+    if !self.pass_statistics.contains_key(pass_name.as_str()) {
+      self.pass_order.push(pass_name.clone());
+    }
+    let statistics = self.pass_statistics.entry(pass_name).or_default();
+    statistics.runs += 1;
+    statistics.rewrites += rewrites;
+    statistics.instructions_removed = (statistics.instructions_removed as isize + instructions_removed).max(0) as usize;
+  }
+
+  pub fn print(&self) {
+    println!("Optimization Report (opt-level {})", self.opt_level);
+    println!("  Instructions: {} -> {}", self.instructions_before, self.instructions_after);
+    for pass_name in &self.pass_order {
+      let statistics = &self.pass_statistics[pass_name];
+      println!("  Pass: {}", pass_name);
+      println!("    Runs: {}", statistics.runs);
+      println!("    Rewrites: {}", statistics.rewrites);
+      println!("    Instructions Removed: {}", statistics.instructions_removed);
+    }
+    OptimizationReport::print_list("Inlined", &self.inlined);
+    OptimizationReport::print_list("Evaluated Pure Calls", &self.evaluated_calls);
+    OptimizationReport::print_list("Removed Functions", &self.removed_functions);
+    OptimizationReport::print_list("Removed Layouts", &self.removed_layouts);
+    OptimizationReport::print_list("Removed Modules", &self.removed_modules);
+  }
+
+  fn print_list(title: &str, entries: &Vec<String>) {
+    println!("  {}: {}", title, entries.len());
+    for entry in entries {
+      println!("    {}", entry);
+    }
+  }
+}
+
+pub struct Optimizer {
+  opt_level: u8,
+  passes: Vec<Box<dyn FunctionPass>>,
+}
+
+impl Optimizer {
+  pub fn new(opt_level: u8) -> Self {
+    let mut passes: Vec<Box<dyn FunctionPass>> = Vec::new();
+    if opt_level >= 1 {
+      passes.push(Box::new(RedundantStackElimination {}));
+      passes.push(Box::new(DeadCodeElimination {}));
+    }
+    if opt_level >= 2 {
+      passes.insert(0, Box::new(ConstantFolding {}));
+      passes.insert(1, Box::new(BranchFolding {}));
+      passes.push(Box::new(JumpThreading {}));
+      // jump threading can leave behind jumps that nothing reaches anymore
+      passes.push(Box::new(DeadCodeElimination {}));
+    }
+    Self { opt_level, passes }
+  }
+
+  // opt-level 3 keeps rerunning the pipeline until it stops finding things to rewrite
+  fn max_iterations(&self) -> usize {
+    match self.opt_level {
+      0 => 0,
+      1 | 2 => 1,
+      _ => 16,
+    }
+  }
+
+  pub fn optimize(&self, compilation_unit: &mut CompilationUnit) -> OptimizationReport {
+    let mut report = OptimizationReport::new(self.opt_level);
+    report.instructions_before = compilation_unit.instruction_count();
+
+    if self.opt_level >= 2 {
+      Inlining { max_callee_size: 16, max_rounds: 4 }.run(compilation_unit, &mut report);
+      PureCallEvaluation {}.run(compilation_unit, &mut report);
+    }
+
+    for module_name in compilation_unit.get_module_names() {
+      let module = compilation_unit.get_module_mut(module_name.as_str()).unwrap();
+      for function in module.functions.values_mut() {
+        self.optimize_function(function, &mut report);
+      }
+    }
+
+    if self.opt_level >= 1 {
+      DeadFunctionElimination {}.run(compilation_unit, &mut report);
+    }
+
+    report.instructions_after = compilation_unit.instruction_count();
+    report
+  }
+
+  pub fn optimize_function(&self, function: &mut Function, report: &mut OptimizationReport) {
+    for _ in 0..self.max_iterations() {
+      let mut total_rewrites = 0;
+      for pass in &self.passes {
+        let before = function.body.len() as isize;
+        let rewrites = pass.run(function);
+        report.record(pass.name(), rewrites, before - function.body.len() as isize);
+        total_rewrites += rewrites;
+      }
+      if total_rewrites == 0 {
+        break;
+      }
+    }
+  }
+}
@@ -0,0 +1,120 @@
+use crate::hydro::analyzer::effects::EffectAnalysis;
+use crate::hydro::compilationunit::CompilationUnit;
+use crate::hydro::function::Function;
+use crate::hydro::instruction::{Instruction, Push};
+use crate::hydro::optimizer::OptimizationReport;
+use crate::hydro::value::Value;
+use std::panic::AssertUnwindSafe;
+
+// Replaces `push args...` + `push funcp` + `call` with the callee's result when the callee is pure, can't loop forever, and
+// every argument is a constant. Calls that throw are left alone so the exception still happens at runtime
+pub struct PureCallEvaluation {}
+
+impl PureCallEvaluation {
+  pub fn run(&self, compilation_unit: &mut CompilationUnit, report: &mut OptimizationReport) {
+    let effects = EffectAnalysis::run(compilation_unit);
+    // calls are evaluated against the program as it was before any of them were rewritten
+    let mut snapshot = CompilationUnit::new();
+    snapshot.merge(compilation_unit);
+
+    for module_name in compilation_unit.get_module_names() {
+      let module = compilation_unit.get_module_mut(module_name.as_str()).unwrap();
+      let mut function_names = module.functions.keys().cloned().collect::<Vec<String>>();
+      function_names.sort();
+      for function_name in function_names {
+        let function = module.functions.get_mut(function_name.as_str()).unwrap();
+        let mut index = 0;
+        while index < function.body.len() {
+          match self.evaluate_at(&snapshot, &effects, module_name.as_str(), function, index) {
+            Some((start, callee, result)) => {
+              report.evaluated_calls.push(format!("{} in {}.{} at pc {}", callee, module_name, function_name, start));
+              let replacement = result.map(|value| Instruction::PushValue(Push { value })).into_iter().collect::<Vec<Instruction>>();
+              let added = replacement.len();
+              function.replace_range(start, index + 1, replacement);
+              index = start + added;
+            }
+            None => index += 1,
+          }
+        }
+      }
+    }
+  }
+
+  // the start of the call sequence ending at index, the callee's name, and what the call leaves on the stack
+  fn evaluate_at(&self, compilation_unit: &CompilationUnit, effects: &EffectAnalysis, module_name: &str, function: &Function, index: usize) -> Option<(usize, String, Option<Value>)> {
+    if !matches!(function.body[index], Instruction::Call(_)) || index == 0 {
+      return None;
+    }
+    let pointer = match &function.body[index - 1] {
+      Instruction::PushValue(Push { value: Value::FunctionPointer(pointer) }) => pointer,
+      _ => return None,
+    };
+    let callee_module = pointer.module.clone().unwrap_or(module_name.to_string());
+    let callee = compilation_unit.get_function(callee_module.as_str(), pointer.function.as_str())?;
+    if !effects.get(callee_module.as_str(), callee.name.as_str()).is_evaluable() || callee.parameters.len() > index - 1 {
+      return None;
+    }
+
+    // only the first instruction of the sequence can be jumped to since the rest is about to become one push
+    let start = index - 1 - callee.parameters.len();
+    if (start + 1..=index).any(|target| function.is_jump_target(target)) {
+      return None;
+    }
+    let mut arguments = Vec::new();
+    for instruction in &function.body[start..index - 1] {
+      match instruction {
+        Instruction::PushValue(push) if is_portable(&push.value) => arguments.push(push.value.clone()),
+        _ => return None,
+      }
+    }
+
+    // an operator the vm doesn't define for its operand types panics instead of throwing. The call may never run so
+    // that panic is left for runtime just like an exception
+    let hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(|_| {}));
+    let result = std::panic::catch_unwind(AssertUnwindSafe(|| compilation_unit.execute(callee_module.clone(), callee.name.clone(), arguments, None)));
+    std::panic::set_hook(hook);
+    match result.ok()? {
+      Ok(Some(value)) if is_portable(&value) => Some((start, format!("{}.{}", callee_module, callee.name), Some(value))),
+      Ok(None) => Some((start, format!("{}.{}", callee_module, callee.name), None)),
+      _ => None,
+    }
+  }
+}
+
+// references and module relative function pointers mean something different once they move between functions
+fn is_portable(value: &Value) -> bool {
+  match value {
+    Value::Reference(_) => false,
+    Value::FunctionPointer(pointer) => pointer.module.is_some(),
+    Value::Array(array) => array.values.iter().all(is_portable),
+    Value::Layout(layout) => layout.values.values().all(is_portable),
+    _ => true,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::hydro::testing::{compile, debug_main, headless_debug_context, listing};
+
+  #[test]
+  fn folds_calls_that_finish_without_throwing() {
+    let source = "module main\n\nfunction double s32 returns s32 body\n    push s32 2\n    multiply\n    return\n\nfunction inverse s32 returns s32 body\n    push s32 100\n    swap\n    divide\n    return\n\nfunction forever s32 returns s32 body\n    push funcp main forever\n    call\n    return\n\nfunction bad s32 returns s32 body\n    push bool true\n    add\n    return\n\nfunction unused returns s32 body\n    push s32 7\n    push funcp main bad\n    call\n    return\n\nmain body\n    push s32 21\n    push funcp main double\n    call\n    push s32 0\n    push funcp main inverse\n    call\n    push s32 3\n    push funcp main forever\n    call\n    return\n";
+    let mut compilation_unit = compile(source);
+    let mut report = OptimizationReport::new(3);
+    PureCallEvaluation {}.run(&mut compilation_unit, &mut report);
+
+    assert_eq!(report.evaluated_calls, vec!["main.double in main.main at pc 0"]);
+    // bad panics in the vm since s32 + bool isn't defined, but nothing calls it so the program still runs
+    assert_eq!(listing(compilation_unit.get_function("main", "unused").unwrap()), vec!["push Signed32 7", "push funcp main bad", "call", "return"]);
+    // inverse throws on 0 so the division is left for runtime and forever is never run early
+    let main = compilation_unit.get_function("main", "main").unwrap();
+    assert_eq!(
+      listing(main),
+      vec!["push Signed32 42", "push Signed32 0", "push funcp main inverse", "call", "push Signed32 3", "push funcp main forever", "call", "return"]
+    );
+    let exception = debug_main(&compilation_unit, &mut headless_debug_context()).unwrap_err();
+    assert_eq!(exception.context.current_function, "inverse");
+  }
+}
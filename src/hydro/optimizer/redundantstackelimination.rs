@@ -0,0 +1,81 @@
+use crate::hydro::analyzer::dataflow::solve;
+use crate::hydro::analyzer::dataflow::stackdepth::{MinimumStackDepth, StackDepth};
+use crate::hydro::controlflowgraph::ControlFlowGraph;
+use crate::hydro::function::Function;
+use crate::hydro::instruction::{Duplicate, Instruction};
+use crate::hydro::optimizer::FunctionPass;
+
+// Removes pairs of stack instructions that cancel each other out like a duplicate followed by a pop. A duplicate or swap
+// throws when the stack is too short so those pairs are only removed where the stack is known to be deep enough
+pub struct RedundantStackElimination {}
+
+impl FunctionPass for RedundantStackElimination {
+  fn name(&self) -> String {
+    "Redundant Stack Elimination".to_string()
+  }
+
+  fn run(&self, function: &mut Function) -> usize {
+    let stack_depths = solve(&StackDepth { parameters: function.parameters.len() }, function, &ControlFlowGraph::build(function));
+    // every pair leaves the stack as it found it so the depths of the remaining instructions stay the same
+    let mut depths = (0..function.body.len()).map(|index| stack_depths.before(index)).collect::<Vec<MinimumStackDepth>>();
+
+    let mut rewrites = 0;
+    let mut index = 0;
+    while index + 1 < function.body.len() {
+      let needed_depth = match (&function.body[index], &function.body[index + 1]) {
+        (Instruction::Duplicate(Duplicate { offset: 0 }), Instruction::PopValue(_)) => Some(1),
+        (Instruction::Swap(_), Instruction::Swap(_)) => Some(2),
+        (Instruction::PushValue(_), Instruction::PopValue(_)) => Some(0),
+        _ => None,
+      };
+      let redundant = match needed_depth {
+        Some(needed_depth) => !function.is_jump_target(index + 1) && depths[index].at_least(needed_depth),
+        None => false,
+      };
+      if redundant {
+        function.replace_range(index, index + 2, vec![]);
+        depths.drain(index..index + 2);
+        rewrites += 1;
+        // removing the pair can expose a new pair with the instruction before it
+        index = index.saturating_sub(1);
+      } else {
+        index += 1;
+      }
+    }
+    rewrites
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::hydro::testing::{function_with_body, listing};
+
+  #[test]
+  fn removes_pairs_that_cancel_out() {
+    let mut function = function_with_body("f s32 s32 returns s32", &["duplicate", "pop", "swap", "swap", "push s32 1", "duplicate", "pop", "pop", "return"]);
+    assert_eq!(RedundantStackElimination {}.run(&mut function), 4);
+    assert_eq!(listing(&function), vec!["return"]);
+  }
+
+  #[test]
+  fn keeps_pairs_that_would_underflow() {
+    let mut function = function_with_body("f returns s32", &["duplicate", "pop", "push s32 1", "return"]);
+    assert_eq!(RedundantStackElimination {}.run(&mut function), 0);
+    assert_eq!(listing(&function), vec!["duplicate", "pop", "push Signed32 1", "return"]);
+
+    let mut function = function_with_body("f s32 returns s32", &["swap", "swap", "return"]);
+    assert_eq!(RedundantStackElimination {}.run(&mut function), 0);
+
+    // one path into the loop has an empty stack
+    let mut function = function_with_body("f returns s32", &["push bool true", "branch loop end", "label loop", "duplicate", "pop", "push s32 1", "jump loop", "label end", "push s32 1", "return"]);
+    assert_eq!(RedundantStackElimination {}.run(&mut function), 0);
+  }
+
+  #[test]
+  fn keeps_pairs_split_by_a_label() {
+    let mut function = function_with_body("f s32 returns s32", &["duplicate", "label again", "pop", "jump again"]);
+    assert_eq!(RedundantStackElimination {}.run(&mut function), 0);
+    assert_eq!(function.jump_labels["again"], 1);
+  }
+}
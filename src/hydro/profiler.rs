@@ -0,0 +1,221 @@
+use crate::hydro::compilationunit::CompilationUnit;
+use crate::hydro::controlflowgraph::ControlFlowGraph;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+// Counts every instruction a debug session runs, per module, function and program counter. The time between two
+// instructions is charged to the first of them, so a call instruction only gets the time it took to enter the callee.
+// The clock is stopped while the debugger is paused
+
+#[derive(Debug, Clone, Default)]
+pub struct FunctionProfile {
+  // both are indexed by program counter and grow as instructions are hit
+  pub counts: Vec<u64>,
+  pub times: Vec<Duration>,
+}
+
+impl FunctionProfile {
+  pub fn count(&self) -> u64 {
+    self.counts.iter().sum()
+  }
+
+  pub fn time(&self) -> Duration {
+    self.times.iter().sum()
+  }
+
+  pub fn count_at(&self, program_counter: usize) -> u64 {
+    self.counts.get(program_counter).cloned().unwrap_or(0)
+  }
+
+  pub fn time_at(&self, program_counter: usize) -> Duration {
+    self.times.get(program_counter).cloned().unwrap_or(Duration::ZERO)
+  }
+
+  fn grow(&mut self, program_counter: usize) {
+    if program_counter >= self.counts.len() {
+      self.counts.resize(program_counter + 1, 0);
+      self.times.resize(program_counter + 1, Duration::ZERO);
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct InstructionProfile {
+  functions: HashMap<String, HashMap<String, FunctionProfile>>,
+  // the instruction the running clock is charged to
+  last: Option<(String, String, usize)>,
+  clock: Option<Instant>,
+}
+
+impl InstructionProfile {
+  pub fn new() -> Self {
+    Self { functions: HashMap::new(), last: None, clock: None }
+  }
+
+  // called right before the instruction at program_counter runs
+  pub fn enter(&mut self, module_name: &str, function_name: &str, program_counter: usize) {
+    let now = Instant::now();
+    self.charge(now);
+    self.clock = Some(now);
+
+    let profile = self.function_mut(module_name, function_name);
+    profile.grow(program_counter);
+    profile.counts[program_counter] += 1;
+
+    match &mut self.last {
+      Some((last_module, last_function, last_program_counter)) if last_module == module_name && last_function == function_name => *last_program_counter = program_counter,
+      _ => self.last = Some((module_name.to_string(), function_name.to_string(), program_counter)),
+    }
+  }
+
+  pub fn pause(&mut self) {
+    self.charge(Instant::now());
+    self.clock = None;
+  }
+
+  pub fn resume(&mut self) {
+    if self.last.is_some() {
+      self.clock = Some(Instant::now());
+    }
+  }
+
+  pub fn get(&self, module_name: &str, function_name: &str) -> Option<&FunctionProfile> {
+    self.functions.get(module_name).and_then(|functions| functions.get(function_name))
+  }
+
+  // (module, function, profile) sorted by module and function name
+  pub fn functions(&self) -> Vec<(&String, &String, &FunctionProfile)> {
+    let mut functions = self.functions.iter().flat_map(|(module_name, functions)| functions.iter().map(move |(function_name, profile)| (module_name, function_name, profile))).collect::<Vec<(&String, &String, &FunctionProfile)>>();
+    functions.sort_by(|a, b| (a.0, a.1).cmp(&(b.0, b.1)));
+    functions
+  }
+
+  pub fn total_count(&self) -> u64 {
+    self.functions().iter().map(|(_, _, profile)| profile.count()).sum()
+  }
+
+  pub fn total_time(&self) -> Duration {
+    self.functions().iter().map(|(_, _, profile)| profile.time()).sum()
+  }
+
+  // the function that ran the most instructions
+  pub fn hottest_function(&self) -> Option<(String, String)> {
+    self.functions().into_iter().filter(|(_, _, profile)| profile.count() > 0).max_by_key(|(_, _, profile)| profile.count()).map(|(module_name, function_name, _)| (module_name.clone(), function_name.clone()))
+  }
+
+  fn function_mut(&mut self, module_name: &str, function_name: &str) -> &mut FunctionProfile {
+    if !self.functions.contains_key(module_name) {
+      self.functions.insert(module_name.to_string(), HashMap::new());
+    }
+    let functions = self.functions.get_mut(module_name).unwrap();
+    if !functions.contains_key(function_name) {
+      functions.insert(function_name.to_string(), FunctionProfile::default());
+    }
+    functions.get_mut(function_name).unwrap()
+  }
+
+  fn charge(&mut self, now: Instant) {
+    if let (Some(clock), Some((module_name, function_name, program_counter))) = (self.clock, self.last.clone()) {
+      let profile = self.function_mut(module_name.as_str(), function_name.as_str());
+      profile.grow(program_counter);
+      profile.times[program_counter] += now.duration_since(clock);
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HotSpotKind {
+  Block,
+  Loop,
+}
+
+// a basic block, or a loop with every block between its head and its last back edge
+#[derive(Debug, Clone)]
+pub struct HotSpot {
+  pub kind: HotSpotKind,
+  pub module_name: String,
+  pub function_name: String,
+  pub name: String,
+  // the instructions in [start, end)
+  pub start: usize,
+  pub end: usize,
+  pub count: u64,
+  pub time: Duration,
+}
+
+impl HotSpot {
+  fn build(kind: HotSpotKind, module_name: &str, function_name: &str, name: String, start: usize, end: usize, profile: &FunctionProfile) -> Self {
+    Self {
+      kind,
+      module_name: module_name.to_string(),
+      function_name: function_name.to_string(),
+      name,
+      start,
+      end,
+      count: (start..end).map(|program_counter| profile.count_at(program_counter)).sum(),
+      time: (start..end).map(|program_counter| profile.time_at(program_counter)).sum(),
+    }
+  }
+}
+
+// every block and loop of every profiled function, hottest first
+pub fn hot_spots(compilation_unit: &CompilationUnit, profile: &InstructionProfile) -> Vec<HotSpot> {
+  let mut spots = Vec::new();
+  for (module_name, function_name, function_profile) in profile.functions() {
+    let function = match compilation_unit.get_function(module_name.as_str(), function_name.as_str()) {
+      Some(function) => function,
+      None => continue,
+    };
+    let graph = ControlFlowGraph::build(function);
+    for block in &graph.blocks {
+      spots.push(HotSpot::build(HotSpotKind::Block, module_name, function_name, graph.block_name(block.id), block.start, block.end, function_profile));
+    }
+    for head in graph.loop_heads() {
+      // the loop body runs up to the furthest block that jumps back to the head
+      let tail = graph.blocks.iter().filter(|block| block.id >= head && block.successors.contains(&head)).map(|block| block.id).max().unwrap_or(head);
+      spots.push(HotSpot::build(HotSpotKind::Loop, module_name, function_name, graph.block_name(head), graph.blocks[head].start, graph.blocks[tail].end, function_profile));
+    }
+  }
+  spots.retain(|spot| spot.count > 0);
+  spots.sort_by(|a, b| b.count.cmp(&a.count).then(b.time.cmp(&a.time)));
+  spots
+}
+
+pub fn share(part: f64, whole: f64) -> f64 {
+  if whole == 0.0 {
+    0.0
+  } else {
+    part / whole * 100.0
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::thread::sleep;
+
+  #[test]
+  fn counts_instructions_and_charges_time_to_the_previous_one() {
+    let mut profile = InstructionProfile::new();
+    for _ in 0..3 {
+      profile.enter("main", "main", 0);
+      profile.enter("main", "main", 1);
+    }
+    profile.enter("main", "helper", 0);
+    sleep(Duration::from_millis(5));
+    profile.pause();
+    // paused time is never charged
+    sleep(Duration::from_millis(50));
+    profile.resume();
+    profile.enter("main", "main", 2);
+
+    let main = profile.get("main", "main").unwrap();
+    assert_eq!(main.counts, vec![3, 3, 1]);
+    assert_eq!(profile.get("main", "helper").unwrap().counts, vec![1]);
+    assert_eq!(profile.total_count(), 8);
+    let helper_time = profile.get("main", "helper").unwrap().time_at(0);
+    assert!(helper_time >= Duration::from_millis(5));
+    assert!(helper_time < Duration::from_millis(50));
+    assert_eq!(profile.hottest_function(), Some(("main".to_string(), "main".to_string())));
+  }
+}
@@ -0,0 +1,394 @@
+use crate::hydro::executioncontext::ExecutionContext;
+use crate::hydro::value::Value;
+use std::collections::{HashMap, VecDeque};
+use std::mem::size_of;
+
+// Records every instruction a debug session runs so the debugger can go back in time. The recording is split into
+// segments that each start with a snapshot of the whole frame chain followed by one delta per instruction. Any step is
+// rebuilt by replaying the deltas of its segment on top of the snapshot. Once the recording is over its memory budget
+// the oldest segments are thrown away
+
+const SNAPSHOT_INTERVAL: usize = 256;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameState {
+  pub module_name: String,
+  pub function_name: String,
+  pub program_counter: usize,
+  pub stack: Vec<Value>,
+  pub variables: HashMap<String, Value>,
+}
+
+impl FrameState {
+  fn empty(module_name: String, function_name: String) -> Self {
+    Self { module_name, function_name, program_counter: 0, stack: Vec::new(), variables: HashMap::new() }
+  }
+
+  fn from_context(context: &ExecutionContext) -> Self {
+    Self {
+      module_name: context.current_module.clone(),
+      function_name: context.current_function.clone(),
+      program_counter: context.program_counter,
+      stack: context.stack.clone(),
+      variables: context.variables.clone(),
+    }
+  }
+}
+
+// how the frame at depth changed since that depth was last recorded
+#[derive(Debug, Clone)]
+struct StepDelta {
+  depth: usize,
+  // set when this step is the first instruction of a new call
+  entered: Option<(String, String)>,
+  program_counter: usize,
+  stack_kept: usize,
+  stack_pushed: Vec<Value>,
+  variables_set: Vec<(String, Value)>,
+  variables_removed: Vec<String>,
+}
+
+impl StepDelta {
+  fn apply(&self, frames: &mut Vec<FrameState>) {
+    match &self.entered {
+      Some((module_name, function_name)) => {
+        frames.truncate(self.depth - 1);
+        frames.push(FrameState::empty(module_name.clone(), function_name.clone()));
+      }
+      None => frames.truncate(self.depth),
+    }
+    let frame = frames.last_mut().unwrap();
+    frame.program_counter = self.program_counter;
+    frame.stack.truncate(self.stack_kept);
+    frame.stack.extend(self.stack_pushed.iter().cloned());
+    for (name, value) in &self.variables_set {
+      frame.variables.insert(name.clone(), value.clone());
+    }
+    for name in &self.variables_removed {
+      frame.variables.remove(name);
+    }
+  }
+
+  fn size(&self) -> usize {
+    size_of::<StepDelta>()
+      + self.entered.as_ref().map(|(module_name, function_name)| module_name.len() + function_name.len()).unwrap_or(0)
+      + self.stack_pushed.iter().map(value_size).sum::<usize>()
+      + self.variables_set.iter().map(|(name, value)| name.len() + value_size(value)).sum::<usize>()
+      + self.variables_removed.iter().map(|name| size_of::<String>() + name.len()).sum::<usize>()
+  }
+}
+
+struct Segment {
+  first_step: u64,
+  // the frames at first_step with the innermost function last
+  snapshot: Vec<FrameState>,
+  // deltas[i] turns step first_step + i into step first_step + i + 1
+  deltas: Vec<StepDelta>,
+  size: usize,
+}
+
+// a rough count of the bytes a value holds on to
+pub fn value_size(value: &Value) -> usize {
+  size_of::<Value>()
+    + match value {
+      Value::Array(array) => array.values.iter().map(value_size).sum::<usize>(),
+      Value::Layout(layout) => layout.values.iter().map(|(name, value)| name.len() + value_size(value)).sum::<usize>() + layout.module_name.len() + layout.layout_name.len(),
+      Value::FunctionPointer(pointer) => pointer.function.len() + pointer.module.as_ref().map(|module| module.len()).unwrap_or(0),
+      _ => 0,
+    }
+}
+
+fn frame_size(frame: &FrameState) -> usize {
+  size_of::<FrameState>() + frame.module_name.len() + frame.function_name.len() + frame.stack.iter().map(value_size).sum::<usize>() + frame.variables.iter().map(|(name, value)| name.len() + value_size(value)).sum::<usize>()
+}
+
+pub struct ExecutionRecording {
+  // in bytes
+  pub budget: usize,
+  segments: VecDeque<Segment>,
+  size: usize,
+  // the last recorded state of every frame on the call stack
+  frames: Vec<FrameState>,
+  steps: u64,
+  last_depth: usize,
+  // the step the debugger is looking at while going through the past
+  pub cursor: Option<u64>,
+}
+
+impl ExecutionRecording {
+  pub fn new(budget: usize) -> Self {
+    Self {
+      budget,
+      segments: VecDeque::new(),
+      size: 0,
+      frames: Vec::new(),
+      steps: 0,
+      last_depth: 0,
+      cursor: None,
+    }
+  }
+
+  // called before every instruction runs
+  pub fn record(&mut self, context: &ExecutionContext) {
+    let depth = context.depth();
+    let entered = depth > self.last_depth;
+    self.last_depth = depth;
+
+    // the recording was started part way through the program so there is nothing to take a delta against
+    let resynchronize = (entered && self.frames.len() != depth - 1) || (!entered && self.frames.len() < depth);
+    let delta = if resynchronize {
+      let mut frames = vec![FrameState::from_context(context)];
+      let mut parent = context.parent_execution_context.as_deref();
+      while let Some(parent_context) = parent {
+        frames.insert(0, FrameState::from_context(parent_context));
+        parent = parent_context.parent_execution_context.as_deref();
+      }
+      self.frames = frames;
+      None
+    } else {
+      let delta = self.delta(context, depth, entered);
+      delta.apply(&mut self.frames);
+      Some(delta)
+    };
+
+    let start_segment = match (self.segments.back(), &delta) {
+      (Some(segment), Some(_)) => segment.deltas.len() >= SNAPSHOT_INTERVAL || segment.size > self.budget / 8,
+      _ => true,
+    };
+    if start_segment {
+      let size = self.frames.iter().map(frame_size).sum::<usize>();
+      self.size += size;
+      self.segments.push_back(Segment { first_step: self.steps, snapshot: self.frames.clone(), deltas: Vec::new(), size });
+    } else if let Some(delta) = delta {
+      let size = delta.size();
+      self.size += size;
+      let segment = self.segments.back_mut().unwrap();
+      segment.size += size;
+      segment.deltas.push(delta);
+    }
+    self.steps += 1;
+
+    while self.size > self.budget && self.segments.len() > 1 {
+      let segment = self.segments.pop_front().unwrap();
+      self.size -= segment.size;
+    }
+  }
+
+  fn delta(&self, context: &ExecutionContext, depth: usize, entered: bool) -> StepDelta {
+    let empty = FrameState::empty(String::new(), String::new());
+    let previous = if entered { &empty } else { &self.frames[depth - 1] };
+
+    let stack_kept = previous.stack.iter().zip(&context.stack).take_while(|(previous_value, value)| previous_value == value).count();
+    let variables_set = context
+      .variables
+      .iter()
+      .filter(|(name, value)| previous.variables.get(*name) != Some(*value))
+      .map(|(name, value)| (name.clone(), value.clone()))
+      .collect::<Vec<(String, Value)>>();
+    let variables_removed = previous.variables.keys().filter(|name| !context.variables.contains_key(*name)).cloned().collect::<Vec<String>>();
+
+    StepDelta {
+      depth,
+      entered: if entered { Some((context.current_module.clone(), context.current_function.clone())) } else { None },
+      program_counter: context.program_counter,
+      stack_kept,
+      stack_pushed: context.stack[stack_kept..].to_vec(),
+      variables_set,
+      variables_removed,
+    }
+  }
+
+  pub fn size(&self) -> usize {
+    self.size
+  }
+
+  // the oldest step that is still recorded
+  pub fn first_step(&self) -> Option<u64> {
+    self.segments.front().map(|segment| segment.first_step)
+  }
+
+  // the step that is about to run in the live program
+  pub fn present(&self) -> Option<u64> {
+    self.steps.checked_sub(1).filter(|_| !self.segments.is_empty())
+  }
+
+  // the step being looked at, either the cursor or the present
+  pub fn current(&self) -> Option<u64> {
+    self.cursor.or(self.present())
+  }
+
+  fn segment_index(&self, step: u64) -> Option<usize> {
+    if step >= self.steps {
+      return None;
+    }
+    let index = self.segments.partition_point(|segment| segment.first_step <= step);
+    index.checked_sub(1)
+  }
+
+  pub fn frames_at(&self, step: u64) -> Option<Vec<FrameState>> {
+    let segment = &self.segments[self.segment_index(step)?];
+    let mut frames = segment.snapshot.clone();
+    for delta in &segment.deltas[..(step - segment.first_step) as usize] {
+      delta.apply(&mut frames);
+    }
+    Some(frames)
+  }
+
+  pub fn context_at(&self, step: u64) -> Option<ExecutionContext> {
+    ExecutionRecording::to_context(&self.frames_at(step)?)
+  }
+
+  // the frames as execution contexts where the innermost one is the last frame
+  pub fn to_context(frames: &[FrameState]) -> Option<ExecutionContext> {
+    let mut context: Option<ExecutionContext> = None;
+    for frame in frames {
+      context = Some(ExecutionContext {
+        parent_execution_context: context.map(Box::new),
+        stack: frame.stack.clone(),
+        program_counter: frame.program_counter,
+        variables: frame.variables.clone(),
+        return_value: None,
+        current_function: frame.function_name.clone(),
+        current_module: frame.module_name.clone(),
+      });
+    }
+    context
+  }
+
+  // Walks backwards from the step before 'from' and returns the first step the predicate accepts. Each segment is
+  // rebuilt once so this is linear in the number of steps searched
+  pub fn find_back(&self, from: u64, mut predicate: impl FnMut(u64, &[FrameState]) -> bool) -> Option<u64> {
+    let last = from.checked_sub(1)?;
+    let mut segment_index = self.segment_index(last)?;
+    loop {
+      let segment = &self.segments[segment_index];
+      let mut states = vec![segment.snapshot.clone()];
+      let end = ((last - segment.first_step) as usize).min(segment.deltas.len());
+      for delta in &segment.deltas[..end] {
+        let mut frames = states.last().unwrap().clone();
+        delta.apply(&mut frames);
+        states.push(frames);
+      }
+      for (offset, frames) in states.iter().enumerate().rev() {
+        let step = segment.first_step + offset as u64;
+        if step <= last && predicate(step, frames) {
+          return Some(step);
+        }
+      }
+      segment_index = segment_index.checked_sub(1)?;
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  struct Program {
+    frames: Vec<ExecutionContext>,
+  }
+
+  impl Program {
+    fn new() -> Self {
+      Self { frames: vec![Program::frame("main", 0)] }
+    }
+
+    fn frame(function_name: &str, program_counter: usize) -> ExecutionContext {
+      ExecutionContext {
+        parent_execution_context: None,
+        stack: Vec::new(),
+        program_counter,
+        variables: HashMap::new(),
+        return_value: None,
+        current_function: function_name.to_string(),
+        current_module: "main".to_string(),
+      }
+    }
+
+    fn context(&self) -> ExecutionContext {
+      let mut context = self.frames[0].clone();
+      for frame in &self.frames[1..] {
+        let mut frame = frame.clone();
+        frame.parent_execution_context = Some(Box::new(context));
+        context = frame;
+      }
+      context
+    }
+
+    // a made up program that pushes, stores and makes calls so every kind of delta shows up
+    fn run(&mut self, step: u64, recording: &mut ExecutionRecording, history: &mut Vec<Vec<FrameState>>) {
+      let depth = self.frames.len();
+      // callers stay on their call instruction like they do in the vm
+      if step % 7 != 3 || depth >= 4 {
+        self.frames[depth - 1].program_counter += 1;
+      }
+      match step % 7 {
+        3 if depth < 4 => self.frames.push(Program::frame("callee", 0)),
+        5 if depth > 1 => {
+          self.frames.pop();
+          self.frames[depth - 2].stack.push(Value::Boolean(true));
+        }
+        0 => self.frames[depth - 1].stack.push(Value::Unsigned32(step as u32)),
+        1 => {
+          self.frames[depth - 1].stack.pop();
+        }
+        2 => {
+          self.frames[depth - 1].variables.insert(format!("v{}", step % 3), Value::Unsigned64(step));
+        }
+        _ if self.frames[depth - 1].stack.len() > 8 => self.frames[depth - 1].stack.clear(),
+        _ => self.frames[depth - 1].stack.push(Value::string(format!("s{}", step))),
+      }
+      recording.record(&self.context());
+      history.push(self.frames.iter().map(FrameState::from_context).collect());
+    }
+  }
+
+  #[test]
+  fn every_step_is_rebuilt_exactly() {
+    let mut recording = ExecutionRecording::new(usize::MAX);
+    let mut program = Program::new();
+    let mut history = Vec::new();
+    for step in 0..1000 {
+      program.run(step, &mut recording, &mut history);
+    }
+    for (step, expected) in history.iter().enumerate() {
+      assert_eq!(recording.frames_at(step as u64).as_ref(), Some(expected), "step {}", step);
+    }
+    assert_eq!(recording.frames_at(1000), None);
+    assert_eq!(recording.present(), Some(999));
+  }
+
+  #[test]
+  fn the_budget_drops_the_oldest_steps() {
+    let mut recording = ExecutionRecording::new(64 * 1024);
+    let mut program = Program::new();
+    let mut history = Vec::new();
+    for step in 0..20000 {
+      program.run(step, &mut recording, &mut history);
+    }
+    assert!(recording.size() <= recording.budget);
+    let first_step = recording.first_step().unwrap();
+    assert!(first_step > 0);
+    assert_eq!(recording.frames_at(first_step - 1), None);
+    assert_eq!(recording.frames_at(first_step).as_ref(), Some(&history[first_step as usize]));
+    assert_eq!(recording.frames_at(19999).as_ref(), Some(&history[19999]));
+  }
+
+  #[test]
+  fn searching_back_visits_steps_in_reverse() {
+    let mut recording = ExecutionRecording::new(usize::MAX);
+    let mut program = Program::new();
+    let mut history = Vec::new();
+    for step in 0..600 {
+      program.run(step, &mut recording, &mut history);
+    }
+    let mut visited = Vec::new();
+    let found = recording.find_back(590, |step, frames| {
+      assert_eq!(frames, history[step as usize].as_slice());
+      visited.push(step);
+      frames.len() == 1 && step < 300
+    });
+    assert_eq!(visited.first(), Some(&589));
+    assert_eq!(found, history.iter().enumerate().take(300).rev().find(|(_, frames)| frames.len() == 1).map(|(step, _)| step as u64));
+  }
+}
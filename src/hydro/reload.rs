@@ -0,0 +1,234 @@
+use crate::hydro::compilationunit::CompilationUnit;
+use crate::hydro::executioncontext::ExecutionContext;
+use crate::hydro::function::Function;
+use crate::hydro::inspector::format_type;
+use crate::hydro::module::Module;
+use crate::hydro::value::Value;
+use std::collections::HashMap;
+
+// What changed between two versions of a program, so 'reload' can say what it swapped in and warn about values that
+// were built with a layout that no longer looks the same
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayoutChange {
+  pub layout_name: String,
+  // members that were added, removed or that now have a different type. The layout is gone when all are empty
+  pub added: Vec<String>,
+  pub removed: Vec<String>,
+  pub retyped: Vec<String>,
+}
+
+impl LayoutChange {
+  pub fn describe(&self) -> String {
+    if self.added.is_empty() && self.removed.is_empty() && self.retyped.is_empty() {
+      return format!("{} was removed", self.layout_name);
+    }
+    let mut parts = Vec::new();
+    if !self.added.is_empty() {
+      parts.push(format!("added {}", self.added.join(", ")));
+    }
+    if !self.removed.is_empty() {
+      parts.push(format!("removed {}", self.removed.join(", ")));
+    }
+    if !self.retyped.is_empty() {
+      parts.push(format!("changed {}", self.retyped.join(", ")));
+    }
+    format!("{} {}", self.layout_name, parts.join("; "))
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ModuleChange {
+  pub module_name: String,
+  pub added: bool,
+  pub removed: bool,
+  pub added_functions: Vec<String>,
+  pub removed_functions: Vec<String>,
+  pub changed_functions: Vec<String>,
+  pub layouts: Vec<LayoutChange>,
+}
+
+impl ModuleChange {
+  pub fn describe(&self) -> String {
+    if self.added {
+      return format!("{} was added", self.module_name);
+    }
+    if self.removed {
+      return format!("{} was removed", self.module_name);
+    }
+    let mut parts = Vec::new();
+    for (kind, functions) in [("added", &self.added_functions), ("removed", &self.removed_functions), ("changed", &self.changed_functions)] {
+      if !functions.is_empty() {
+        parts.push(format!("{} {}", kind, functions.join(", ")));
+      }
+    }
+    if !self.layouts.is_empty() {
+      parts.push(format!("layouts {}", self.layouts.iter().map(|layout| layout.layout_name.clone()).collect::<Vec<String>>().join(", ")));
+    }
+    if parts.is_empty() {
+      parts.push("usings or intrinsics changed".to_string());
+    }
+    format!("{}: {}", self.module_name, parts.join("; "))
+  }
+}
+
+// every module that is different in the new version, sorted by name
+pub fn compare(old: &CompilationUnit, new: &CompilationUnit) -> Vec<ModuleChange> {
+  let mut module_names = old.get_module_names();
+  module_names.extend(new.get_module_names());
+  module_names.sort();
+  module_names.dedup();
+
+  let mut changes = Vec::new();
+  for module_name in module_names {
+    let change = match (old.get_module(module_name.as_str()), new.get_module(module_name.as_str())) {
+      (Some(old_module), Some(new_module)) => compare_module(old_module, new_module),
+      (None, Some(_)) => Some(ModuleChange { module_name: module_name.clone(), added: true, ..Default::default() }),
+      (Some(_), None) => Some(ModuleChange { module_name: module_name.clone(), removed: true, ..Default::default() }),
+      (None, None) => None,
+    };
+    changes.extend(change);
+  }
+  changes
+}
+
+fn compare_module(old: &Module, new: &Module) -> Option<ModuleChange> {
+  let mut change = ModuleChange { module_name: new.name.clone(), ..Default::default() };
+  for (function_name, old_function) in sorted(&old.functions) {
+    match new.functions.get(function_name.as_str()) {
+      Some(new_function) if !same_function(old_function, new_function) => change.changed_functions.push(function_name),
+      Some(_) => {}
+      None => change.removed_functions.push(function_name),
+    }
+  }
+  for (function_name, _) in sorted(&new.functions) {
+    if !old.functions.contains_key(function_name.as_str()) {
+      change.added_functions.push(function_name);
+    }
+  }
+
+  for (layout_name, old_layout) in sorted(&old.layout_templates) {
+    let new_members = match new.layout_templates.get(layout_name.as_str()) {
+      Some(new_layout) => &new_layout.members,
+      None => {
+        change.layouts.push(LayoutChange { layout_name, added: Vec::new(), removed: Vec::new(), retyped: Vec::new() });
+        continue;
+      }
+    };
+    let mut layout_change = LayoutChange { layout_name, added: Vec::new(), removed: Vec::new(), retyped: Vec::new() };
+    for (member, old_value) in sorted(&old_layout.members) {
+      match new_members.get(member.as_str()) {
+        Some(new_value) if new_value.type_of() != old_value.type_of() => {
+          layout_change.retyped.push(format!("{} from {} to {}", member, format_type(&old_value.type_of()), format_type(&new_value.type_of())))
+        }
+        Some(_) => {}
+        None => layout_change.removed.push(member),
+      }
+    }
+    for (member, _) in sorted(new_members) {
+      if !old_layout.members.contains_key(member.as_str()) {
+        layout_change.added.push(member);
+      }
+    }
+    if !(layout_change.added.is_empty() && layout_change.removed.is_empty() && layout_change.retyped.is_empty()) {
+      change.layouts.push(layout_change);
+    }
+  }
+
+  let mut old_intrinsics = old.intrinsics.keys().collect::<Vec<&String>>();
+  let mut new_intrinsics = new.intrinsics.keys().collect::<Vec<&String>>();
+  old_intrinsics.sort();
+  new_intrinsics.sort();
+  let unchanged = change.added_functions.is_empty() && change.removed_functions.is_empty() && change.changed_functions.is_empty() && change.layouts.is_empty() && old.modules == new.modules && old_intrinsics == new_intrinsics;
+  if unchanged {
+    None
+  } else {
+    Some(change)
+  }
+}
+
+// instructions are compared by how they are written since they can't be compared directly
+fn same_function(old: &Function, new: &Function) -> bool {
+  old.parameters == new.parameters
+    && old.return_type == new.return_type
+    && old.jump_labels == new.jump_labels
+    && old.body.len() == new.body.len()
+    && old.body.iter().zip(&new.body).all(|(old_instruction, new_instruction)| old_instruction.to_string() == new_instruction.to_string())
+}
+
+fn sorted<T>(map: &HashMap<String, T>) -> Vec<(String, &T)> {
+  let mut entries = map.iter().map(|(name, value)| (name.clone(), value)).collect::<Vec<(String, &T)>>();
+  entries.sort_by(|a, b| a.0.cmp(&b.0));
+  entries
+}
+
+// where the frames on the call stack hold a value of the layout, like 'main.main p.position' or 'main.main stack[0]'
+pub fn live_layouts(context: &ExecutionContext, module_name: &str, layout_name: &str) -> Vec<String> {
+  let mut found = Vec::new();
+  let mut frame = Some(context);
+  while let Some(current) = frame {
+    let function = format!("{}.{}", current.current_module, current.current_function);
+    let mut variables = current.variables.iter().collect::<Vec<(&String, &Value)>>();
+    variables.sort_by(|a, b| a.0.cmp(b.0));
+    for (name, value) in variables {
+      find_layouts(value, current.current_module.as_str(), module_name, layout_name, format!("{} {}", function, name), &mut found);
+    }
+    for (index, value) in current.stack.iter().enumerate() {
+      find_layouts(value, current.current_module.as_str(), module_name, layout_name, format!("{} stack[{}]", function, index), &mut found);
+    }
+    frame = current.parent_execution_context.as_deref();
+  }
+  found
+}
+
+fn find_layouts(value: &Value, frame_module: &str, module_name: &str, layout_name: &str, path: String, found: &mut Vec<String>) {
+  match value {
+    Value::Layout(layout) => {
+      // layouts allocated with 'this' belong to the module of the function that made them
+      let layout_module = if layout.module_name == "this" { frame_module } else { layout.module_name.as_str() };
+      if layout_module == module_name && layout.layout_name == layout_name {
+        found.push(path.clone());
+      }
+      for (member, member_value) in sorted(&layout.values) {
+        find_layouts(member_value, frame_module, module_name, layout_name, format!("{}.{}", path, member), found);
+      }
+    }
+    Value::Array(array) => {
+      for (index, element) in array.values.iter().enumerate() {
+        find_layouts(element, frame_module, module_name, layout_name, format!("{}[{}]", path, index), found);
+      }
+    }
+    _ => {}
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::hydro::testing::compile;
+
+  #[test]
+  fn finds_changed_functions_layouts_and_live_values() {
+    let old = compile("module main\n\nlayout point\n    s32 x\n    s32 y\n\nfunction square s32 returns s32 body\n    duplicate\n    multiply\n    return\n\nfunction same body\n    return\n\nmain body\n    return\n");
+    let new = compile("module main\n\nlayout point\n    s64 x\n    s32 z\n\nfunction square s32 returns s32 body\n    push s32 2\n    multiply\n    return\n\nfunction same body\n    return\n\nfunction cube s32 returns s32 body\n    return\n\nmain body\n    return\n");
+    let changes = compare(&old, &new);
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].describe(), "main: added cube; changed square; layouts point");
+    assert_eq!(changes[0].layouts[0].describe(), "point added z; removed y; changed x from s32 to s64");
+    assert!(compare(&old, &old).is_empty());
+
+    let point = old.get_module("main").unwrap().layout_templates.get("point").unwrap().create_value("this".to_string());
+    let parent = ExecutionContext {
+      parent_execution_context: None,
+      stack: vec![point.clone()],
+      program_counter: 0,
+      variables: HashMap::from([("p".to_string(), point)]),
+      return_value: None,
+      current_function: "main".to_string(),
+      current_module: "main".to_string(),
+    };
+    let context = ExecutionContext { parent_execution_context: Some(Box::new(parent.clone())), stack: Vec::new(), variables: HashMap::new(), current_function: "square".to_string(), ..parent };
+    assert_eq!(live_layouts(&context, "main", "point"), vec!["main.main p", "main.main stack[0]"]);
+    assert!(live_layouts(&context, "other", "point").is_empty());
+  }
+}
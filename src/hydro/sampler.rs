@@ -0,0 +1,178 @@
+use crate::hydro::compilationunit::CompilationUnit;
+use crate::hydro::executioncontext::ExecutionContext;
+use crate::hydro::profiler::share;
+use crate::util::metrictracker::MetricTracker;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// A low overhead alternative to timing every call. Between samples an instruction only costs a counter decrement or
+// the load of a flag a timer thread raises. Each sample is charged the time since the one before it, which turns the
+// samples back into a timeline of "total" metrics that the flamegraph and the profile exports already understand
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SampleInterval {
+  Instructions(u64),
+  Time(Duration),
+}
+
+#[derive(Debug, Clone)]
+pub struct Sample {
+  pub stack: Vec<String>,
+  pub module_name: String,
+  pub function_name: String,
+  pub program_counter: usize,
+  pub time: Instant,
+}
+
+pub struct Sampler {
+  interval: SampleInterval,
+  // instructions left until the next sample when sampling by instructions
+  countdown: u64,
+  // raised by the timer thread when sampling by time
+  due: Arc<AtomicBool>,
+  running: Arc<AtomicBool>,
+  started: Instant,
+  samples: Vec<Sample>,
+}
+
+impl Sampler {
+  pub fn new(interval: SampleInterval) -> Self {
+    let due = Arc::new(AtomicBool::new(false));
+    let running = Arc::new(AtomicBool::new(true));
+    if let SampleInterval::Time(period) = interval {
+      let (due, running) = (due.clone(), running.clone());
+      thread::spawn(move || {
+        while running.load(Ordering::Relaxed) {
+          thread::sleep(period);
+          due.store(true, Ordering::Relaxed);
+        }
+      });
+    }
+    let countdown = match interval {
+      SampleInterval::Instructions(every) => every.max(1),
+      SampleInterval::Time(_) => 0,
+    };
+    Self { interval, countdown, due, running, started: Instant::now(), samples: Vec::new() }
+  }
+
+  // called right before every instruction runs
+  pub fn tick(&mut self, context: &ExecutionContext) {
+    let due = match self.interval {
+      SampleInterval::Instructions(every) => {
+        self.countdown -= 1;
+        if self.countdown == 0 {
+          self.countdown = every.max(1);
+          true
+        } else {
+          false
+        }
+      }
+      // only the load runs between samples
+      SampleInterval::Time(_) => self.due.load(Ordering::Relaxed) && self.due.swap(false, Ordering::Relaxed),
+    };
+    if due {
+      self.samples.push(Sample {
+        stack: context.get_call_stack(),
+        module_name: context.current_module.clone(),
+        function_name: context.current_function.clone(),
+        program_counter: context.program_counter,
+        time: Instant::now(),
+      });
+    }
+  }
+
+  pub fn samples(&self) -> &Vec<Sample> {
+    &self.samples
+  }
+
+  // adds a "total" metric for every stretch of samples a frame was on the stack for. Two calls of a function that no
+  // sample fell between look like one longer call. The time after the last sample isn't charged to anything
+  pub fn export_metrics(&self, metric_tracker: &mut MetricTracker) {
+    let mut open: Vec<(String, Instant)> = Vec::new();
+    let mut previous = self.started;
+    for sample in &self.samples {
+      let common = open.iter().zip(&sample.stack).take_while(|((frame, _), sample_frame)| frame == *sample_frame).count();
+      Self::close(&mut open, common, previous, metric_tracker);
+      for frame in &sample.stack[common..] {
+        open.push((frame.clone(), previous));
+      }
+      previous = sample.time;
+    }
+    Self::close(&mut open, 0, previous, metric_tracker);
+  }
+
+  fn close(open: &mut Vec<(String, Instant)>, keep: usize, end: Instant, metric_tracker: &mut MetricTracker) {
+    while open.len() > keep {
+      let stack = open.iter().map(|(frame, _)| frame.clone()).collect::<Vec<String>>();
+      let (_, start) = open.pop().unwrap();
+      metric_tracker.record(stack, "total".to_string(), start, end.duration_since(start));
+    }
+  }
+
+  // the instructions most samples landed on, "  34.5%  main.fibonacci pc 12 (fibonacci.h2o:30)"
+  pub fn summary(&self, compilation_unit: &CompilationUnit, count: usize) -> Vec<String> {
+    let mut hits: HashMap<(&String, &String, usize), usize> = HashMap::new();
+    for sample in &self.samples {
+      *hits.entry((&sample.module_name, &sample.function_name, sample.program_counter)).or_insert(0) += 1;
+    }
+    let mut hits = hits.into_iter().collect::<Vec<((&String, &String, usize), usize)>>();
+    hits.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+    let mut lines = vec![format!("{} samples", self.samples.len())];
+    for ((module_name, function_name, program_counter), samples) in hits.into_iter().take(count) {
+      let location = match compilation_unit.get_source_location(module_name.as_str(), function_name.as_str(), program_counter) {
+        Some(location) => format!(" ({}:{})", location.file, location.line),
+        None => String::new(),
+      };
+      lines.push(format!("{:>7.1}%  {}.{} pc {}{}", share(samples as f64, self.samples.len() as f64), module_name, function_name, program_counter, location));
+    }
+    lines
+  }
+}
+
+impl Drop for Sampler {
+  fn drop(&mut self) {
+    self.running.store(false, Ordering::Relaxed);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::hydro::testing::{compile, debug_main, headless_debug_context};
+  use crate::hydro::value::Value;
+
+  fn sample(stack: &[&str], time: Instant) -> Sample {
+    Sample { stack: stack.iter().map(|frame| frame.to_string()).collect(), module_name: "main".to_string(), function_name: String::new(), program_counter: 0, time }
+  }
+
+  #[test]
+  fn samples_become_metrics_for_the_flamegraph() {
+    let source = "module main\n\nfunction double s32 returns s32 body\n    push s32 2\n    multiply\n    return\n\nmain body\n    push s32 5\n    push funcp main double\n    call\n    push funcp main double\n    call\n    return\n";
+    let compilation_unit = compile(source);
+    let mut debug_context = headless_debug_context();
+    debug_context.sampler = Some(Sampler::new(SampleInterval::Instructions(2)));
+    let result = debug_main(&compilation_unit, &mut debug_context);
+    assert_eq!(result.unwrap(), Some(Value::Signed32(20)));
+    // no call was timed and every other one of the 12 instructions was sampled
+    assert!(debug_context.metric_tracker.get_events().is_empty());
+    let sampler = debug_context.sampler.take().unwrap();
+    let sampled = sampler.samples().iter().map(|sample| (sample.function_name.as_str(), sample.program_counter)).collect::<Vec<(&str, usize)>>();
+    assert_eq!(sampled, vec![("main", 1), ("double", 0), ("double", 2), ("main", 4), ("double", 1), ("main", 5)]);
+
+    let start = Instant::now();
+    let at = |millis: u64| start + Duration::from_millis(millis);
+    let mut sampler = Sampler::new(SampleInterval::Instructions(1));
+    sampler.started = start;
+    sampler.samples = vec![sample(&["main.main"], at(10)), sample(&["main.main", "main.fib"], at(20)), sample(&["main.main", "main.fib", "main.fib"], at(30)), sample(&["main.main", "main.fib"], at(40))];
+    let mut metric_tracker = MetricTracker::new();
+    sampler.export_metrics(&mut metric_tracker);
+    let events = metric_tracker.get_events().into_iter().map(|event| (event.stack.len(), event.duration.as_millis())).collect::<Vec<(usize, u128)>>();
+    assert_eq!(events, vec![(1, 40), (2, 30), (3, 10)]);
+    let flamegraph = metric_tracker.get_flamegraph(Vec::new()).unwrap();
+    assert_eq!((flamegraph.stack.clone(), flamegraph.subgraph.len()), (vec!["main.main".to_string()], 1));
+  }
+}
@@ -0,0 +1,35 @@
+use ocean_macros::New;
+use std::fs;
+
+#[derive(Debug, Clone, PartialEq, New)]
+pub struct SourceLocation {
+  pub file: String,
+  pub line: usize,
+  pub column: usize,
+  #[default((0, 0))]
+  pub span: (usize, usize),
+}
+
+impl SourceLocation {
+  pub fn spanning(mut self, span: (usize, usize)) -> Self {
+    self.span = span;
+    self
+  }
+
+  pub fn to_string(&self) -> String {
+    format!("{}:{}:{}", self.file, self.line, self.column)
+  }
+
+  // reads the line back out of the source file so we don't have to keep every file in memory
+  pub fn source_line(&self) -> Option<String> {
+    match fs::read_to_string(self.file.as_str()) {
+      Ok(contents) => contents.lines().nth(self.line.checked_sub(1)?).map(|x| x.trim().to_string()),
+      Err(_) => None,
+    }
+  }
+
+  // "file.h2o" should match "examples/hydro/file.h2o" so we compare path components from the end
+  pub fn is_file(&self, file_name: &str) -> bool {
+    std::path::Path::new(self.file.as_str()).ends_with(file_name)
+  }
+}
@@ -0,0 +1,53 @@
+#![cfg(test)]
+
+use crate::hydro::analyzer::dataflow::variableaccess::{find_variable_accesses, VariableAccess};
+use crate::hydro::analyzer::functionanalysis::analyze_function;
+use crate::hydro::compilationunit::CompilationUnit;
+use crate::hydro::debugcontext::DebugContext;
+use crate::hydro::exception::Exception;
+use crate::hydro::function::Function;
+use crate::hydro::frontend::parser::Parser;
+use crate::hydro::value::Value;
+use std::collections::HashMap;
+
+// compiles the source of a test as if it was the file main.h2o
+pub fn compile(source: &str) -> CompilationUnit {
+  let mut compilation_unit = CompilationUnit::new();
+  for module in Parser::from_source("main.h2o", source).parse() {
+    compilation_unit.add_module(&module);
+  }
+  compilation_unit
+}
+
+// a debugger that never stops or records so tests can run programs straight through its hooks
+pub fn headless_debug_context() -> DebugContext {
+  let mut debug_context = DebugContext::new();
+  debug_context.headless = true;
+  debug_context.recording = None;
+  debug_context
+}
+
+pub fn debug_main(compilation_unit: &CompilationUnit, debug_context: &mut DebugContext) -> Result<Option<Value>, Exception> {
+  compilation_unit.debug("main".to_string(), "main".to_string(), Vec::new(), None, debug_context)
+}
+
+// a function of the main module in the source of a test
+pub fn function(source: &str, name: &str) -> Function {
+  compile(source).get_function("main", name).unwrap().clone()
+}
+
+// a lone function from its header, "f s32 returns s32", and its body lines
+pub fn function_with_body(header: &str, lines: &[&str]) -> Function {
+  let body = lines.iter().map(|line| format!("    {}\n", line)).collect::<String>();
+  function(format!("module main\n\nfunction {} body\n{}", header, body).as_str(), header.split(' ').next().unwrap())
+}
+
+// the instructions of a function the way the disassembler shows them so tests can compare bodies
+pub fn listing(function: &Function) -> Vec<String> {
+  function.body.iter().map(|instruction| instruction.to_string()).collect()
+}
+
+// the variables each instruction of a function on its own reads and writes
+pub fn variable_accesses(function: &Function) -> HashMap<usize, VariableAccess> {
+  find_variable_accesses(function, &analyze_function(&CompilationUnit::new(), "main", function))
+}
@@ -0,0 +1,289 @@
+use crate::hydro::executioncontext::ExecutionContext;
+use crate::hydro::inspector::ValueFormatter;
+use crate::hydro::instruction::Instruction;
+use crate::hydro::value::Value;
+use serde_json::{json, Value as JsonValue};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+// An execution trace is a jsonl file with one record per instruction that ran. A record is written once its instruction
+// is done, so the record of a call comes after the records of the function it called. Values are written the way the
+// inspector shows them so two traces can be compared as text
+
+pub const DEFAULT_TRACE_STACK: usize = 3;
+// how far past a divergence trace-diff looks for the traces to line up again
+pub const RESYNC_WINDOW: usize = 256;
+// how many records in a row have to match for the traces to count as lined up again
+const RESYNC_LENGTH: usize = 8;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceRecord {
+  // the number of instructions that ran before this one, counting the ones that were filtered out
+  pub step: u64,
+  pub depth: usize,
+  pub module_name: String,
+  pub function_name: String,
+  pub program_counter: usize,
+  pub instruction: String,
+  // the top of the stack after the instruction ran, top first
+  pub stack: Vec<String>,
+  // variables the instruction created or changed, with their new values
+  pub changed: BTreeMap<String, String>,
+}
+
+impl TraceRecord {
+  pub fn to_json(&self) -> JsonValue {
+    json!({
+      "step": self.step,
+      "depth": self.depth,
+      "module": self.module_name,
+      "function": self.function_name,
+      "pc": self.program_counter,
+      "instruction": self.instruction,
+      "stack": self.stack,
+      "changed": self.changed,
+    })
+  }
+
+  pub fn from_json(line: &str) -> Result<Self, String> {
+    let record = serde_json::from_str::<JsonValue>(line).map_err(|error| error.to_string())?;
+    let text = |field: &str| record[field].as_str().map(|value| value.to_string()).ok_or(format!("Missing '{}'", field));
+    let number = |field: &str| record[field].as_u64().ok_or(format!("Missing '{}'", field));
+    let stack = match record["stack"].as_array() {
+      Some(stack) => stack.iter().map(|value| value.as_str().map(|value| value.to_string()).ok_or("Stack values must be strings".to_string())).collect::<Result<Vec<String>, String>>()?,
+      None => return Err("Missing 'stack'".to_string()),
+    };
+    let changed = match record["changed"].as_object() {
+      Some(changed) => changed.iter().map(|(name, value)| value.as_str().map(|value| (name.clone(), value.to_string())).ok_or("Changed values must be strings".to_string())).collect::<Result<BTreeMap<String, String>, String>>()?,
+      None => return Err("Missing 'changed'".to_string()),
+    };
+    Ok(Self {
+      step: number("step")?,
+      depth: number("depth")? as usize,
+      module_name: text("module")?,
+      function_name: text("function")?,
+      program_counter: number("pc")? as usize,
+      instruction: text("instruction")?,
+      stack,
+      changed,
+    })
+  }
+
+  // the fields that differ between two records. Steps and program counters move whenever code is added or removed so
+  // they are not part of what a program did
+  pub fn differences(&self, other: &TraceRecord) -> Vec<&'static str> {
+    let mut differences = Vec::new();
+    if self.module_name != other.module_name || self.function_name != other.function_name {
+      differences.push("function");
+    }
+    if self.depth != other.depth {
+      differences.push("depth");
+    }
+    if self.instruction != other.instruction {
+      differences.push("instruction");
+    }
+    if self.stack != other.stack {
+      differences.push("stack");
+    }
+    if self.changed != other.changed {
+      differences.push("changed variables");
+    }
+    differences
+  }
+
+  pub fn describe(&self) -> String {
+    let mut description = format!("#{} {}.{}:{} {}  stack [{}]", self.step, self.module_name, self.function_name, self.program_counter, self.instruction, self.stack.join(", "));
+    if !self.changed.is_empty() {
+      let changed = self.changed.iter().map(|(name, value)| format!("{} = {}", name, value)).collect::<Vec<String>>();
+      description += format!("  changed {}", changed.join(", ")).as_str();
+    }
+    description
+  }
+}
+
+pub struct TraceWriter {
+  output: BufWriter<File>,
+  // how many values from the top of the stack each record keeps
+  stack_size: usize,
+  // only instructions in these modules and functions are written. Empty means everything
+  modules: Vec<String>,
+  functions: Vec<String>,
+  step: u64,
+  // the first write that failed. Nothing more is written after it and finish returns it
+  error: Option<std::io::Error>,
+}
+
+impl TraceWriter {
+  pub fn create(path: &str, stack_size: usize, modules: Vec<String>, functions: Vec<String>) -> std::io::Result<Self> {
+    Ok(Self { output: BufWriter::new(File::create(path)?), stack_size, modules, functions, step: 0, error: None })
+  }
+
+  // called before every instruction runs. Returns the step of the instruction when it should be written
+  pub fn start(&mut self, module_name: &str, function_name: &str) -> Option<u64> {
+    if self.error.is_some() {
+      return None;
+    }
+    let step = self.step;
+    self.step += 1;
+    let module_matches = self.modules.is_empty() || self.modules.iter().any(|module| module == module_name);
+    // functions can be given as 'fibonacci' or 'main.fibonacci'
+    let function_matches = self.functions.is_empty() || self.functions.iter().any(|function| function == function_name || *function == format!("{}.{}", module_name, function_name));
+    if module_matches && function_matches {
+      Some(step)
+    } else {
+      None
+    }
+  }
+
+  // called once the instruction at program_counter is done with the variables from before it ran
+  pub fn record(&mut self, step: u64, program_counter: usize, instruction: &Instruction, variables_before: &HashMap<String, Value>, context: &ExecutionContext) {
+    let formatter = ValueFormatter::new(Some(context));
+    let changed = context.variables.iter().filter(|(name, value)| variables_before.get(*name) != Some(*value)).map(|(name, value)| (name.clone(), formatter.format(value))).collect::<BTreeMap<String, String>>();
+    let record = TraceRecord {
+      step,
+      depth: context.depth(),
+      module_name: context.current_module.clone(),
+      function_name: context.current_function.clone(),
+      program_counter,
+      instruction: instruction.to_string(),
+      stack: context.stack.iter().rev().take(self.stack_size).map(|value| formatter.format(value)).collect(),
+      changed,
+    };
+    if let Err(error) = writeln!(self.output, "{}", record.to_json()) {
+      self.error = Some(error);
+    }
+  }
+
+  pub fn finish(&mut self) -> std::io::Result<()> {
+    match self.error.take() {
+      Some(error) => Err(error),
+      None => self.output.flush(),
+    }
+  }
+}
+
+pub fn read_trace(path: &str) -> Result<Vec<TraceRecord>, String> {
+  let contents = std::fs::read_to_string(path).map_err(|error| format!("Could not read '{}': {}", path, error))?;
+  contents.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()).map(|(index, line)| TraceRecord::from_json(line).map_err(|error| format!("{}:{}: {}", path, index + 1, error))).collect()
+}
+
+// where two traces stop doing the same thing, as indexes into each of them
+#[derive(Debug, Clone, PartialEq)]
+pub struct Divergence {
+  pub left: usize,
+  pub right: usize,
+  // how many records each side has to skip until the traces match again, if they do within the resync window
+  pub resync: Option<(usize, usize)>,
+}
+
+pub fn diverge(left: &[TraceRecord], right: &[TraceRecord]) -> Option<Divergence> {
+  let index = (0..left.len().max(right.len())).find(|index| match (left.get(*index), right.get(*index)) {
+    (Some(left_record), Some(right_record)) => !left_record.differences(right_record).is_empty(),
+    _ => true,
+  })?;
+  Some(Divergence { left: index, right: index, resync: resync(&left[index..], &right[index..]) })
+}
+
+// the fewest records to skip on both sides, all together, so that the next records match. The end of both traces
+// counts as a match
+fn resync(left: &[TraceRecord], right: &[TraceRecord]) -> Option<(usize, usize)> {
+  for total in 1..=RESYNC_WINDOW * 2 {
+    for left_skip in total.saturating_sub(RESYNC_WINDOW)..=total.min(RESYNC_WINDOW) {
+      let right_skip = total - left_skip;
+      if left_skip > left.len() || right_skip > right.len() {
+        continue;
+      }
+      let (left_rest, right_rest) = (&left[left_skip..], &right[right_skip..]);
+      let length = RESYNC_LENGTH.min(left_rest.len().max(right_rest.len()));
+      if left_rest.len() >= length && right_rest.len() >= length && left_rest.iter().zip(right_rest).take(length).all(|(a, b)| a.differences(b).is_empty()) {
+        return Some((left_skip, right_skip));
+      }
+    }
+  }
+  None
+}
+
+// the lines trace-diff prints for a divergence, with up to context records before and after it
+pub fn report(left: &[TraceRecord], right: &[TraceRecord], divergence: &Divergence, context: usize) -> Vec<String> {
+  let mut lines = Vec::new();
+  match (left.get(divergence.left), right.get(divergence.right)) {
+    (Some(left_record), Some(right_record)) => {
+      lines.push(format!("Traces diverge at record {} (left step {}, right step {})", divergence.left, left_record.step, right_record.step));
+      lines.push(format!("  differs in {}", left_record.differences(right_record).join(", ")));
+    }
+    (Some(_), None) => lines.push(format!("The right trace ends after {} records while the left one goes on", right.len())),
+    (None, Some(_)) => lines.push(format!("The left trace ends after {} records while the right one goes on", left.len())),
+    (None, None) => {}
+  }
+
+  let before = divergence.left.saturating_sub(context);
+  if before < divergence.left {
+    lines.push("Before:".to_string());
+    for record in &left[before..divergence.left] {
+      lines.push(format!("    {}", record.describe()));
+    }
+  }
+  let (left_count, right_count) = match divergence.resync {
+    Some((left_skip, right_skip)) => (left_skip.max(1).min(context.max(1)), right_skip.max(1).min(context.max(1))),
+    None => (context.max(1), context.max(1)),
+  };
+  lines.push("Left:".to_string());
+  for record in left.iter().skip(divergence.left).take(left_count) {
+    lines.push(format!("  - {}", record.describe()));
+  }
+  lines.push("Right:".to_string());
+  for record in right.iter().skip(divergence.right).take(right_count) {
+    lines.push(format!("  + {}", record.describe()));
+  }
+  match divergence.resync {
+    Some((left_skip, right_skip)) => lines.push(format!("The traces line up again after {} records on the left and {} on the right", left_skip, right_skip)),
+    None => lines.push(format!("The traces do not line up again within {} records", RESYNC_WINDOW)),
+  }
+  lines
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn record(step: u64, instruction: &str, stack: &[&str]) -> TraceRecord {
+    TraceRecord {
+      step,
+      depth: 0,
+      module_name: "main".to_string(),
+      function_name: "main".to_string(),
+      program_counter: step as usize,
+      instruction: instruction.to_string(),
+      stack: stack.iter().map(|value| value.to_string()).collect(),
+      changed: BTreeMap::new(),
+    }
+  }
+
+  #[test]
+  fn finds_the_first_divergence_and_where_traces_line_up_again() {
+    let mut left = (0..20).map(|step| record(step, "push u32 1", &["1"])).collect::<Vec<TraceRecord>>();
+    left[5] = record(5, "add", &["2"]);
+    left[5].changed.insert("count".to_string(), "2".to_string());
+    assert_eq!(TraceRecord::from_json(left[5].to_json().to_string().as_str()), Ok(left[5].clone()));
+
+    // the right side ran one extra instruction, so everything after it has a different step and program counter
+    let mut right = left.clone();
+    right.insert(5, record(5, "pop", &[]));
+    for (index, record) in right.iter_mut().enumerate() {
+      record.step = index as u64;
+      record.program_counter = index;
+    }
+
+    let divergence = diverge(&left, &right).unwrap();
+    assert_eq!(divergence, Divergence { left: 5, right: 5, resync: Some((0, 1)) });
+    let lines = report(&left, &right, &divergence, 2);
+    assert_eq!(lines[1], "  differs in instruction, stack, changed variables");
+    assert!(lines.contains(&"  + #5 main.main:5 pop  stack []".to_string()));
+    assert_eq!(lines.last().unwrap(), "The traces line up again after 0 records on the left and 1 on the right");
+
+    assert_eq!(diverge(&left, &left), None);
+    // running off the end of the shorter trace lines up with the end of the longer one
+    assert_eq!(diverge(&left[..10], &left).unwrap().resync, Some((0, 10)));
+  }
+}
@@ -5,6 +5,7 @@ use ocean_macros::New;
 #[derive(Debug, Clone, PartialEq)]
 pub enum Type {
   Any,
+  Void,
   Boolean,
   Array(Option<u64>, Box<Type>),
   Layout(String, String, Option<HashMap<String, Type>>),
@@ -35,6 +36,7 @@ impl Type {
   pub fn default(&self) -> Value {
     match self {
       Type::Any => todo!("default value for any type. This should likely not be possible"),
+      Type::Void => panic!("There is no default value for void"),
       Type::Boolean => Value::Boolean(false),
       Type::Unsigned8 => Value::Unsigned8(0),
       Type::Unsigned16 => Value::Unsigned16(0),
@@ -84,6 +86,44 @@ impl Type {
     }
   }
 
+  pub fn min(value_type: Type) -> Value {
+    match value_type {
+      Type::Boolean => Value::Boolean(false),
+      Type::Unsigned8 => Value::Unsigned8(u8::MIN),
+      Type::Unsigned16 => Value::Unsigned16(u16::MIN),
+      Type::Unsigned32 => Value::Unsigned32(u32::MIN),
+      Type::Unsigned64 => Value::Unsigned64(u64::MIN),
+      Type::Unsigned128 => Value::Unsigned128(u128::MIN),
+      Type::Signed8 => Value::Signed8(i8::MIN),
+      Type::Signed16 => Value::Signed16(i16::MIN),
+      Type::Signed32 => Value::Signed32(i32::MIN),
+      Type::Signed64 => Value::Signed64(i64::MIN),
+      Type::Signed128 => Value::Signed128(i128::MIN),
+      Type::Float32 => Value::Float32(f32::NEG_INFINITY),
+      Type::Float64 => Value::Float64(f64::NEG_INFINITY),
+      _ => panic!("There is no minimum value for {:?}", value_type),
+    }
+  }
+
+  pub fn max(value_type: Type) -> Value {
+    match value_type {
+      Type::Boolean => Value::Boolean(true),
+      Type::Unsigned8 => Value::Unsigned8(u8::MAX),
+      Type::Unsigned16 => Value::Unsigned16(u16::MAX),
+      Type::Unsigned32 => Value::Unsigned32(u32::MAX),
+      Type::Unsigned64 => Value::Unsigned64(u64::MAX),
+      Type::Unsigned128 => Value::Unsigned128(u128::MAX),
+      Type::Signed8 => Value::Signed8(i8::MAX),
+      Type::Signed16 => Value::Signed16(i16::MAX),
+      Type::Signed32 => Value::Signed32(i32::MAX),
+      Type::Signed64 => Value::Signed64(i64::MAX),
+      Type::Signed128 => Value::Signed128(i128::MAX),
+      Type::Float32 => Value::Float32(f32::INFINITY),
+      Type::Float64 => Value::Float64(f64::INFINITY),
+      _ => panic!("There is no maximum value for {:?}", value_type),
+    }
+  }
+
   pub fn subset(sub: &Type, sup: &Type) -> bool {
     // TODO type subsetting
     match (sub, sup) {
@@ -99,6 +139,17 @@ impl Type {
           false
         }
       }
+      (Type::Layout(left_module, left_layout, _), Type::Layout(right_module, right_layout, _)) => {
+        // 'this' layouts haven't been resolved to their module so we can only compare the layout names
+        left_layout == right_layout && (left_module == right_module || left_module == "this" || right_module == "this")
+      }
+      (Type::FunctionPointer(left_parameters, left_return), Type::FunctionPointer(right_parameters, right_return)) => {
+        left_parameters.len() == right_parameters.len()
+          && left_parameters.iter().zip(right_parameters.iter()).all(|(left, right)| Type::subset(right, left))
+          && Type::subset(left_return, right_return)
+      }
+      (Type::Reference(left_subtype), Type::Reference(right_subtype)) => Type::subset(left_subtype, right_subtype),
+      (Type::Void, Type::Void) => true,
       (Type::Boolean, Type::Boolean) => true,
       (Type::Unsigned8, Type::Unsigned8) => true,
       (Type::Unsigned16, Type::Unsigned16) => true,
@@ -151,7 +202,14 @@ impl Value {
     match self {
       Value::Boolean(_) => Type::Boolean,
       Value::Array(array) => Type::Array(Some(array.length.to_u64().unwrap()), Box::new(array.value_type.clone())),
-      Value::Layout(_) => todo!(),
+      Value::Layout(layout) => {
+        let mut member_types = HashMap::new();
+        for (member_name, member_value) in &layout.values {
+          member_types.insert(member_name.clone(), member_value.type_of());
+        }
+        Type::Layout(layout.module_name.clone(), layout.layout_name.clone(), Some(member_types))
+      }
+      // function pointers need the compilation unit to be typed. See CompilationUnit::type_of
       Value::FunctionPointer(_) => todo!(),
       Value::Reference(_) => todo!(),
       Value::Unsigned8(_) => Type::Unsigned8,
@@ -298,6 +356,36 @@ impl Value {
     }
   }
 
+  // only for sites the compiler proved are in bounds
+  pub fn index_unchecked(&self, index: u64) -> Result<Value, String> {
+    match self {
+      Value::Array(array) => match array.values.get(index as usize) {
+        Some(value) => Ok(value.clone()),
+        None => Err(format!("Array index out of bounds. Tried to index array of length {:?} with index {}", *array.length, index)),
+      },
+      _ => Err(format!("Cannot index a {:?} :(", self.type_of())),
+    }
+  }
+
+  // only for sites the compiler proved are in bounds
+  pub fn set_index_unchecked(&mut self, index: u64, value: Value) -> Result<(), String> {
+    match self {
+      Value::Array(array) => {
+        if !Type::subset(&value.type_of(), &array.value_type) {
+          return Err(format!("Cannot insert value {:?} of type {:?} into array of type {:?}", value, value.type_of(), array.value_type));
+        }
+        match array.values.get_mut(index as usize) {
+          Some(element) => {
+            *element = value;
+            Ok(())
+          }
+          None => Err(format!("Array index out of bounds. Tried to index array of length {:?} with index {}", *array.length, index)),
+        }
+      }
+      _ => Err(format!("Cannot index a {:?} :(", self.type_of())),
+    }
+  }
+
   pub fn get_member(&self, member: String) -> Result<Value, String> {
     match self {
       Value::Layout(layout) => match layout.values.get(member.as_str()) {
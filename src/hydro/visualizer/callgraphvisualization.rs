@@ -0,0 +1,69 @@
+use crate::hydro::callgraph::CallGraph;
+use crate::hydro::compilationunit::CompilationUnit;
+use crate::hydro::visualizer::{quoted_id, GraphVisualization};
+use graphviz_rust::dot_generator::*;
+use graphviz_rust::dot_structures::*;
+
+// Every function in the program and the functions it may call. Functions and calls that are part of a recursion cycle are red
+pub struct CallGraphVisualization {
+  call_graph: CallGraph,
+  intrinsics: Vec<(String, String)>,
+}
+
+impl CallGraphVisualization {
+  pub fn create(compilation_unit: &CompilationUnit) -> CallGraphVisualization {
+    let mut intrinsics = Vec::new();
+    for module_name in compilation_unit.get_module_names() {
+      for intrinsic_name in compilation_unit.get_module(module_name.as_str()).unwrap().intrinsics.keys() {
+        intrinsics.push((module_name.clone(), intrinsic_name.clone()));
+      }
+    }
+    CallGraphVisualization { call_graph: CallGraph::build(compilation_unit), intrinsics }
+  }
+
+  fn function_id(function: &(String, String)) -> NodeId {
+    NodeId(quoted_id(format!("{}.{}", function.0, function.1).as_str()), None)
+  }
+
+  // a call is part of a cycle when the callee can get back to the caller
+  fn is_recursive_call(&self, caller: &(String, String), callee: &(String, String)) -> bool {
+    self.call_graph.reachable_from(callee).contains(caller)
+  }
+}
+
+impl GraphVisualization for CallGraphVisualization {
+  fn build_graph(&self) -> Graph {
+    let mut graph = graph!(strict di id!("call_graph_viz_graph"));
+
+    let mut functions = self.call_graph.edges.keys().cloned().collect::<Vec<(String, String)>>();
+    functions.sort();
+    for function in &functions {
+      let mut attributes = Vec::new();
+      if self.intrinsics.contains(function) {
+        attributes.push(attr!("shape", "box"));
+        attributes.push(attr!("style", "dashed"));
+      }
+      if self.call_graph.is_recursive(function) {
+        attributes.push(attr!("color", "red"));
+        attributes.push(attr!("fontcolor", "red"));
+      }
+      graph.add_stmt(Stmt::Node(Node::new(CallGraphVisualization::function_id(function), attributes)));
+    }
+
+    for caller in &functions {
+      for callee in self.call_graph.callees(caller) {
+        let mut attributes = Vec::new();
+        if self.is_recursive_call(caller, &callee) {
+          attributes.push(attr!("color", "red"));
+          attributes.push(attr!("penwidth", 2));
+        }
+        graph.add_stmt(Stmt::Edge(Edge {
+          ty: EdgeTy::Pair(Vertex::N(CallGraphVisualization::function_id(caller)), Vertex::N(CallGraphVisualization::function_id(&callee))),
+          attributes,
+        }));
+      }
+    }
+
+    graph
+  }
+}
@@ -0,0 +1,76 @@
+use crate::hydro::controlflowgraph::ControlFlowGraph;
+use crate::hydro::function::Function;
+use crate::hydro::instruction::Instruction;
+use crate::hydro::visualizer::{escape, quoted_id, GraphVisualization};
+use graphviz_rust::dot_generator::*;
+use graphviz_rust::dot_structures::*;
+
+// One node per basic block listing its instructions. Branch edges are labeled with the condition that takes them
+pub struct ControlFlowVisualization {
+  name: String,
+  function: Function,
+  graph: ControlFlowGraph,
+}
+
+impl ControlFlowVisualization {
+  pub fn create(module_name: &String, function: &Function) -> ControlFlowVisualization {
+    ControlFlowVisualization { name: format!("{}.{}", module_name, function.name), function: function.clone(), graph: ControlFlowGraph::build(function) }
+  }
+
+  fn block_id(&self, id: usize) -> NodeId {
+    NodeId(quoted_id(self.graph.block_name(id).as_str()), None)
+  }
+
+  // '\l' left justifies each line in graphviz
+  fn block_label(&self, id: usize) -> String {
+    let block = &self.graph.blocks[id];
+    let mut label = format!("{}:\\l", escape(self.graph.block_name(id).as_str()));
+    for program_counter in block.start..block.end {
+      label += format!("{:>4}  {}\\l", program_counter, escape(self.function.body[program_counter].to_string().as_str())).as_str();
+    }
+    label
+  }
+}
+
+impl GraphVisualization for ControlFlowVisualization {
+  fn build_graph(&self) -> Graph {
+    let mut graph = graph!(strict di id!("cfg_viz_graph"));
+    graph.add_stmt(Stmt::Attribute(attr!("label", esc escape(self.name.as_str()))));
+    graph.add_stmt(Stmt::GAttribute(GraphAttributes::Node(vec![attr!("shape", "box"), attr!("fontname", "monospace")])));
+
+    let loop_heads = self.graph.loop_heads();
+    for block in &self.graph.blocks {
+      let mut attributes = vec![attr!("label", esc self.block_label(block.id))];
+      if block.id == 0 {
+        attributes.push(attr!("style", "bold"));
+      }
+      if loop_heads.contains(&block.id) {
+        attributes.push(attr!("color", "blue"));
+      }
+      graph.add_stmt(Stmt::Node(Node::new(self.block_id(block.id), attributes)));
+    }
+
+    for block in &self.graph.blocks {
+      let last_instruction = &self.function.body[block.last_instruction()];
+      for successor in &block.successors {
+        let mut attributes = Vec::new();
+        if let Instruction::Branch(branch) = last_instruction {
+          let true_block = self.function.get_target_pointer(branch.true_target.clone()).ok().and_then(|target| self.graph.block_containing(target).map(|block| block.id));
+          let false_block = self.function.get_target_pointer(branch.false_target.clone()).ok().and_then(|target| self.graph.block_containing(target).map(|block| block.id));
+          let label = match (true_block == Some(*successor), false_block == Some(*successor)) {
+            (true, true) => "always",
+            (true, false) => "true",
+            _ => "false",
+          };
+          attributes.push(attr!("label", label));
+        }
+        if *successor <= block.id {
+          attributes.push(attr!("color", "blue"));
+        }
+        graph.add_stmt(Stmt::Edge(Edge { ty: EdgeTy::Pair(Vertex::N(self.block_id(block.id)), Vertex::N(self.block_id(*successor))), attributes }));
+      }
+    }
+
+    graph
+  }
+}
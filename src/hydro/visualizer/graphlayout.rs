@@ -0,0 +1,246 @@
+use graphviz_rust::dot_structures::{Attribute, EdgeTy, Graph, Id, Stmt, Vertex};
+use std::collections::HashMap;
+
+// The parts of a graphviz graph that the built in renderers understand, so visualizations can be drawn without
+// graphviz installed. Nodes are kept in the order they were added to the graph and edges only between known nodes
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlainNode {
+  pub name: String,
+  // the lines of the node's label, or just its name when it has no label
+  pub label: Vec<String>,
+  pub color: Option<String>,
+  pub dashed: bool,
+  pub bold: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlainEdge {
+  pub from: usize,
+  pub to: usize,
+  pub label: Option<String>,
+  pub color: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlainGraph {
+  pub title: Option<String>,
+  pub nodes: Vec<PlainNode>,
+  pub edges: Vec<PlainEdge>,
+}
+
+impl PlainGraph {
+  pub fn from_dot(graph: &Graph) -> Self {
+    let statements = match graph {
+      Graph::Graph { stmts, .. } => stmts,
+      Graph::DiGraph { stmts, .. } => stmts,
+    };
+    let mut plain_graph = PlainGraph { title: None, nodes: Vec::new(), edges: Vec::new() };
+    let mut node_indexes = HashMap::new();
+    for statement in statements {
+      match statement {
+        Stmt::Attribute(Attribute(key, value)) if id_text(key) == "label" => plain_graph.title = Some(id_text(value)),
+        Stmt::Node(node) => {
+          let name = id_text(&node.id.0);
+          let label = attribute(&node.attributes, "label").unwrap_or(name.clone());
+          let style = attribute(&node.attributes, "style").unwrap_or_default();
+          node_indexes.insert(name.clone(), plain_graph.nodes.len());
+          plain_graph.nodes.push(PlainNode {
+            name,
+            label: label.trim_end_matches('\n').split('\n').map(|line| line.to_string()).collect(),
+            color: attribute(&node.attributes, "color"),
+            dashed: style == "dashed",
+            bold: style == "bold",
+          });
+        }
+        Stmt::Edge(edge) => {
+          let vertices = match &edge.ty {
+            EdgeTy::Pair(from, to) => vec![from.clone(), to.clone()],
+            EdgeTy::Chain(vertices) => vertices.clone(),
+          };
+          let names = vertices
+            .iter()
+            .filter_map(|vertex| match vertex {
+              Vertex::N(node_id) => Some(id_text(&node_id.0)),
+              Vertex::S(_) => None,
+            })
+            .collect::<Vec<String>>();
+          for pair in names.windows(2) {
+            if let (Some(from), Some(to)) = (node_indexes.get(&pair[0]), node_indexes.get(&pair[1])) {
+              plain_graph.edges.push(PlainEdge { from: *from, to: *to, label: attribute(&edge.attributes, "label"), color: attribute(&edge.attributes, "color") });
+            }
+          }
+        }
+        _ => {}
+      }
+    }
+    plain_graph
+  }
+
+  // the indexes of the edges leaving a node, in the order they were added
+  pub fn outgoing(&self, node: usize) -> Vec<usize> {
+    (0..self.edges.len()).filter(|edge| self.edges[*edge].from == node).collect()
+  }
+
+  // nodes nothing points at except through a back edge
+  pub fn roots(&self, back_edges: &Vec<bool>) -> Vec<usize> {
+    let mut has_incoming = vec![false; self.nodes.len()];
+    for (index, edge) in self.edges.iter().enumerate() {
+      if !back_edges[index] {
+        has_incoming[edge.to] = true;
+      }
+    }
+    (0..self.nodes.len()).filter(|node| !has_incoming[*node]).collect()
+  }
+
+  // whether each edge closes a cycle, found with a depth first search from the first node. Without them the graph has
+  // no cycles and can be drawn top to bottom
+  pub fn back_edges(&self) -> Vec<bool> {
+    let mut back_edges = vec![false; self.edges.len()];
+    // 0 is unvisited, 1 is on the search path and 2 is done
+    let mut state = vec![0u8; self.nodes.len()];
+    for start in 0..self.nodes.len() {
+      if state[start] != 0 {
+        continue;
+      }
+      let mut path = vec![(start, self.outgoing(start), 0usize)];
+      state[start] = 1;
+      while let Some((node, edges, next)) = path.last_mut() {
+        if *next >= edges.len() {
+          state[*node] = 2;
+          path.pop();
+          continue;
+        }
+        let edge = edges[*next];
+        *next += 1;
+        let target = self.edges[edge].to;
+        match state[target] {
+          0 => {
+            state[target] = 1;
+            let target_edges = self.outgoing(target);
+            path.push((target, target_edges, 0));
+          }
+          1 => back_edges[edge] = true,
+          _ => {}
+        }
+      }
+    }
+    back_edges
+  }
+
+  // the nodes of each row, top to bottom. A node goes one row below the lowest node that points at it and the nodes of
+  // a row are ordered to sit near the nodes they are connected to
+  pub fn layers(&self, back_edges: &Vec<bool>) -> Vec<Vec<usize>> {
+    let forward = self.edges.iter().enumerate().filter(|(index, _)| !back_edges[*index]).map(|(_, edge)| edge).collect::<Vec<&PlainEdge>>();
+    let mut incoming = vec![0usize; self.nodes.len()];
+    for edge in &forward {
+      incoming[edge.to] += 1;
+    }
+    let mut layer = vec![0usize; self.nodes.len()];
+    let mut ready = (0..self.nodes.len()).filter(|node| incoming[*node] == 0).collect::<Vec<usize>>();
+    while let Some(node) = ready.pop() {
+      for edge in forward.iter().filter(|edge| edge.from == node) {
+        layer[edge.to] = layer[edge.to].max(layer[node] + 1);
+        incoming[edge.to] -= 1;
+        if incoming[edge.to] == 0 {
+          ready.push(edge.to);
+        }
+      }
+    }
+
+    let mut layers = vec![Vec::new(); layer.iter().max().map(|deepest| deepest + 1).unwrap_or(0)];
+    for node in 0..self.nodes.len() {
+      layers[layer[node]].push(node);
+    }
+    // a few sweeps of moving each node to the average position of its neighbours in the row above, then below
+    for sweep in 0..4 {
+      let downward = sweep % 2 == 0;
+      for row in 0..layers.len() {
+        let neighbour_row = match (downward, row) {
+          (true, 0) => continue,
+          (true, _) => row - 1,
+          (false, _) if row + 1 >= layers.len() => continue,
+          (false, _) => row + 1,
+        };
+        let positions = layers[neighbour_row].iter().enumerate().map(|(position, node)| (*node, position as f64)).collect::<HashMap<usize, f64>>();
+        let mut centers = layers[row]
+          .iter()
+          .enumerate()
+          .map(|(position, node)| {
+            let neighbours = forward.iter().filter_map(|edge| if edge.to == *node { positions.get(&edge.from) } else if edge.from == *node { positions.get(&edge.to) } else { None }).collect::<Vec<&f64>>();
+            let center = if neighbours.is_empty() { position as f64 } else { neighbours.iter().copied().sum::<f64>() / neighbours.len() as f64 };
+            (center, *node)
+          })
+          .collect::<Vec<(f64, usize)>>();
+        centers.sort_by(|a, b| a.0.total_cmp(&b.0));
+        layers[row] = centers.into_iter().map(|(_, node)| node).collect();
+      }
+    }
+    layers
+  }
+}
+
+fn attribute(attributes: &Vec<Attribute>, name: &str) -> Option<String> {
+  attributes.iter().find(|Attribute(key, _)| id_text(key) == name).map(|Attribute(_, value)| id_text(value))
+}
+
+// the text of an id without the quotes and escapes dot needs. Graphviz line breaks become newlines
+pub fn id_text(id: &Id) -> String {
+  match id {
+    Id::Escaped(text) => {
+      let text = text.strip_prefix('"').and_then(|text| text.strip_suffix('"')).unwrap_or(text);
+      let mut unescaped = String::new();
+      let mut characters = text.chars();
+      while let Some(character) = characters.next() {
+        if character != '\\' {
+          unescaped.push(character);
+          continue;
+        }
+        match characters.next() {
+          Some('l') | Some('n') | Some('r') => unescaped.push('\n'),
+          Some(escaped) => unescaped.push(escaped),
+          None => unescaped.push('\\'),
+        }
+      }
+      unescaped
+    }
+    Id::Html(text) => text.trim_start_matches('<').trim_end_matches('>').to_string(),
+    Id::Plain(text) | Id::Anonymous(text) => text.clone(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::hydro::visualizer::quoted_id;
+  use graphviz_rust::dot_generator::*;
+  use graphviz_rust::dot_structures::*;
+
+  fn node_id(name: &str) -> NodeId {
+    NodeId(quoted_id(name), None)
+  }
+
+  #[test]
+  fn reads_dot_graphs_and_lays_cycles_out_top_to_bottom() {
+    let mut graph = graph!(strict di id!("test"));
+    graph.add_stmt(Stmt::Node(Node::new(node_id("main.main"), vec![attr!("label", esc "main:\\l   0  push \\\"a\\\"\\l")])));
+    graph.add_stmt(Stmt::Node(Node::new(node_id("main.fib"), vec![attr!("color", "red")])));
+    graph.add_stmt(Stmt::Node(Node::new(node_id("std.print"), vec![attr!("style", "dashed")])));
+    graph.add_stmt(Stmt::Edge(Edge { ty: EdgeTy::Pair(Vertex::N(node_id("main.main")), Vertex::N(node_id("main.fib"))), attributes: vec![attr!("label", "true")] }));
+    graph.add_stmt(Stmt::Edge(Edge { ty: EdgeTy::Pair(Vertex::N(node_id("main.fib")), Vertex::N(node_id("main.fib"))), attributes: vec![] }));
+    graph.add_stmt(Stmt::Edge(Edge { ty: EdgeTy::Pair(Vertex::N(node_id("main.fib")), Vertex::N(node_id("std.print"))), attributes: vec![] }));
+    graph.add_stmt(Stmt::Edge(Edge { ty: EdgeTy::Pair(Vertex::N(node_id("main.main")), Vertex::N(node_id("std.print"))), attributes: vec![] }));
+
+    let plain_graph = PlainGraph::from_dot(&graph);
+    assert_eq!(plain_graph.nodes[0].label, vec!["main:", "   0  push \"a\""]);
+    assert_eq!(plain_graph.nodes[1].color, Some("red".to_string()));
+    assert!(plain_graph.nodes[2].dashed);
+    assert_eq!(plain_graph.edges[0].label, Some("true".to_string()));
+
+    let back_edges = plain_graph.back_edges();
+    assert_eq!(back_edges, vec![false, true, false, false]);
+    assert_eq!(plain_graph.roots(&back_edges), vec![0]);
+    // print sits below fib even though main points at it directly
+    assert_eq!(plain_graph.layers(&back_edges), vec![vec![0], vec![1], vec![2]]);
+  }
+}
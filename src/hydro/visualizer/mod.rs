@@ -1 +1,58 @@
+use graphviz_rust::cmd::{CommandArg, Format};
+use graphviz_rust::dot_structures::{Graph, Id};
+use graphviz_rust::printer::PrinterContext;
+use graphviz_rust::{exec, print};
+use crate::hydro::visualizer::graphlayout::PlainGraph;
+use crate::hydro::visualizer::svgrenderer::render_svg;
+use crate::hydro::visualizer::textrenderer::render_text;
+use std::fs;
+
+pub mod callgraphvisualization;
+pub mod controlflowvisualization;
+pub mod graphlayout;
 pub mod moduledependencyvisualization;
+pub mod svgrenderer;
+pub mod textrenderer;
+
+pub trait GraphVisualization {
+  fn build_graph(&self) -> Graph;
+
+  fn png(&self, output_filename: String) -> Result<(), String> {
+    if !graphviz_installed() {
+      return Err("Graphviz not installed. Install it for png visualizations or use the svg, dot, text or ascii format".to_string());
+    }
+    exec(self.build_graph(), &mut PrinterContext::default(), vec![Format::Png.into(), CommandArg::Output(output_filename)]).map(|_| ()).map_err(|error| format!("Graphviz failed: {}", error))
+  }
+
+  // uses graphviz when it is installed and the built in layout otherwise
+  fn svg(&self, output_filename: String) -> Result<(), String> {
+    if !graphviz_installed() {
+      let svg = render_svg(&PlainGraph::from_dot(&self.build_graph()))?;
+      return fs::write(output_filename, svg).map_err(|error| error.to_string());
+    }
+    exec(self.build_graph(), &mut PrinterContext::default(), vec![Format::Svg.into(), CommandArg::Output(output_filename)]).map(|_| ()).map_err(|error| format!("Graphviz failed: {}", error))
+  }
+
+  // writes the graph source itself which doesn't need graphviz to be installed
+  fn dot(&self, output_filename: String) -> Result<(), String> {
+    fs::write(output_filename, print(self.build_graph(), &mut PrinterContext::default())).map_err(|error| error.to_string())
+  }
+
+  // a tree drawn with box drawing characters, or plain ascii, for the terminal
+  fn text(&self, unicode: bool) -> String {
+    render_text(&PlainGraph::from_dot(&self.build_graph()), unicode)
+  }
+}
+
+pub fn graphviz_installed() -> bool {
+  which::which("dot").is_ok()
+}
+
+// dot only needs quotes and backslashes escaped inside of a quoted id
+pub fn quoted_id(text: &str) -> Id {
+  Id::Escaped(format!("\"{}\"", escape(text)))
+}
+
+pub fn escape(text: &str) -> String {
+  text.replace('\\', "\\\\").replace('"', "\\\"")
+}
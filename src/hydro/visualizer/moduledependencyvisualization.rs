@@ -1,11 +1,7 @@
 use crate::hydro::compilationunit::CompilationUnit;
+use crate::hydro::visualizer::{quoted_id, GraphVisualization};
 use graphviz_rust::dot_generator::*;
 use graphviz_rust::dot_structures::*;
-use graphviz_rust::{
-  cmd::{CommandArg, Format},
-  exec,
-  printer::PrinterContext,
-};
 use std::collections::HashMap;
 
 pub struct ModuleDependencyVisualization {
@@ -22,14 +18,29 @@ impl ModuleDependencyVisualization {
     visualization
   }
 
-  pub fn png(&self, output_filename: String) {
-    exec(self.build_graph(), &mut PrinterContext::default(), vec![Format::Png.into(), CommandArg::Output(output_filename)]).unwrap();
-  }
+  fn generate_internal(&mut self, compilation_unit: &CompilationUnit, module_name: &String) {
+    if self.seen_modules.contains_key(module_name) {
+      return;
+    }
+    // module names have dots in them so the id needs to be quoted
+    let module_node = Node::new(NodeId(quoted_id(module_name.as_str()), None), vec![]);
+    let module_id = module_node.id.clone();
+    self.nodes.push(module_node);
+    self.seen_modules.insert(module_name.clone(), module_id.clone());
+    self.connections.insert(module_name.clone(), Vec::new());
 
-  pub fn svg(&self, output_filename: String) {
-    exec(self.build_graph(), &mut PrinterContext::default(), vec![Format::Svg.into(), CommandArg::Output(output_filename)]).unwrap();
+    let this_module = compilation_unit.get_module(module_name).unwrap();
+    for dependency in &this_module.modules {
+      self.generate_internal(compilation_unit, dependency);
+      match self.connections.get_mut(module_name.as_str()) {
+        Some(connect) => connect.push(self.seen_modules.get(dependency.as_str()).unwrap().clone()),
+        None => panic!("should've happened :("),
+      }
+    }
   }
+}
 
+impl GraphVisualization for ModuleDependencyVisualization {
   fn build_graph(&self) -> Graph {
     let mut graph = graph!(strict di id!("mod_viz_graph"));
 
@@ -50,24 +61,4 @@ impl ModuleDependencyVisualization {
 
     graph
   }
-
-  fn generate_internal(&mut self, compilation_unit: &CompilationUnit, module_name: &String) {
-    if self.seen_modules.contains_key(module_name) {
-      return;
-    }
-    let module_node = node!(module_name.as_str());
-    let module_id = module_node.id.clone();
-    self.nodes.push(module_node);
-    self.seen_modules.insert(module_name.clone(), module_id.clone());
-    self.connections.insert(module_name.clone(), Vec::new());
-
-    let this_module = compilation_unit.get_module(module_name).unwrap();
-    for dependency in &this_module.modules {
-      self.generate_internal(compilation_unit, dependency);
-      match self.connections.get_mut(module_name.as_str()) {
-        Some(connect) => connect.push(self.seen_modules.get(dependency.as_str()).unwrap().clone()),
-        None => panic!("should've happened :("),
-      }
-    }
-  }
 }
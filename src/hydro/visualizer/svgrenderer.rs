@@ -0,0 +1,122 @@
+use crate::hydro::visualizer::graphlayout::PlainGraph;
+
+// A layered layout for graphs that are too small to need graphviz. Rows go top to bottom with edges pointing down;
+// edges that close a cycle curve back up along the right side of the nodes. Text is monospace so node sizes can be
+// worked out without measuring fonts
+
+// graphs with more nodes than this get too tangled without graphviz's edge routing
+pub const BUILTIN_SVG_NODE_LIMIT: usize = 100;
+
+const FONT_SIZE: f64 = 12.0;
+const CHARACTER_WIDTH: f64 = 7.2;
+const LINE_HEIGHT: f64 = 16.0;
+const PADDING: f64 = 8.0;
+const ROW_GAP: f64 = 50.0;
+const NODE_GAP: f64 = 30.0;
+const MARGIN: f64 = 20.0;
+const CYCLE_BULGE: f64 = 40.0;
+
+struct Placement {
+  x: f64,
+  y: f64,
+  width: f64,
+  height: f64,
+}
+
+pub fn render_svg(graph: &PlainGraph) -> Result<String, String> {
+  if graph.nodes.len() > BUILTIN_SVG_NODE_LIMIT {
+    return Err(format!("The graph has {} nodes which is more than the built in layout can draw ({}). Install Graphviz or use the dot, text or ascii format", graph.nodes.len(), BUILTIN_SVG_NODE_LIMIT));
+  }
+  let back_edges = graph.back_edges();
+  let layers = graph.layers(&back_edges);
+
+  let mut placements = graph
+    .nodes
+    .iter()
+    .map(|node| {
+      let longest = node.label.iter().map(|line| line.chars().count()).max().unwrap_or(0) as f64;
+      Placement { x: 0.0, y: 0.0, width: longest * CHARACTER_WIDTH + 2.0 * PADDING, height: node.label.len() as f64 * LINE_HEIGHT + 2.0 * PADDING }
+    })
+    .collect::<Vec<Placement>>();
+
+  let top = MARGIN + if graph.title.is_some() { LINE_HEIGHT * 2.0 } else { 0.0 };
+  let row_widths = layers.iter().map(|row| row.iter().map(|node| placements[*node].width).sum::<f64>() + NODE_GAP * row.len().saturating_sub(1) as f64).collect::<Vec<f64>>();
+  let widest = row_widths.iter().cloned().fold(0.0, f64::max);
+  let mut y = top;
+  for (row, nodes) in layers.iter().enumerate() {
+    // rows are centered under the widest one
+    let mut x = MARGIN + (widest - row_widths[row]) / 2.0;
+    let mut tallest: f64 = 0.0;
+    for node in nodes {
+      placements[*node].x = x;
+      placements[*node].y = y;
+      x += placements[*node].width + NODE_GAP;
+      tallest = tallest.max(placements[*node].height);
+    }
+    y += tallest + ROW_GAP;
+  }
+  let width = widest + 2.0 * MARGIN + CYCLE_BULGE;
+  let height = y - ROW_GAP + MARGIN;
+
+  let mut colors = graph.edges.iter().map(|edge| color(&edge.color)).collect::<Vec<&str>>();
+  colors.sort();
+  colors.dedup();
+
+  let mut svg = Vec::new();
+  svg.push(format!("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{:.0}\" height=\"{:.0}\" viewBox=\"0 0 {:.0} {:.0}\" font-family=\"monospace\" font-size=\"{}\">", width, height, width, height, FONT_SIZE));
+  svg.push("<defs>".to_string());
+  for edge_color in colors {
+    svg.push(format!("<marker id=\"arrow-{}\" viewBox=\"0 0 10 10\" refX=\"10\" refY=\"5\" markerWidth=\"8\" markerHeight=\"8\" orient=\"auto-start-reverse\"><path d=\"M 0 0 L 10 5 L 0 10 z\" fill=\"{}\"/></marker>", edge_color, edge_color));
+  }
+  svg.push("</defs>".to_string());
+  svg.push(format!("<rect width=\"{:.0}\" height=\"{:.0}\" fill=\"white\"/>", width, height));
+  if let Some(title) = &graph.title {
+    svg.push(format!("<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\" font-weight=\"bold\">{}</text>", width / 2.0, MARGIN + LINE_HEIGHT, escape(title)));
+  }
+
+  for (index, edge) in graph.edges.iter().enumerate() {
+    let (from, to) = (&placements[edge.from], &placements[edge.to]);
+    let edge_color = color(&edge.color);
+    let (path, label_x, label_y) = if back_edges[index] {
+      // out of the right side of the source and into the right side of the target
+      let (start_x, start_y) = (from.x + from.width, from.y + from.height / 2.0);
+      let (end_x, end_y) = (to.x + to.width, to.y + to.height / 2.0);
+      let bulge = start_x.max(end_x) + CYCLE_BULGE;
+      (format!("M {:.1} {:.1} C {:.1} {:.1}, {:.1} {:.1}, {:.1} {:.1}", start_x, start_y, bulge, start_y, bulge, end_y, end_x, end_y), bulge - CYCLE_BULGE / 4.0, (start_y + end_y) / 2.0)
+    } else {
+      let (start_x, start_y) = (from.x + from.width / 2.0, from.y + from.height);
+      let (end_x, end_y) = (to.x + to.width / 2.0, to.y);
+      (format!("M {:.1} {:.1} L {:.1} {:.1}", start_x, start_y, end_x, end_y), (start_x + end_x) / 2.0 + 4.0, (start_y + end_y) / 2.0)
+    };
+    svg.push(format!("<path d=\"{}\" fill=\"none\" stroke=\"{}\" marker-end=\"url(#arrow-{})\"/>", path, edge_color, edge_color));
+    if let Some(label) = &edge.label {
+      svg.push(format!("<text x=\"{:.1}\" y=\"{:.1}\" fill=\"{}\">{}</text>", label_x, label_y, edge_color, escape(label)));
+    }
+  }
+
+  for (node, placement) in graph.nodes.iter().zip(&placements) {
+    let node_color = color(&node.color);
+    let dash = if node.dashed { " stroke-dasharray=\"4 3\"" } else { "" };
+    let stroke_width = if node.bold { 2 } else { 1 };
+    svg.push(format!("<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" rx=\"4\" fill=\"white\" stroke=\"{}\" stroke-width=\"{}\"{}/>", placement.x, placement.y, placement.width, placement.height, node_color, stroke_width, dash));
+    // single line labels are centered, longer ones are left justified like graphviz's '\l'
+    let (anchor, text_x) = if node.label.len() == 1 { ("middle", placement.x + placement.width / 2.0) } else { ("start", placement.x + PADDING) };
+    let lines = node.label.iter().enumerate().map(|(line, text)| format!("<tspan x=\"{:.1}\" y=\"{:.1}\" xml:space=\"preserve\">{}</tspan>", text_x, placement.y + PADDING + (line as f64 + 0.75) * LINE_HEIGHT, escape(text))).collect::<Vec<String>>();
+    svg.push(format!("<text text-anchor=\"{}\" fill=\"{}\">{}</text>", anchor, node_color, lines.join("")));
+  }
+  svg.push("</svg>".to_string());
+  svg.push(String::new());
+  Ok(svg.join("\n"))
+}
+
+// graphviz color names are also svg color names. Anything else would break the markup so it is drawn black
+fn color(color: &Option<String>) -> &str {
+  match color {
+    Some(color) if color.chars().all(|character| character.is_ascii_alphanumeric()) => color.as_str(),
+    _ => "black",
+  }
+}
+
+fn escape(text: &str) -> String {
+  text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
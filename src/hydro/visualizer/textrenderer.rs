@@ -0,0 +1,92 @@
+use crate::hydro::visualizer::graphlayout::PlainGraph;
+
+// Draws a graph as a tree for the terminal. Every node is written out once where it is first reached; later edges to
+// it say 'see above' and edges that close a cycle say 'cycle'. Labels with more than one line, like the instructions
+// of a basic block, are written under the node's first line
+
+struct Glyphs {
+  branch: &'static str,
+  last_branch: &'static str,
+  line: &'static str,
+  arrow: &'static str,
+  dash: &'static str,
+  gutter: &'static str,
+}
+
+const UNICODE: Glyphs = Glyphs { branch: "├─", last_branch: "└─", line: "│   ", arrow: "▶ ", dash: "─", gutter: "│ " };
+const ASCII: Glyphs = Glyphs { branch: "|-", last_branch: "`-", line: "|   ", arrow: "> ", dash: "-", gutter: "| " };
+
+pub fn render_text(graph: &PlainGraph, unicode: bool) -> String {
+  let glyphs = if unicode { &UNICODE } else { &ASCII };
+  let back_edges = graph.back_edges();
+  let mut lines = Vec::new();
+  if let Some(title) = &graph.title {
+    lines.push(title.clone());
+  }
+  let mut shown = vec![false; graph.nodes.len()];
+  // nodes only reachable through a cycle have no root so they start their own tree
+  let mut starts = graph.roots(&back_edges);
+  starts.extend(0..graph.nodes.len());
+  for start in starts {
+    if !shown[start] {
+      write_node(graph, glyphs, &back_edges, start, String::new(), String::new(), &mut shown, &mut lines);
+    }
+  }
+  lines.push(String::new());
+  lines.join("\n")
+}
+
+fn write_node(graph: &PlainGraph, glyphs: &Glyphs, back_edges: &Vec<bool>, node: usize, first_prefix: String, prefix: String, shown: &mut Vec<bool>, lines: &mut Vec<String>) {
+  let label = &graph.nodes[node].label;
+  if shown[node] {
+    lines.push(format!("{}{} (see above)", first_prefix, label[0]));
+    return;
+  }
+  shown[node] = true;
+  lines.push(format!("{}{}", first_prefix, label[0]));
+
+  let edges = graph.outgoing(node);
+  let gutter = if edges.is_empty() { "  " } else { glyphs.gutter };
+  for line in label.iter().skip(1) {
+    lines.push(format!("{}{}{}", prefix, gutter, line).trim_end().to_string());
+  }
+  for (position, edge) in edges.iter().enumerate() {
+    let last = position + 1 == edges.len();
+    let branch = if last { glyphs.last_branch } else { glyphs.branch };
+    let edge_label = graph.edges[*edge].label.as_ref().map(|label| format!("{}{}{}", glyphs.dash, label, glyphs.dash)).unwrap_or_default();
+    let first_prefix = format!("{}{}{}{}", prefix, branch, edge_label, glyphs.arrow);
+    let target = graph.edges[*edge].to;
+    if back_edges[*edge] {
+      lines.push(format!("{}{} (cycle)", first_prefix, graph.nodes[target].label[0]));
+    } else {
+      let child_prefix = format!("{}{}", prefix, if last { "    " } else { glyphs.line });
+      write_node(graph, glyphs, back_edges, target, first_prefix, child_prefix, shown, lines);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::hydro::visualizer::graphlayout::{PlainEdge, PlainNode};
+
+  fn node(label: &[&str]) -> PlainNode {
+    PlainNode { name: label[0].to_string(), label: label.iter().map(|line| line.to_string()).collect(), color: None, dashed: false, bold: false }
+  }
+
+  fn edge(from: usize, to: usize, label: Option<&str>) -> PlainEdge {
+    PlainEdge { from, to, label: label.map(|label| label.to_string()), color: None }
+  }
+
+  #[test]
+  fn draws_graphs_as_trees() {
+    let graph = PlainGraph {
+      title: Some("main.main".to_string()),
+      nodes: vec![node(&["entry:", "   0  push u32 1"]), node(&["loop:", "   1  branch loop end"]), node(&["end:"])],
+      edges: vec![edge(0, 1, None), edge(1, 1, Some("true")), edge(1, 2, Some("false")), edge(0, 2, None)],
+    };
+    let expected = "main.main\nentry:\n│    0  push u32 1\n├─▶ loop:\n│   │    1  branch loop end\n│   ├──true─▶ loop: (cycle)\n│   └──false─▶ end:\n└─▶ end: (see above)\n";
+    assert_eq!(render_text(&graph, true), expected);
+    assert!(render_text(&graph, false).contains("|   |--true-> loop: (cycle)"));
+  }
+}
@@ -7,17 +7,30 @@ pub mod util;
 
 extern crate clap;
 
+use crate::hydro::debugadapter::DebugAdapter;
+use crate::hydro::coverage::Coverage;
 use crate::hydro::debugcontext::DebugContext;
 use crate::hydro::frontend::compiler::HydroTranslateType;
+use crate::hydro::inspector::ValueFormatter;
+use crate::hydro::recording::ExecutionRecording;
+use crate::hydro::sampler::{SampleInterval, Sampler};
+use crate::hydro::trace;
+use crate::hydro::trace::TraceWriter;
 use crate::hydro::value::Value;
 use crate::hydro::Hydro;
 use crate::ocean::Ocean;
-use util::cli_args::{Cli, Command, HydroCommand, DebugOutputMode};
+use util::cli_args::{Cli, Command, HydroCommand, DebugOutputMode, ProfileFormat};
+use util::lcov::LcovReport;
+use util::metricexport;
 use clap::Parser;
+use std::time::Duration;
 
 fn main() -> std::io::Result<()> {
   let args = Cli::parse();
-  println!("Command: {:?}", args.command);
+  // the debug adapter owns stdout so nothing can be printed before it starts
+  if !matches!(args.command, Command::Hydro { command: HydroCommand::Debug { dap: true, .. } }) {
+    println!("Command: {:?}", args.command);
+  }
 
   match args.command {
     Command::Build { tokens, ast, source_file } => {
@@ -30,11 +43,15 @@ fn main() -> std::io::Result<()> {
       //println!("{:#?}", compilation_unit);
     }
     Command::Hydro { command } => match command {
-      HydroCommand::Build { output_file, format, source_file } => {
-        let compiled_module = match Hydro::compile(source_file.as_str()) {
+      HydroCommand::Build { output_file, format, opt_level, source_file } => {
+        let mut compiled_module = match Hydro::compile(source_file.as_str()) {
           Ok(module) => module,
           Err(errors) => panic!("ERRORS\n{:#?}", errors),
         };
+        Hydro::optimize(&mut compiled_module, opt_level);
+        if !Hydro::check_bounds(&mut compiled_module) {
+          std::process::exit(1);
+        }
         Hydro::output(
           match format.as_str() {
             "binary" => HydroTranslateType::Binary,
@@ -44,33 +61,200 @@ fn main() -> std::io::Result<()> {
           output_file.clone(),
         )?;
       }
-      HydroCommand::Debug { source_file } => {
+      HydroCommand::Debug { dap: true, source_file, .. } => DebugAdapter::serve_stdio(source_file)?,
+      HydroCommand::Debug { dap: false, record_budget, script, commands, exit, no_init, history, source_file } => {
         let compilation_unit = match Hydro::compile(source_file.as_str()) {
           Ok(compilation_unit) => compilation_unit,
           Err(errors) => panic!("ERRORS\n{:#?}", errors),
         };
         let mut debug_context = DebugContext::new();
+        debug_context.recording = if record_budget == 0 { None } else { Some(ExecutionRecording::new(record_budget * 1024 * 1024)) };
+        debug_context.history_file = if history.is_empty() { None } else { Some(history) };
+        debug_context.source_file = Some(source_file.clone());
+        if !no_init {
+          match std::path::Path::new(source_file.as_str()).canonicalize() {
+            Ok(path) => debug_context.load_init_file(path.parent().unwrap())?,
+            Err(_) => {}
+          }
+        }
+        if let Some(script) = script {
+          debug_context.load_script(std::fs::read_to_string(script)?.as_str());
+        }
+        if let Some(commands) = commands {
+          debug_context.load_commands(commands.as_str());
+        }
+        debug_context.exit_after_script = exit;
 
         let return_value = compilation_unit.debug("main".to_string(), "main".to_string(), vec![Value::Unsigned32(69)], None, &mut debug_context);
 
         match return_value {
-          Ok(result) => debug_context.console(&compilation_unit, &"main".to_string(), &mut None, result).unwrap(),
-          Err(e) => e.print_stacktrace(),
+          Ok(result) => {
+            let latest = debug_context.reloaded.clone();
+            debug_context.console(latest.as_deref().unwrap_or(&compilation_unit), &"main".to_string(), &mut None, result).unwrap()
+          }
+          Err(e) => {
+            e.print_stacktrace(&compilation_unit);
+            if debug_context.exit_after_script {
+              std::process::exit(debug_context.exit_code());
+            }
+          }
+        }
+      }
+      HydroCommand::Lint { allow, effects, source_file } => {
+        let compilation_unit = match Hydro::compile(source_file.as_str()) {
+          Ok(compilation_unit) => compilation_unit,
+          Err(errors) => panic!("ERRORS\n{:#?}", errors),
+        };
+        if Hydro::lint(&compilation_unit, source_file.as_str(), allow, effects) {
+          std::process::exit(1);
         }
       }
-      HydroCommand::Run { source_file } => {
+      HydroCommand::Profile { format, output_file, stack, time_scale, sample_every, sample_interval, source_file } => {
         let compilation_unit = match Hydro::compile(source_file.as_str()) {
           Ok(compilation_unit) => compilation_unit,
           Err(errors) => panic!("ERRORS\n{:#?}", errors),
         };
-        let return_value = compilation_unit.execute("main".to_string(), "main".to_string(), vec![Value::Unsigned32(69)], None);
+        let mut debug_context = DebugContext::new();
+        debug_context.headless = true;
+        debug_context.recording = None;
+        debug_context.sampler = match (sample_every, sample_interval) {
+          (Some(instructions), _) => Some(Sampler::new(SampleInterval::Instructions(instructions))),
+          (None, Some(micros)) => Some(Sampler::new(SampleInterval::Time(Duration::from_micros(micros)))),
+          (None, None) => None,
+        };
 
+        let return_value = compilation_unit.debug("main".to_string(), "main".to_string(), vec![Value::Unsigned32(69)], None, &mut debug_context);
+        debug_context.metric_tracker.stop_all();
         match return_value {
           Ok(result) => match result {
             Some(value) => println!("{}", value.to_string()),
             None => println!("None"),
           },
-          Err(e) => e.print_stacktrace(),
+          Err(e) => e.print_stacktrace(&compilation_unit),
+        }
+
+        if let Some(sampler) = debug_context.sampler.take() {
+          for line in sampler.summary(&compilation_unit, 10) {
+            println!("{}", line);
+          }
+          sampler.export_metrics(&mut debug_context.metric_tracker);
+        }
+
+        let profile = metricexport::export(&debug_context.metric_tracker, format, &stack, time_scale, source_file.as_str());
+        let output_file = output_file.unwrap_or(
+          match format {
+            ProfileFormat::Folded => "profile.folded",
+            ProfileFormat::Chrome => "profile.trace.json",
+            ProfileFormat::Speedscope => "profile.speedscope.json",
+            ProfileFormat::Csv => "profile.csv",
+          }
+          .to_string(),
+        );
+        std::fs::write(output_file.clone(), profile)?;
+        println!("Wrote profile to '{}'", output_file);
+      }
+      HydroCommand::Run { opt_level, trace, trace_stack, trace_module, trace_function, coverage, coverage_append, source_file } => {
+        let mut compilation_unit = match Hydro::compile(source_file.as_str()) {
+          Ok(compilation_unit) => compilation_unit,
+          Err(errors) => panic!("ERRORS\n{:#?}", errors),
+        };
+        Hydro::optimize(&mut compilation_unit, opt_level);
+        if !Hydro::check_bounds(&mut compilation_unit) {
+          std::process::exit(1);
+        }
+        // tracing and coverage need the debugger's hook on every instruction so the program runs headless under it
+        let mut debug_context = DebugContext::new();
+        debug_context.headless = true;
+        debug_context.recording = None;
+        let return_value = if trace.is_some() || coverage.is_some() {
+          if let Some(trace_file) = &trace {
+            debug_context.trace = Some(TraceWriter::create(trace_file.as_str(), trace_stack, trace_module, trace_function)?);
+          }
+          if coverage.is_some() {
+            debug_context.coverage = Some(Coverage::new());
+          }
+          compilation_unit.debug("main".to_string(), "main".to_string(), vec![Value::Unsigned32(69)], None, &mut debug_context)
+        } else {
+          compilation_unit.execute("main".to_string(), "main".to_string(), vec![Value::Unsigned32(69)], None)
+        };
+        // a trace that couldn't be written stops the run from succeeding but the coverage is still saved
+        let trace_result = debug_context.trace.as_mut().map(|trace_writer| trace_writer.finish());
+
+        match return_value {
+          Ok(result) => match result {
+            Some(value) => println!("{}", ValueFormatter::new(None).format(&value)),
+            None => println!("None"),
+          },
+          Err(e) => e.print_stacktrace(&compilation_unit),
+        }
+        let trace_failed = match (trace, trace_result) {
+          (Some(trace_file), Some(Err(error))) => {
+            eprintln!("Could not write the trace to '{}': {}", trace_file, error);
+            true
+          }
+          (Some(trace_file), _) => {
+            println!("Wrote trace to '{}'", trace_file);
+            false
+          }
+          (None, _) => false,
+        };
+        if let (Some(coverage_file), Some(run_coverage)) = (coverage, &debug_context.coverage) {
+          for line in run_coverage.summary(&compilation_unit) {
+            println!("{}", line);
+          }
+          let mut report = run_coverage.to_lcov(&compilation_unit);
+          if coverage_append && std::path::Path::new(coverage_file.as_str()).exists() {
+            match LcovReport::parse(std::fs::read_to_string(coverage_file.as_str())?.as_str()) {
+              Ok(mut existing) => {
+                existing.merge(&report);
+                report = existing;
+              }
+              Err(error) => {
+                eprintln!("Could not read the coverage in '{}': {}", coverage_file, error);
+                std::process::exit(1);
+              }
+            }
+          }
+          std::fs::write(coverage_file.as_str(), report.to_string())?;
+          println!("Wrote coverage to '{}'", coverage_file);
+        }
+        if trace_failed {
+          std::process::exit(1);
+        }
+      }
+      HydroCommand::CoverageMerge { output_file, input_files } => {
+        let mut report = LcovReport::new();
+        for input_file in &input_files {
+          match LcovReport::parse(std::fs::read_to_string(input_file.as_str())?.as_str()) {
+            Ok(input) => report.merge(&input),
+            Err(error) => {
+              eprintln!("Could not read the coverage in '{}': {}", input_file, error);
+              std::process::exit(1);
+            }
+          }
+        }
+        for line in report.summary() {
+          println!("{}", line);
+        }
+        std::fs::write(output_file.as_str(), report.to_string())?;
+        println!("Wrote merged coverage to '{}'", output_file);
+      }
+      HydroCommand::TraceDiff { context, left, right } => {
+        let (left_trace, right_trace) = match (trace::read_trace(left.as_str()), trace::read_trace(right.as_str())) {
+          (Ok(left_trace), Ok(right_trace)) => (left_trace, right_trace),
+          (Err(error), _) | (_, Err(error)) => {
+            eprintln!("{}", error);
+            std::process::exit(2);
+          }
+        };
+        match trace::diverge(&left_trace, &right_trace) {
+          Some(divergence) => {
+            for line in trace::report(&left_trace, &right_trace, &divergence, context) {
+              println!("{}", line);
+            }
+            std::process::exit(1);
+          }
+          None => println!("The traces match ({} records)", left_trace.len()),
         }
       }
     }
@@ -12,7 +12,7 @@ use crate::util::token::Token;
 use crate::util::errors::Error;
 
 use token::tokens::Tokens;
-use ast::AstNodes;
+use ast::astnodes::AstNodes;
 
 #[derive(Debug, Clone)]
 pub struct CompilationUnit {
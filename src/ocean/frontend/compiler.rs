@@ -10,7 +10,7 @@ use std::cell::RefCell;
 use std::os::unix::fs::MetadataExt;
 use std::rc::Rc;
 use std::time::Instant;
-use crate::ocean::frontend::compilationunit::ast::AstNodes;
+use crate::ocean::frontend::compilationunit::ast::astnodes::AstNodes;
 use crate::ocean::frontend::compilationunit::CompilationUnit;
 use crate::ocean::frontend::compilationunit::token::tokens::Tokens;
 use crate::util::errors::{Error, Severity};
@@ -1,3 +1,7 @@
+use crate::hydro::linter::Lint;
+use crate::hydro::trace::DEFAULT_TRACE_STACK;
+use crate::util::debug_args::TimeScale;
+use clap::builder::PossibleValuesParser;
 use clap::{Args, Parser, Subcommand, ValueEnum};
 
 #[derive(Parser, Debug)]
@@ -15,6 +19,15 @@ pub enum DebugOutputMode
   None
 }
 
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum ProfileFormat
+{
+  Folded,
+  Chrome,
+  Speedscope,
+  Csv
+}
+
 #[derive(Debug, Subcommand)]
 pub enum Command {
   Build {
@@ -46,15 +59,104 @@ pub enum HydroCommand {
     output_file: String,
     #[arg(short, long, default_value="binary", value_parser(["binary", "source"]))]
     format: String,
+    #[arg(long, default_value_t=0, value_parser=clap::value_parser!(u8).range(0..=3))]
+    opt_level: u8,
     #[arg(default_value="main.h2o")]
     source_file: String,
   },
   Debug {
+    // speak the debug adapter protocol over stdin and stdout instead of opening the console
+    #[arg(long)]
+    dap: bool,
+    // how many MiB of execution history to keep for stepping backwards. 0 turns recording off
+    #[arg(long, default_value_t=64)]
+    record_budget: usize,
+    // a file of debugger commands to run, one per line
+    #[arg(long)]
+    script: Option<String>,
+    // debugger commands to run separated by ';'. They run after the script. A '\;' stays in the command, like between
+    // the instructions of an eval
+    #[arg(long)]
+    commands: Option<String>,
+    // exit once every scripted command has run. The exit code is 1 if any of them failed
+    #[arg(long)]
+    exit: bool,
+    // skip the .hydrodebug file in the project directory
+    #[arg(long)]
+    no_init: bool,
+    // where the console history is kept. An empty name keeps no history
+    #[arg(long, default_value="history.txt")]
+    history: String,
+    #[arg(default_value="main.h2o")]
+    source_file: String,
+  },
+  Lint {
+    #[arg(short='A', long, value_parser = PossibleValuesParser::new(Lint::all().iter().map(Lint::name)))]
+    allow: Vec<String>,
+    // also print what each function in the linted modules can do
+    #[arg(long)]
+    effects: bool,
+    #[arg(default_value="main.h2o")]
+    source_file: String,
+  },
+  // runs the program to completion with metrics on and exports them
+  Profile {
+    #[arg(short, long, value_enum, default_value_t=ProfileFormat::Folded)]
+    format: ProfileFormat,
+    // defaults to profile.folded, profile.trace.json, profile.speedscope.json or profile.csv
+    #[arg(short, long)]
+    output_file: Option<String>,
+    // only keep metrics under this call stack, e.g. main.main,main.fibonacci
+    #[arg(long, value_delimiter=',')]
+    stack: Vec<String>,
+    #[arg(long, value_enum, default_value_t=TimeScale::Micro)]
+    time_scale: TimeScale,
+    // sample the call stack every this many instructions instead of timing every call
+    #[arg(long, conflicts_with="sample_interval", value_parser=clap::value_parser!(u64).range(1..))]
+    sample_every: Option<u64>,
+    // sample the call stack every this many microseconds instead of timing every call
+    #[arg(long, value_parser=clap::value_parser!(u64).range(1..))]
+    sample_interval: Option<u64>,
     #[arg(default_value="main.h2o")]
     source_file: String,
   },
   Run {
+    #[arg(long, default_value_t=0, value_parser=clap::value_parser!(u8).range(0..=3))]
+    opt_level: u8,
+    // write a jsonl record of every instruction that runs to this file
+    #[arg(long)]
+    trace: Option<String>,
+    // how many values from the top of the stack each trace record keeps
+    #[arg(long, default_value_t=DEFAULT_TRACE_STACK)]
+    trace_stack: usize,
+    // only trace these modules
+    #[arg(long, value_delimiter=',')]
+    trace_module: Vec<String>,
+    // only trace these functions, e.g. fibonacci or main.fibonacci
+    #[arg(long, value_delimiter=',')]
+    trace_function: Vec<String>,
+    // write an lcov report of which instructions and branch directions ran to this file
+    #[arg(long)]
+    coverage: Option<String>,
+    // add this run to the coverage already in the file instead of replacing it
+    #[arg(long, requires="coverage")]
+    coverage_append: bool,
     #[arg(default_value="main.h2o")]
     source_file: String,
   },
+  // adds lcov reports from several runs together
+  CoverageMerge {
+    #[arg(short, long, default_value="coverage.info")]
+    output_file: String,
+    #[arg(required=true)]
+    input_files: Vec<String>,
+  },
+  // compares two traces from 'run --trace' and shows where they first do something different
+  TraceDiff {
+    // how many records to show around the divergence
+    #[arg(long, default_value_t=3)]
+    context: usize,
+    left: String,
+    right: String,
+  },
 }
\ No newline at end of file
@@ -15,6 +15,14 @@ pub struct PositionArg {
   pub function: String,
 }
 
+// Either '<file>:<line>' or '<module> <function> <pc or label>'
+#[derive(Args, Clone, Debug)]
+pub struct BreakpointArg {
+  pub target: String,
+  pub function: Option<String>,
+  pub position: Option<String>,
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
 pub enum TimeScale {
   Sec,
@@ -26,6 +34,9 @@ pub enum TimeScale {
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
 pub enum Visualization {
   ModDep,
+  // the basic blocks of a single function
+  Cfg,
+  CallGraph,
 }
 
 #[derive(Debug, Subcommand)]
@@ -34,17 +45,75 @@ pub enum DebugCommand {
   //Version,
   Breakpoint {
     #[command(flatten)]
-    location: PositionArg,
-    program_counter: usize,
+    location: BreakpointArg,
+    // only stop when this holds, e.g. '--condition count > 3' or '--condition top == true'
+    #[arg(long, num_args = 1.., allow_negative_numbers = true)]
+    condition: Vec<String>,
+    // skip this many hits before stopping
+    #[arg(long, default_value_t=0)]
+    ignore: usize,
+    // print this message instead of stopping. '{name}' is replaced with the value of name
+    #[arg(long, num_args = 1..)]
+    log: Vec<String>,
+  },
+  Breakpoints,
+  // call a function with literal arguments given as type and value pairs, e.g. 'call main square u32 3'
+  Call {
+    module: String,
+    function: String,
+    #[arg(allow_negative_numbers=true)]
+    arguments: Vec<String>,
   },
   CallGraph {
     #[arg(value_enum, default_value_t=TimeScale::Micro)]
     time_scale: TimeScale,
   },
   Continue,
+  Delete {
+    id: usize,
+  },
+  Disable {
+    id: usize,
+  },
+  // what the functions in a module can do, defaults to every function in the current module
+  Effects {
+    #[arg(long)]
+    module: Option<String>,
+    #[arg(long)]
+    function: Option<String>,
+  },
+  Enable {
+    id: usize,
+  },
+  // run instructions against a copy of the current context and show the stack they leave behind
+  Eval {
+    #[arg(required=true, trailing_var_arg=true, allow_hyphen_values=true)]
+    instructions: Vec<String>,
+  },
   Exit,
-  HotPath,
+  // run until the current function returns and show what it returned
+  Finish,
+  // the hottest blocks and loops followed by the hottest function with execution counts in the margin
+  HotPath {
+    #[arg(long, default_value_t = 10)]
+    count: usize,
+  },
+  // a tree of a variable, stack slot, array element or layout member. Array elements are paged with offset and count
+  Inspect {
+    target: String,
+    #[arg(long, default_value_t=0)]
+    offset: usize,
+    #[arg(long, default_value_t=16)]
+    count: usize,
+    // how many levels of nested arrays and layouts to expand
+    #[arg(long, default_value_t=2)]
+    depth: usize,
+  },
   Instruction,
+  // when the value of a variable, array element or layout member last changed in the function being looked at
+  LastChange {
+    target: String,
+  },
   Metric {
     #[command(flatten)]
     location: PositionArg,
@@ -54,29 +123,75 @@ pub enum DebugCommand {
     #[arg(value_enum, default_value_t=TimeScale::Micro)]
     time_scale: TimeScale,
   },
+  // step over calls
+  Next {
+    #[arg(default_value_t=1)]
+    count: usize,
+  },
   Pop,
   Push {
     value_type: String,
     value: String,
   },
+  // the budget is in MiB
+  Record {
+    #[arg(long)]
+    budget: Option<usize>,
+    #[arg(long)]
+    off: bool,
+  },
+  // back to the last breakpoint that was hit
+  ReverseContinue,
   Run,
+  // assign a variable, array element or layout member, e.g. 'set numbers[2] u32 7'
+  Set {
+    target: String,
+    value_type: String,
+    #[arg(allow_negative_numbers=true)]
+    value: String,
+  },
   Stack {
     size: usize,
   },
   Stacktrace,
+  // step into calls
   Step { 
     #[arg(default_value_t=1)]
     count: usize,
   },
+  StepBack {
+    #[arg(default_value_t=1)]
+    count: usize,
+  },
+  // run until a label or pc of the current function, or until it returns
+  Until {
+    position: String,
+  },
   Variable {
     name: String,
   },
   Variables,
+  // stop when a variable, array element or layout member changes, e.g. 'numbers[2]' or 'point.x'
+  Watch {
+    target: String,
+  },
+  // compiles the program again and swaps the changed modules in. Running frames finish with their old code
+  Reload {
+    // swap the new code in even if live values use a layout that changed shape
+    #[arg(long)]
+    force: bool,
+  },
   Viz {
     #[arg(value_enum)]
     visualization: Visualization,
-    #[arg(value_parser(["png", "svg"]))]
+    // svg falls back to a built in layout without graphviz. text and ascii are printed when there is no output file
+    #[arg(value_parser(["png", "svg", "dot", "text", "ascii"]))]
     format: String,
-    output_file: String,
+    output_file: Option<String>,
+    // the function for a cfg, defaults to the current function
+    #[arg(long)]
+    module: Option<String>,
+    #[arg(long)]
+    function: Option<String>,
   },
 }
\ No newline at end of file
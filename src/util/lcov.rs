@@ -0,0 +1,177 @@
+use crate::hydro::profiler::share;
+use std::collections::BTreeMap;
+
+// The lcov tracefile format that genhtml, codecov and most editors read. Reports are kept by source file so reports from
+// different runs can be read back in and added together. The summary counts (FNF, LH, ...) are always recomputed when
+// writing so they are ignored when reading
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LcovFile {
+  // function name to the line it starts on and how many times it was called
+  pub functions: BTreeMap<String, (usize, u64)>,
+  // (line, block, branch) to how many times the branch was taken. None when the branch was never reached
+  pub branches: BTreeMap<(usize, usize, usize), Option<u64>>,
+  // line to how many times it ran
+  pub lines: BTreeMap<usize, u64>,
+}
+
+impl LcovFile {
+  pub fn merge(&mut self, other: &LcovFile) {
+    for (name, (line, count)) in &other.functions {
+      let function = self.functions.entry(name.clone()).or_insert((*line, 0));
+      function.1 += count;
+    }
+    for (branch, taken) in &other.branches {
+      let existing = self.branches.entry(*branch).or_insert(None);
+      *existing = match (*existing, taken) {
+        (None, None) => None,
+        (existing, taken) => Some(existing.unwrap_or(0) + taken.unwrap_or(0)),
+      };
+    }
+    for (line, count) in &other.lines {
+      *self.lines.entry(*line).or_insert(0) += count;
+    }
+  }
+
+  pub fn functions_hit(&self) -> usize {
+    self.functions.values().filter(|(_, count)| *count > 0).count()
+  }
+
+  pub fn branches_hit(&self) -> usize {
+    self.branches.values().filter(|taken| taken.unwrap_or(0) > 0).count()
+  }
+
+  pub fn lines_hit(&self) -> usize {
+    self.lines.values().filter(|count| **count > 0).count()
+  }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LcovReport {
+  pub files: BTreeMap<String, LcovFile>,
+}
+
+impl LcovReport {
+  pub fn new() -> Self {
+    Self { files: BTreeMap::new() }
+  }
+
+  pub fn parse(text: &str) -> Result<Self, String> {
+    let mut report = LcovReport::new();
+    let mut current: Option<(String, LcovFile)> = None;
+    for (index, line) in text.lines().enumerate() {
+      let line = line.trim();
+      let error = |message: &str| format!("line {}: {}", index + 1, message);
+      let (key, value) = line.split_once(':').unwrap_or((line, ""));
+      let fields = value.split(',').collect::<Vec<&str>>();
+      let number = |field: &str| field.trim().parse::<u64>().map_err(|_| error(format!("'{}' is not a number", field).as_str()));
+      if key == "SF" {
+        current = Some((value.to_string(), LcovFile::default()));
+        continue;
+      }
+      if key == "end_of_record" {
+        // the same file can show up in more than one record
+        if let Some((file, coverage)) = current.take() {
+          report.files.entry(file).or_default().merge(&coverage);
+        }
+        continue;
+      }
+      let file = match current.as_mut() {
+        Some((_, file)) => file,
+        None => continue,
+      };
+      match (key, fields.as_slice()) {
+        // lcov 2 writes the line a function ends on as well
+        ("FN", [line, name]) | ("FN", [line, _, name]) => {
+          let function = file.functions.entry(name.to_string()).or_insert((0, 0));
+          function.0 = number(line)? as usize;
+        }
+        ("FNDA", [count, name]) => file.functions.entry(name.to_string()).or_insert((0, 0)).1 += number(count)?,
+        ("BRDA", [line, block, branch, taken]) => {
+          let taken = if *taken == "-" { None } else { Some(number(taken)?) };
+          file.branches.insert((number(line)? as usize, number(block)? as usize, number(branch)? as usize), taken);
+        }
+        ("DA", [line, count, ..]) => {
+          file.lines.insert(number(line)? as usize, number(count)?);
+        }
+        ("FN" | "FNDA" | "BRDA" | "DA", _) => return Err(error(format!("malformed {} record", key).as_str())),
+        _ => {}
+      }
+    }
+    if current.is_some() {
+      return Err("The last record is missing end_of_record".to_string());
+    }
+    Ok(report)
+  }
+
+  pub fn merge(&mut self, other: &LcovReport) {
+    for (file, coverage) in &other.files {
+      self.files.entry(file.clone()).or_default().merge(coverage);
+    }
+  }
+
+  pub fn to_string(&self) -> String {
+    let mut output = String::new();
+    for (file, coverage) in &self.files {
+      output += format!("TN:\nSF:{}\n", file).as_str();
+      for (name, (line, _)) in &coverage.functions {
+        output += format!("FN:{},{}\n", line, name).as_str();
+      }
+      for (name, (_, count)) in &coverage.functions {
+        output += format!("FNDA:{},{}\n", count, name).as_str();
+      }
+      output += format!("FNF:{}\nFNH:{}\n", coverage.functions.len(), coverage.functions_hit()).as_str();
+      for ((line, block, branch), taken) in &coverage.branches {
+        output += format!("BRDA:{},{},{},{}\n", line, block, branch, taken.map(|taken| taken.to_string()).unwrap_or("-".to_string())).as_str();
+      }
+      output += format!("BRF:{}\nBRH:{}\n", coverage.branches.len(), coverage.branches_hit()).as_str();
+      for (line, count) in &coverage.lines {
+        output += format!("DA:{},{}\n", line, count).as_str();
+      }
+      output += format!("LF:{}\nLH:{}\nend_of_record\n", coverage.lines.len(), coverage.lines_hit()).as_str();
+    }
+    output
+  }
+
+  // one line per source file with how much of its lines, functions and branches ran
+  pub fn summary(&self) -> Vec<String> {
+    let mut lines = vec![format!("{:<50} {:>16} {:>16} {:>16}", "file", "lines", "functions", "branches")];
+    for (file, coverage) in &self.files {
+      lines.push(format!(
+        "{:<50} {:>16} {:>16} {:>16}",
+        file,
+        ratio(coverage.lines_hit(), coverage.lines.len()),
+        ratio(coverage.functions_hit(), coverage.functions.len()),
+        ratio(coverage.branches_hit(), coverage.branches.len())
+      ));
+    }
+    lines
+  }
+}
+
+// "3/4 (75.0%)"
+pub fn ratio(hit: usize, total: usize) -> String {
+  format!("{}/{} ({:.1}%)", hit, total, share(hit as f64, total as f64))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn reports_merge_and_round_trip() {
+    let first = "TN:\nSF:main.h2o\nFN:3,main.square\nFNDA:0,main.square\nBRDA:5,4,0,-\nBRDA:5,4,1,-\nDA:4,1\nDA:5,0\nend_of_record\n";
+    let second = "SF:main.h2o\nFN:3,7,main.square\nFNDA:2,main.square\nBRDA:5,4,0,2\nBRDA:5,4,1,0\nDA:4,2\nDA:5,2\nDA:6,1\nend_of_record\n";
+    let mut report = LcovReport::parse(first).unwrap();
+    report.merge(&LcovReport::parse(second).unwrap());
+
+    let file = &report.files["main.h2o"];
+    assert_eq!(file.functions["main.square"], (3, 2));
+    assert_eq!(file.branches[&(5, 4, 0)], Some(2));
+    assert_eq!(file.branches[&(5, 4, 1)], Some(0));
+    assert_eq!(file.lines, BTreeMap::from([(4, 3), (5, 2), (6, 1)]));
+    assert_eq!((file.branches_hit(), file.lines_hit()), (1, 3));
+    assert_eq!(LcovReport::parse(report.to_string().as_str()), Ok(report.clone()));
+    assert!(LcovReport::parse("SF:main.h2o\nDA:x,1\nend_of_record\n").is_err());
+  }
+}
@@ -0,0 +1,186 @@
+use crate::util::cli_args::ProfileFormat;
+use crate::util::debug_args::TimeScale;
+use crate::util::metrictracker::{MetricEvent, MetricResults, MetricTracker};
+use serde_json::{json, Value as JsonValue};
+use std::collections::HashMap;
+use std::time::Duration;
+
+// Turns the metrics of a finished run into files other tools can read. Folded stacks and speedscope only look at the
+// "total" metric of each function since the other metrics overlap the calls they measure. The chrome trace keeps every
+// metric as its own event
+
+pub fn export(metric_tracker: &MetricTracker, format: ProfileFormat, stack_filter: &Vec<String>, time_scale: TimeScale, name: &str) -> String {
+  let events = metric_tracker.get_events().into_iter().filter(|event| event.stack.starts_with(stack_filter)).collect::<Vec<MetricEvent>>();
+  match format {
+    ProfileFormat::Folded => folded(&events, time_scale),
+    ProfileFormat::Chrome => chrome_trace(&events, time_scale),
+    ProfileFormat::Speedscope => speedscope(&events, time_scale, name),
+    ProfileFormat::Csv => {
+      let results = metric_tracker.get_results().into_iter().filter(|result| result.stack.starts_with(stack_filter)).collect::<Vec<MetricResults>>();
+      csv(&results, time_scale)
+    }
+  }
+}
+
+fn convert(duration: Duration, time_scale: TimeScale) -> f64 {
+  match time_scale {
+    TimeScale::Sec => duration.as_secs_f64(),
+    TimeScale::Milli => duration.as_secs_f64() * 1_000.0,
+    TimeScale::Micro => duration.as_secs_f64() * 1_000_000.0,
+    TimeScale::Nano => duration.as_nanos() as f64,
+  }
+}
+
+fn unit_name(time_scale: TimeScale) -> &'static str {
+  match time_scale {
+    TimeScale::Sec => "seconds",
+    TimeScale::Milli => "milliseconds",
+    TimeScale::Micro => "microseconds",
+    TimeScale::Nano => "nanoseconds",
+  }
+}
+
+// the time spent in each call stack without the time spent in the functions it called, sorted by stack
+fn self_times(events: &Vec<MetricEvent>) -> Vec<(Vec<String>, Duration)> {
+  let mut inclusive = HashMap::new();
+  for event in events.iter().filter(|event| event.metric_name == "total") {
+    *inclusive.entry(event.stack.clone()).or_insert(Duration::ZERO) += event.duration;
+  }
+  let mut exclusive = inclusive.clone();
+  for (stack, duration) in inclusive.iter().filter(|(stack, _)| !stack.is_empty()) {
+    if let Some(parent) = exclusive.get_mut(&stack[..stack.len() - 1]) {
+      *parent = parent.saturating_sub(*duration);
+    }
+  }
+  let mut self_times = exclusive.into_iter().collect::<Vec<(Vec<String>, Duration)>>();
+  self_times.sort();
+  self_times
+}
+
+// one line per call stack, "main.main;main.fibonacci 1234", the input format of flamegraph.pl and inferno
+fn folded(events: &Vec<MetricEvent>, time_scale: TimeScale) -> String {
+  let mut output = String::new();
+  for (stack, duration) in self_times(events) {
+    let weight = convert(duration, time_scale).round() as u128;
+    if weight > 0 {
+      output += format!("{} {}\n", stack.join(";"), weight).as_str();
+    }
+  }
+  output
+}
+
+// complete events for chrome://tracing and perfetto. Trace event timestamps are always in microseconds so the time
+// scale only picks the unit the viewer displays
+fn chrome_trace(events: &Vec<MetricEvent>, time_scale: TimeScale) -> String {
+  let origin = events.iter().map(|event| event.start).min();
+  let trace_events = events
+    .iter()
+    .map(|event| {
+      let name = if event.metric_name == "total" { event.stack.last().cloned().unwrap_or_default() } else { event.metric_name.clone() };
+      json!({
+        "name": name,
+        "cat": event.metric_name,
+        "ph": "X",
+        "ts": convert(event.start.duration_since(origin.unwrap()), TimeScale::Micro),
+        "dur": convert(event.duration, TimeScale::Micro),
+        "pid": 1,
+        "tid": 1,
+        "args": { "stack": event.stack.join(";") },
+      })
+    })
+    .collect::<Vec<JsonValue>>();
+  let display_time_unit = match time_scale {
+    TimeScale::Nano => "ns",
+    _ => "ms",
+  };
+  format!("{}\n", json!({ "traceEvents": trace_events, "displayTimeUnit": display_time_unit }))
+}
+
+// a sampled profile where each call stack is one sample weighted by its self time
+fn speedscope(events: &Vec<MetricEvent>, time_scale: TimeScale, name: &str) -> String {
+  let mut frames = Vec::new();
+  let mut frame_ids = HashMap::new();
+  let mut samples = Vec::new();
+  let mut weights = Vec::new();
+  for (stack, duration) in self_times(events) {
+    let mut sample = Vec::new();
+    for frame in stack {
+      let id = *frame_ids.entry(frame.clone()).or_insert_with(|| {
+        frames.push(json!({ "name": frame }));
+        frames.len() - 1
+      });
+      sample.push(id);
+    }
+    samples.push(sample);
+    weights.push(convert(duration, time_scale));
+  }
+  let end_value = weights.iter().sum::<f64>();
+  let document = json!({
+    "$schema": "https://www.speedscope.app/file-format-schema.json",
+    "name": name,
+    "exporter": "ocean",
+    "activeProfileIndex": 0,
+    "shared": { "frames": frames },
+    "profiles": [{
+      "type": "sampled",
+      "name": name,
+      "unit": unit_name(time_scale),
+      "startValue": 0,
+      "endValue": end_value,
+      "samples": samples,
+      "weights": weights,
+    }],
+  });
+  format!("{}\n", document)
+}
+
+fn csv_field(field: String) -> String {
+  if field.contains(',') || field.contains('"') || field.contains('\n') {
+    format!("\"{}\"", field.replace('"', "\"\""))
+  } else {
+    field
+  }
+}
+
+fn csv(results: &Vec<MetricResults>, time_scale: TimeScale) -> String {
+  let mut output = format!("stack,metric,count,total,min,quartile1,median,quartile3,max,mean,standard_deviation,unit\n");
+  let mut results = results.iter().collect::<Vec<&MetricResults>>();
+  results.sort_by(|a, b| (&a.stack, &a.name).cmp(&(&b.stack, &b.name)));
+  for result in results {
+    let times = [result.total_time, result.min, result.quartile1, result.median, result.quartile3, result.max, result.mean, result.standard_deviation].iter().map(|time| format!("{:.3}", convert(*time, time_scale))).collect::<Vec<String>>();
+    output += format!("{},{},{},{},{}\n", csv_field(result.stack.join(";")), csv_field(result.name.clone()), result.total_count, times.join(","), unit_name(time_scale)).as_str();
+  }
+  output
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::time::Instant;
+
+  fn event(stack: &[&str], metric_name: &str, start: Instant, micros: u64) -> MetricEvent {
+    MetricEvent::new(stack.iter().map(|frame| frame.to_string()).collect(), metric_name.to_string(), start, Duration::from_micros(micros))
+  }
+
+  #[test]
+  fn folded_stacks_use_self_time() {
+    let start = Instant::now();
+    let events = vec![
+      event(&["main.main"], "total", start, 100),
+      event(&["main.main"], "call", start, 70),
+      event(&["main.main", "main.fib"], "total", start, 30),
+      event(&["main.main", "main.fib"], "total", start, 30),
+      event(&["main.main", "main.fib", "main.fib"], "total", start, 10),
+    ];
+    assert_eq!(folded(&events, TimeScale::Micro), "main.main 40\nmain.main;main.fib 50\nmain.main;main.fib;main.fib 10\n");
+
+    let document = serde_json::from_str::<JsonValue>(speedscope(&events, TimeScale::Micro, "test").as_str()).unwrap();
+    assert_eq!(document["shared"]["frames"], json!([{ "name": "main.main" }, { "name": "main.fib" }]));
+    assert_eq!(document["profiles"][0]["samples"], json!([[0], [0, 1], [0, 1, 1]]));
+    assert_eq!(document["profiles"][0]["endValue"], json!(100.0));
+
+    let trace = serde_json::from_str::<JsonValue>(chrome_trace(&events, TimeScale::Micro).as_str()).unwrap();
+    assert_eq!(trace["traceEvents"].as_array().unwrap().len(), 5);
+    assert_eq!(trace["traceEvents"][2]["name"], json!("main.fib"));
+  }
+}
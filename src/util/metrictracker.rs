@@ -96,6 +96,14 @@ impl MetricTracker {
     }
   }
 
+  // a metric that was timed somewhere else, like the stretches a sampled profile saw a function on the stack for
+  pub fn record(&mut self, stack: Vec<String>, metric_name: String, start: Instant, duration: Duration) {
+    let mut metric = Metric::new();
+    metric.start_time = Some(start);
+    metric.durations.push(duration);
+    self.finished_metrics.entry((stack, metric_name)).or_default().push(metric);
+  }
+
   pub fn get_result(&self, stack: Vec<String>, metric_name: String) -> Option<MetricResults> {
     self.finished_metrics.get(&(stack.clone(), metric_name.clone())).and_then(|metric_list| Some(MetricResults::new(stack, metric_name, metric_list.clone())))
   }
@@ -109,6 +117,16 @@ impl MetricTracker {
     results
   }
 
+  // every finished metric in the order they were started
+  pub fn get_events(&self) -> Vec<MetricEvent> {
+    self
+      .finished_metrics
+      .iter()
+      .flat_map(|((stack, metric_name), metrics)| metrics.iter().filter_map(|metric| metric.start_time.map(|start| MetricEvent::new(stack.clone(), metric_name.clone(), start, metric.duration()))))
+      .sorted_by(|a, b| a.start.cmp(&b.start).then(a.stack.len().cmp(&b.stack.len())))
+      .collect()
+  }
+
   pub fn get_flamegraph(&self, stack: Vec<String>) -> Option<Flamegraph> {
     let all_metrics = self
       .finished_metrics
@@ -164,6 +182,12 @@ impl MetricTracker {
       }
     }
 
+    // calls that are still on the stack when the metrics end, like the last ones a sampled profile saw
+    while flamegraph_stack.len() > 1 {
+      let (_, flamegraph) = flamegraph_stack.pop().unwrap();
+      flamegraph_stack.last_mut().unwrap().1.subgraph.push(flamegraph);
+    }
+
     if flamegraph_stack.len() != 1 {
       panic!("Something went wrong generating the the flamegraph :(");
     }
@@ -215,6 +239,14 @@ impl Flamegraph {
   }
 }
 
+#[derive(Clone, Debug, New)]
+pub struct MetricEvent {
+  pub stack: Vec<String>,
+  pub metric_name: String,
+  pub start: Instant,
+  pub duration: Duration,
+}
+
 pub struct MetricResults {
   pub stack: Vec<String>,
   pub name: String,
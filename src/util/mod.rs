@@ -2,6 +2,8 @@ pub mod cli_args;
 pub mod debug_args;
 pub mod dependencygraph;
 pub mod errors;
+pub mod lcov;
+pub mod metricexport;
 pub mod metrictracker;
 pub mod span;
 pub mod token;